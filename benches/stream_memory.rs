@@ -0,0 +1,127 @@
+//! Peak memory of `--stream` against fetching the whole response
+//!
+//! Writes a synthetic e4sig response of a large dataset to a temporary file,
+//! then computes its MACS twice, once by deserializing the full response and
+//! once by streaming the points into a `StreamingMacs`, recording the peak
+//! heap use of each with a counting allocator. Run with
+//! `cargo bench --bench stream_memory [-- <points>]`.
+
+use macs_rs::exfor_client::{self, CrossSectionResponse};
+use macs_rs::macs::StreamingMacs;
+use macs_rs::{IntegrationMethod, LinLin, MaxwellianWeighting, calculate_macs};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Points of the synthetic dataset unless given on the command line
+const DEFAULT_POINTS: usize = 2_000_000;
+
+/// Forwards to the system allocator, tracking current and peak heap use
+struct CountingAllocator;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            let current = CURRENT.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(current, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Runs `f`, returning its result and the heap it used at most beyond what
+/// was already allocated
+fn peak_during<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let baseline = CURRENT.load(Ordering::Relaxed);
+    PEAK.store(baseline, Ordering::Relaxed);
+    let value = f();
+    (value, PEAK.load(Ordering::Relaxed) - baseline)
+}
+
+/// Writes a 1/v cross section of `points` log-spaced energies from 10 μeV to
+/// 20 MeV as an e4sig response
+fn write_response(path: &Path, points: usize) -> std::io::Result<()> {
+    let mut out = BufWriter::new(std::fs::File::create(path)?);
+    write!(
+        out,
+        r#"{{"format":"json","program":"e4sig","datasets":[{{"id":"1","TEMP":0.0,"COLUMNS":["E(eV)","Sig(b)"],"pts":["#
+    )?;
+    let (low, high) = (1e-5f64.ln(), 2e7f64.ln());
+    for i in 0..points {
+        let energy = (low + (high - low) * i as f64 / (points - 1) as f64).exp();
+        let separator = if i == 0 { "" } else { "," };
+        write!(
+            out,
+            r#"{}{{"E":{},"Sig":{}}}"#,
+            separator,
+            energy,
+            (0.0253 / energy).sqrt()
+        )?;
+    }
+    writeln!(out, "]}}]}}")?;
+    out.flush()
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let points = match std::env::args().nth(1).filter(|arg| arg != "--bench") {
+        Some(arg) => arg.parse()?,
+        None => DEFAULT_POINTS,
+    };
+    let path = std::env::temp_dir().join(format!("macs-rs-stream-{}.json", std::process::id()));
+    write_response(&path, points)?;
+    let size = std::fs::metadata(&path)?.len();
+    let temperatures = [30.0];
+
+    let (full, full_peak) = peak_during(|| -> Result<f64, Box<dyn std::error::Error>> {
+        let file = BufReader::new(std::fs::File::open(&path)?);
+        let response: CrossSectionResponse = serde_json::from_reader(file)?;
+        let (energies, cross_sections) = response.datasets[0].points_in_mev_barns()?;
+        Ok(calculate_macs(
+            &energies,
+            &cross_sections,
+            94.0,
+            temperatures[0],
+            MaxwellianWeighting::Flux,
+            IntegrationMethod::Trapezoid,
+            &LinLin,
+        )?)
+    });
+    let (streamed, stream_peak) = peak_during(|| -> Result<f64, Box<dyn std::error::Error>> {
+        let file = BufReader::new(std::fs::File::open(&path)?);
+        let mut accumulator = StreamingMacs::new(94.0, &temperatures, MaxwellianWeighting::Flux)?;
+        exfor_client::stream_points(file, &mut accumulator)?;
+        Ok(accumulator.finish()?[0])
+    });
+    std::fs::remove_file(&path)?;
+
+    let kib = |bytes: usize| bytes as f64 / 1024.0;
+    println!(
+        "{} points, {:.1} MiB of JSON",
+        points,
+        size as f64 / (1024.0 * 1024.0)
+    );
+    println!(
+        "full response: MACS {:.6} mb, peak heap {:10.1} KiB",
+        full?,
+        kib(full_peak)
+    );
+    println!(
+        "streamed:      MACS {:.6} mb, peak heap {:10.1} KiB",
+        streamed?,
+        kib(stream_peak)
+    );
+    Ok(())
+}
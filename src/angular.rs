@@ -0,0 +1,207 @@
+//! Angle integration of differential cross sections
+//!
+//! Some reactions are only tabulated as angular distributions dσ/dΩ(E, θ)
+//! (EXFOR quantity `DA`). Integrating each distribution over the full solid
+//! angle,
+//!
+//! σ(E) = 2π ∫₋₁¹ dσ/dΩ(E, μ) dμ with μ = cos θ,
+//!
+//! recovers an energy-dependent cross section for the MACS. The integral uses
+//! the trapezoidal rule over the tabulated angles, and a distribution that
+//! does not reach μ = ±1 is held constant out to the ends. Both make the result
+//! an approximation, so it is flagged as such.
+
+use crate::exfor_client::{CrossSectionDataset, CrossSectionPoint, CrossSectionResponse};
+use std::f64::consts::PI;
+
+/// Column names holding the scattering angle as a cosine
+const COSINE_COLUMNS: [&str; 3] = ["Cos", "cos", "MU"];
+
+/// Column names holding the scattering angle in degrees
+const DEGREE_COLUMNS: [&str; 3] = ["Ang", "ANG", "angle"];
+
+/// How the angle of a point is read
+enum AngleColumn<'a> {
+    Cosine(&'a str),
+    Degrees(&'a str),
+}
+
+impl AngleColumn<'_> {
+    /// Finds the angle column among the dataset's declared columns
+    fn find(columns: &[String]) -> Option<AngleColumn<'_>> {
+        let base = |c: &String| crate::units::split_column_header(c).0.to_string();
+        columns
+            .iter()
+            .find(|c| COSINE_COLUMNS.contains(&base(c).as_str()))
+            .map(|c| AngleColumn::Cosine(c))
+            .or_else(|| {
+                columns
+                    .iter()
+                    .find(|c| DEGREE_COLUMNS.contains(&base(c).as_str()))
+                    .map(|c| AngleColumn::Degrees(c))
+            })
+    }
+
+    /// Cosine of the scattering angle of a point
+    fn cosine(&self, point: &CrossSectionPoint) -> Option<f64> {
+        match self {
+            AngleColumn::Cosine(name) => point.column(name),
+            AngleColumn::Degrees(name) => point.column(name).map(|d| d.to_radians().cos()),
+        }
+    }
+}
+
+/// Integrates one angular distribution over the full solid angle
+///
+/// `distribution` holds (μ, dσ/dΩ) pairs; at least two distinct angles are needed.
+fn integrate_distribution(mut distribution: Vec<(f64, f64)>) -> Option<f64> {
+    distribution.sort_by(|a, b| a.0.total_cmp(&b.0));
+    distribution.dedup_by(|a, b| a.0 == b.0);
+    let (&(mu_first, first), &(mu_last, last)) = (distribution.first()?, distribution.last()?);
+    if distribution.len() < 2 {
+        return None;
+    }
+    let inner: f64 = distribution
+        .windows(2)
+        .map(|w| 0.5 * (w[0].1 + w[1].1) * (w[1].0 - w[0].0))
+        .sum();
+    let ends = first * (mu_first + 1.0) + last * (1.0 - mu_last);
+    Some(2.0 * PI * (inner + ends))
+}
+
+/// Angle-integrates a dataset of angular distributions
+///
+/// Points are grouped by energy, each group being one distribution with its
+/// angle in a cosine (`Cos`, `MU`) or degree (`Ang`) column. Energies with
+/// fewer than two angles are skipped.
+///
+/// # Returns
+/// * `Ok(dataset)` - The σ(E) dataset, with the metadata of the input
+/// * `Err(msg)` - If there is no angle column or no energy has two angles
+pub fn angle_integrated(dataset: &CrossSectionDataset) -> Result<CrossSectionDataset, String> {
+    let angle = AngleColumn::find(&dataset.columns).ok_or_else(|| {
+        format!(
+            "Dataset {} has no angle column (Cos or Ang), it cannot be angle-integrated",
+            dataset.id
+        )
+    })?;
+    let mut groups: Vec<(f64, Vec<(f64, f64)>)> = Vec::new();
+    for point in &dataset.points {
+        let Some(mu) = angle.cosine(point) else {
+            continue;
+        };
+        match groups.last_mut() {
+            Some((energy, distribution)) if *energy == point.energy => {
+                distribution.push((mu, point.cross_section))
+            }
+            _ => groups.push((point.energy, vec![(mu, point.cross_section)])),
+        }
+    }
+    groups.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let points: Vec<CrossSectionPoint> = groups
+        .into_iter()
+        .filter_map(|(energy, distribution)| {
+            Some(CrossSectionPoint {
+                energy,
+                cross_section: integrate_distribution(distribution)?,
+                uncertainty: None,
+                extra: Default::default(),
+            })
+        })
+        .collect();
+    if points.is_empty() {
+        return Err(format!(
+            "Dataset {} has no energy with two or more angles to integrate",
+            dataset.id
+        ));
+    }
+
+    let energy_column = dataset
+        .columns
+        .iter()
+        .find(|c| crate::units::split_column_header(c).0 == "E")
+        .cloned()
+        .unwrap_or_else(|| "E".to_string());
+    Ok(CrossSectionDataset {
+        columns: vec![energy_column, "Sig".to_string()],
+        n_pts: points.len() as u32,
+        points,
+        ..dataset.clone()
+    })
+}
+
+/// Angle-integrates every dataset of a response and marks it as approximate
+pub fn angle_integrated_response(
+    response: &CrossSectionResponse,
+) -> Result<CrossSectionResponse, String> {
+    Ok(CrossSectionResponse {
+        datasets: response
+            .datasets
+            .iter()
+            .map(angle_integrated)
+            .collect::<Result<_, _>>()?,
+        angle_integrated: true,
+        ..response.clone()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dataset(columns: &str, points: &str) -> CrossSectionDataset {
+        let mut response: CrossSectionResponse = serde_json::from_str(&format!(
+            r#"{{"datasets": [{{"id": "1", "COLUMNS": {}, "pts": [{}]}}]}}"#,
+            columns, points
+        ))
+        .unwrap();
+        response.datasets.remove(0)
+    }
+
+    #[test]
+    fn distribution_is_integrated_over_the_solid_angle() {
+        // dσ/dΩ = 1 + μ integrates to 4π, whatever the angle unit
+        let cosines = dataset(
+            r#"["E(eV)", "Cos", "Sig"]"#,
+            r#"{"E": 10, "Cos": -1, "Sig": 0}, {"E": 10, "Cos": 0, "Sig": 1},
+               {"E": 10, "Cos": 1, "Sig": 2}"#,
+        );
+        let degrees = dataset(
+            r#"["E(eV)", "Ang(deg)", "Sig"]"#,
+            r#"{"E": 10, "Ang": 180, "Sig": 0}, {"E": 10, "Ang": 90, "Sig": 1},
+               {"E": 10, "Ang": 0, "Sig": 2}"#,
+        );
+        for dataset in [cosines, degrees] {
+            let integrated = angle_integrated(&dataset).unwrap();
+            assert_eq!(integrated.columns, vec!["E(eV)", "Sig"]);
+            assert_eq!(integrated.points.len(), 1);
+            assert!((integrated.points[0].cross_section - 4.0 * PI).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn distribution_is_held_constant_beyond_its_angles() {
+        assert_eq!(
+            integrate_distribution(vec![(0.5, 1.0), (-0.5, 1.0)]),
+            Some(4.0 * PI)
+        );
+        assert_eq!(integrate_distribution(vec![(0.5, 1.0), (0.5, 2.0)]), None);
+    }
+
+    #[test]
+    fn energies_need_two_angles_and_an_angle_column() {
+        let integrated = angle_integrated(&dataset(
+            r#"["E", "MU", "Sig"]"#,
+            r#"{"E": 2, "MU": 0, "Sig": 1}, {"E": 1, "MU": -1, "Sig": 1},
+               {"E": 1, "MU": 1, "Sig": 1}"#,
+        ))
+        .unwrap();
+        let energies: Vec<f64> = integrated.points.iter().map(|p| p.energy).collect();
+        assert_eq!(energies, vec![1.0]);
+
+        let err =
+            angle_integrated(&dataset(r#"["E", "Sig"]"#, r#"{"E": 1, "Sig": 1}"#)).unwrap_err();
+        assert!(err.contains("no angle column"), "{}", err);
+    }
+}
@@ -0,0 +1,325 @@
+//! Maxwellian averages for charged-particle induced reactions
+//!
+//! For a charged projectile the Coulomb barrier suppresses the cross section
+//! at low energy, so the integrand σ(E)·E·exp(-E/kT) peaks in the Gamow window
+//! well above kT instead of at the thermal peak. The average itself is the
+//! same ⟨σv⟩/v_T as for neutrons, with the projectile's reduced mass:
+//!
+//! MACS = (2*a²/(√π * (kT)²)) * ∫ σ(E) * E * exp(-a*E/(kT)) dE, a = A/(A+m)
+//!
+//! where m is the projectile mass in atomic mass units. What changes is how
+//! σ(E) is evaluated between grid points: it varies exponentially through the
+//! Coulomb penetrability, so it is interpolated via the astrophysical S-factor
+//! S(E) = σ(E) * E * exp(2πη(E)), which varies slowly, and the integral is
+//! always computed with the adaptive rule.
+
+use crate::interpolation::{Interpolation, LinLin};
+use crate::macs::{self, IntegrationMethod, MaxwellianWeighting};
+use crate::units;
+
+/// 2πη = SOMMERFELD_KEV * Z1 * Z2 * √(μ / E), with μ in amu and E in keV
+const SOMMERFELD_KEV: f64 = 31.29;
+
+/// Incident particle of a reaction
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Projectile {
+    /// Symbol used in reaction strings (e.g., "p" in "p,g")
+    pub symbol: &'static str,
+    /// Charge number
+    pub charge: u32,
+    /// Mass in atomic mass units
+    pub mass: f64,
+}
+
+/// Projectiles recognized in reaction strings
+const PROJECTILES: [Projectile; 6] = [
+    Projectile {
+        symbol: "n",
+        charge: 0,
+        mass: macs::NEUTRON_MASS,
+    },
+    Projectile {
+        symbol: "p",
+        charge: 1,
+        mass: 1.00728,
+    },
+    Projectile {
+        symbol: "d",
+        charge: 1,
+        mass: 2.01355,
+    },
+    Projectile {
+        symbol: "t",
+        charge: 1,
+        mass: 3.01550,
+    },
+    Projectile {
+        symbol: "h",
+        charge: 2,
+        mass: 3.01493,
+    },
+    Projectile {
+        symbol: "a",
+        charge: 2,
+        mass: 4.00151,
+    },
+];
+
+impl Projectile {
+    /// Reads the projectile from a reaction string such as "n,g" or "a,g"
+    ///
+    /// Returns `None` if the projectile is not one of [`PROJECTILES`] (e.g.
+    /// a photon), in which case the neutron formula applies as before.
+    pub fn from_reaction(reaction: &str) -> Option<Self> {
+        let symbol = reaction.split(',').next()?.trim();
+        PROJECTILES
+            .iter()
+            .find(|p| p.symbol.eq_ignore_ascii_case(symbol))
+            .copied()
+    }
+
+    /// Whether the projectile is charged, i.e. needs the Coulomb treatment
+    pub fn is_charged(&self) -> bool {
+        self.charge > 0
+    }
+}
+
+/// Coulomb interaction between a charged projectile and a target nucleus
+#[derive(Debug, Clone, Copy)]
+pub struct CoulombSystem {
+    /// Projectile charge times target charge, Z1*Z2
+    charge_product: f64,
+    /// Reduced mass μ in amu
+    reduced_mass: f64,
+    /// Lab to center-of-mass energy factor a = A/(A+m)
+    cm_factor: f64,
+}
+
+impl CoulombSystem {
+    /// Builds the system for a projectile hitting a target
+    ///
+    /// # Arguments
+    /// * `projectile` - Incident particle
+    /// * `target_z` - Atomic number of the target
+    /// * `atomic_mass` - Atomic mass of the target in amu
+    pub fn new(projectile: Projectile, target_z: u32, atomic_mass: f64) -> Self {
+        let cm_factor = macs::reduced_mass_factor(atomic_mass, projectile.mass);
+        CoulombSystem {
+            charge_product: f64::from(projectile.charge * target_z),
+            reduced_mass: projectile.mass * cm_factor,
+            cm_factor,
+        }
+    }
+
+    /// Gamow exponent 2πη at a center-of-mass energy in keV
+    fn gamow_exponent(&self, energy_cm_kev: f64) -> f64 {
+        SOMMERFELD_KEV * self.charge_product * (self.reduced_mass / energy_cm_kev).sqrt()
+    }
+
+    /// Gamow window at a temperature
+    ///
+    /// The peak is E0 = (b*kT/2)^(2/3) with b = 2πη√E, and the 1/e width of the
+    /// Gaussian approximation is Δ = 4/√3 * √(E0*kT).
+    ///
+    /// # Arguments
+    /// * `temperature_kev` - Temperature (kT) in keV
+    ///
+    /// # Returns
+    /// `(peak, width)` of the window, center-of-mass energies in keV
+    pub fn gamow_window(&self, temperature_kev: f64) -> (f64, f64) {
+        let b = self.gamow_exponent(1.0);
+        let peak = (b * temperature_kev / 2.0).powf(2.0 / 3.0);
+        let width = 4.0 / 3f64.sqrt() * (peak * temperature_kev).sqrt();
+        (peak, width)
+    }
+}
+
+/// Interpolates a cross section through the astrophysical S-factor
+///
+/// The S-factor S(E) = σ(E) * E * exp(2πη(E)) is interpolated linearly in
+/// energy between two points, and converted back to σ at the requested
+/// energy. The exponentials are combined as differences of Gamow exponents
+/// so they do not overflow deep below the barrier.
+struct SFactorInterpolation {
+    system: CoulombSystem,
+}
+
+impl Interpolation for SFactorInterpolation {
+    fn interpolate(&self, x: f64, x1: f64, y1: f64, x2: f64, y2: f64) -> f64 {
+        // No barrier penetrability is defined at zero energy
+        if x1 <= 0.0 || x2 == x1 {
+            return LinLin.interpolate(x, x1, y1, x2, y2);
+        }
+        // Lab MeV to center-of-mass keV
+        let to_cm_kev = |e: f64| units::mev_to_kev(e * self.system.cm_factor);
+        let g = self.system.gamow_exponent(to_cm_kev(x));
+        let g1 = self.system.gamow_exponent(to_cm_kev(x1));
+        let g2 = self.system.gamow_exponent(to_cm_kev(x2));
+        let t = (x - x1) / (x2 - x1);
+        y1 * (x1 / x) * (1.0 - t) * (g1 - g).exp() + y2 * (x2 / x) * t * (g2 - g).exp()
+    }
+}
+
+/// Calculates the Maxwellian-averaged cross section of a charged-particle reaction
+///
+/// # Arguments
+/// * `energies` - Projectile energy points in MeV, laboratory frame
+/// * `cross_sections` - Cross section values in barns
+/// * `projectile` - Incident particle
+/// * `target_z` - Atomic number of the target
+/// * `atomic_mass` - Atomic mass of the target (e.g., 94 for Mo-94)
+/// * `temperature_kev` - Temperature in keV
+///
+/// # Returns
+/// * `Ok(macs)` - MACS value in millibarns
+/// * `Err(msg)` - Error message if inputs are invalid
+///
+/// # Example
+/// ```no_run
+/// # use macs_rs::charged::{Projectile, calculate_charged_macs};
+/// # fn example(energies: &[f64], cross_sections: &[f64]) -> Result<(), String> {
+/// let proton = Projectile::from_reaction("p,g").unwrap();
+/// let macs = calculate_charged_macs(energies, cross_sections, proton, 42, 94.0, 30.0)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn calculate_charged_macs(
+    energies: &[f64],
+    cross_sections: &[f64],
+    projectile: Projectile,
+    target_z: u32,
+    atomic_mass: f64,
+    temperature_kev: f64,
+) -> Result<f64, String> {
+    let law = SFactorInterpolation {
+        system: CoulombSystem::new(projectile, target_z, atomic_mass),
+    };
+    macs::calculate_macs_for_projectile(
+        energies,
+        cross_sections,
+        atomic_mass,
+        projectile.mass,
+        temperature_kev,
+        MaxwellianWeighting::Flux,
+        IntegrationMethod::Adaptive,
+        &law,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpolation::LogLog;
+
+    #[test]
+    fn projectile_is_read_from_the_reaction() {
+        let neutron = Projectile::from_reaction("n,g").unwrap();
+        assert_eq!(
+            (neutron.mass, neutron.is_charged()),
+            (macs::NEUTRON_MASS, false)
+        );
+        let alpha = Projectile::from_reaction(" A ,n").unwrap();
+        assert_eq!((alpha.symbol, alpha.charge), ("a", 2));
+        assert_eq!(Projectile::from_reaction("g,n"), None);
+    }
+
+    #[test]
+    fn reduced_mass_factor_uses_the_projectile_mass() {
+        assert_eq!(macs::reduced_mass_factor(94.0, 1.0), 94.0 / 95.0);
+        let alpha = Projectile::from_reaction("a,g").unwrap();
+        assert_eq!(macs::reduced_mass_factor(94.0, alpha.mass), 94.0 / 98.00151);
+    }
+
+    /// MACS of a 1/v cross section of 1 b at 25.3 meV for a projectile mass
+    fn one_over_v_macs(projectile_mass: f64) -> f64 {
+        let energies: Vec<f64> = (0..=4000)
+            .map(|i| 10f64.powf(-12.0 + 13.0 * i as f64 / 4000.0))
+            .collect();
+        let cross_sections: Vec<f64> = energies.iter().map(|e| (2.53e-8 / e).sqrt()).collect();
+        macs::calculate_macs_for_projectile(
+            &energies,
+            &cross_sections,
+            94.0,
+            projectile_mass,
+            30.0,
+            MaxwellianWeighting::Flux,
+            IntegrationMethod::Trapezoid,
+            &LogLog,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn one_over_v_macs_is_taken_at_the_lab_energy_of_kt() {
+        // ⟨σv⟩ is constant for 1/v, so the MACS is σ at the laboratory
+        // energy kT/a, which grows with the projectile mass
+        for projectile in ["n,g", "a,g"] {
+            let mass = Projectile::from_reaction(projectile).unwrap().mass;
+            let lab_energy = 0.03 / macs::reduced_mass_factor(94.0, mass);
+            let expected = 1e3 * (2.53e-8 / lab_energy).sqrt();
+            let macs = one_over_v_macs(mass);
+            assert!(
+                (macs / expected - 1.0).abs() < 1e-3,
+                "{}: {} mb, expected {} mb",
+                projectile,
+                macs,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn neutron_projectile_reduces_to_the_neutron_formula() {
+        let energies = [1e-3, 1e-2, 0.1, 1.0];
+        let cross_sections = [4.0, 2.0, 1.0, 0.5];
+        let neutron = macs::calculate_macs(
+            &energies,
+            &cross_sections,
+            94.0,
+            30.0,
+            MaxwellianWeighting::Flux,
+            IntegrationMethod::Trapezoid,
+            &LinLin,
+        )
+        .unwrap();
+        let projectile = macs::calculate_macs_for_projectile(
+            &energies,
+            &cross_sections,
+            94.0,
+            Projectile::from_reaction("n,g").unwrap().mass,
+            30.0,
+            MaxwellianWeighting::Flux,
+            IntegrationMethod::Trapezoid,
+            &LinLin,
+        )
+        .unwrap();
+        assert_eq!(neutron, projectile);
+    }
+
+    #[test]
+    fn gamow_window_of_the_pp_chain() {
+        // p + p in the Sun (15 MK): E₀ = 5.9 keV, Δ = 6.4 keV (Rolfs & Rodney)
+        let proton = Projectile::from_reaction("p,g").unwrap();
+        let system = CoulombSystem::new(proton, 1, proton.mass);
+        let (peak, width) = system.gamow_window(units::kelvin_to_kev(15e6));
+        assert!((peak / 5.9 - 1.0).abs() < 0.01, "{}", peak);
+        assert!((width / 6.4 - 1.0).abs() < 0.01, "{}", width);
+    }
+
+    #[test]
+    fn gamow_window_matches_the_closed_form() {
+        // ¹²C(α,γ) in helium burning (T9 = 0.2), with the usual
+        // E₀ = 0.1220·(Z₁²Z₂²μT9²)^(1/3) MeV and Δ = 0.2368·(Z₁²Z₂²μT9⁵)^(1/6) MeV
+        let alpha = Projectile::from_reaction("a,g").unwrap();
+        let system = CoulombSystem::new(alpha, 6, 12.0);
+        let (peak, width) = system.gamow_window(units::gk_to_kev(0.2));
+        let mu = alpha.mass * 12.0 / (alpha.mass + 12.0);
+        let z2 = 4.0 * 36.0;
+        let expected_peak = 122.0 * (z2 * mu * 0.2f64.powi(2)).cbrt();
+        let expected_width = 236.8 * (z2 * mu * 0.2f64.powi(5)).powf(1.0 / 6.0);
+        assert!((peak / expected_peak - 1.0).abs() < 2e-3, "{}", peak);
+        assert!((width / expected_width - 1.0).abs() < 2e-3, "{}", width);
+        // The famous ~300 keV, far above kT = 17 keV
+        assert!((peak - 300.0).abs() < 20.0);
+    }
+}
@@ -0,0 +1,90 @@
+//! Reading of two-column numeric text files
+//!
+//! User-supplied tabulations such as spectra or measured cross sections are
+//! plain text files with one (energy, value) pair per line.
+
+use std::path::Path;
+
+/// Reads the rows of a numeric file with a fixed number of columns
+///
+/// The first column must be strictly increasing. `expected` describes the
+/// columns in error messages (e.g. "two columns (energy, flux)").
+fn read_rows(
+    path: &Path,
+    kind: &str,
+    width: usize,
+    expected: &str,
+) -> Result<Vec<Vec<f64>>, Box<dyn std::error::Error>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read {} {}: {}", kind, path.display(), e))?;
+
+    let mut rows: Vec<Vec<f64>> = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<f64> = line
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|field| !field.is_empty())
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("{}:{}: {}", path.display(), number + 1, e))?;
+        if fields.len() != width {
+            return Err(format!("{}:{}: expected {}", path.display(), number + 1, expected).into());
+        }
+        if rows.last().is_some_and(|last| fields[0] <= last[0]) {
+            return Err(format!(
+                "{}:{}: energies must be strictly increasing",
+                path.display(),
+                number + 1
+            )
+            .into());
+        }
+        rows.push(fields);
+    }
+
+    if rows.len() < 2 {
+        return Err(format!("The {} {} has fewer than two points", kind, path.display()).into());
+    }
+    Ok(rows)
+}
+
+/// Reads a two-column file of strictly increasing energies
+///
+/// Each non-empty line holds an energy in MeV and a value, separated by
+/// whitespace or a comma. Lines starting with `#` are comments.
+///
+/// # Arguments
+/// * `path` - File to read
+/// * `kind` - What the file holds, used in error messages (e.g., "spectrum")
+/// * `value_name` - Name of the second column (e.g., "flux")
+///
+/// # Returns
+/// * `Ok((energies, values))` - At least two points
+/// * `Err` - If the file cannot be read or is malformed
+pub fn read_two_columns(
+    path: &Path,
+    kind: &str,
+    value_name: &str,
+) -> Result<(Vec<f64>, Vec<f64>), Box<dyn std::error::Error>> {
+    let rows = read_rows(
+        path,
+        kind,
+        2,
+        &format!("two columns (energy, {})", value_name),
+    )?;
+    Ok(rows.into_iter().map(|row| (row[0], row[1])).unzip())
+}
+
+/// Reads a one-column file of strictly increasing values
+///
+/// Same layout as [`read_two_columns`] with a single number per line.
+///
+/// # Returns
+/// * `Ok(values)` - At least two values
+/// * `Err` - If the file cannot be read or is malformed
+pub fn read_one_column(path: &Path, kind: &str) -> Result<Vec<f64>, Box<dyn std::error::Error>> {
+    let rows = read_rows(path, kind, 1, "one value per line")?;
+    Ok(rows.into_iter().map(|row| row[0]).collect())
+}
@@ -0,0 +1,156 @@
+//! Comparison of two saved MACS runs
+//!
+//! Reads result files written with `--output-format json` and reports the
+//! per-temperature differences between them.
+
+use crate::macs::{MacsResult, MacsTable};
+
+/// Temperatures closer than this (in keV) are considered the same row
+const TEMPERATURE_MATCH_KEV: f64 = 1e-9;
+
+/// Difference between two runs at a single temperature
+#[derive(Debug)]
+pub struct MacsDifference {
+    /// Temperature in keV
+    pub temperature_kev: f64,
+    /// MACS of the baseline run in millibarns, if present
+    pub baseline_mb: Option<f64>,
+    /// MACS of the candidate run in millibarns, if present
+    pub candidate_mb: Option<f64>,
+}
+
+impl MacsDifference {
+    /// Relative difference (candidate - baseline) / baseline
+    ///
+    /// Returns `None` if the temperature is missing from either run.
+    pub fn relative_difference(&self) -> Option<f64> {
+        let (baseline, candidate) = (self.baseline_mb?, self.candidate_mb?);
+        if baseline == candidate {
+            return Some(0.0);
+        }
+        Some((candidate - baseline) / baseline.abs())
+    }
+
+    /// Whether this row should be flagged for the given relative tolerance
+    ///
+    /// Temperatures present in only one run are always flagged.
+    pub fn exceeds(&self, tolerance: f64) -> bool {
+        self.relative_difference()
+            .is_none_or(|diff| diff.abs() > tolerance)
+    }
+}
+
+/// Contents of a saved results file
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum SavedResults {
+    /// Table with target metadata, as written by current versions
+    Table(Box<MacsTable>),
+    /// Bare list of results, as written by older versions
+    List(Vec<MacsResult>),
+}
+
+/// Reads a results file written with `--output-format json`
+pub fn load_results(path: &std::path::Path) -> Result<Vec<MacsResult>, Box<dyn std::error::Error>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    let saved = serde_json::from_str(&content)
+        .map_err(|e| format!("Cannot parse {}: {}", path.display(), e))?;
+    Ok(match saved {
+        SavedResults::Table(table) => table.results,
+        SavedResults::List(results) => results,
+    })
+}
+
+/// Pairs up the results of two runs by temperature
+///
+/// Rows are ordered as in the baseline, followed by temperatures that only
+/// appear in the candidate.
+pub fn compare_results(baseline: &[MacsResult], candidate: &[MacsResult]) -> Vec<MacsDifference> {
+    let find = |results: &[MacsResult], temperature: f64| {
+        results
+            .iter()
+            .find(|r| (r.temperature_kev - temperature).abs() < TEMPERATURE_MATCH_KEV)
+            .map(|r| r.macs_mb)
+    };
+
+    let mut differences: Vec<MacsDifference> = baseline
+        .iter()
+        .map(|b| MacsDifference {
+            temperature_kev: b.temperature_kev,
+            baseline_mb: Some(b.macs_mb),
+            candidate_mb: find(candidate, b.temperature_kev),
+        })
+        .collect();
+
+    differences.extend(
+        candidate
+            .iter()
+            .filter(|c| find(baseline, c.temperature_kev).is_none())
+            .map(|c| MacsDifference {
+                temperature_kev: c.temperature_kev,
+                baseline_mb: None,
+                candidate_mb: Some(c.macs_mb),
+            }),
+    );
+
+    differences
+}
+
+/// Prints the comparison table of two saved runs
+///
+/// # Arguments
+/// * `baseline` - Path of the reference results file
+/// * `candidate` - Path of the results file to check
+/// * `tolerance` - Relative difference above which a row is flagged
+///
+/// # Returns
+/// * `Ok(())` - All temperatures agree within the tolerance
+/// * `Err` - Files could not be read, or at least one row was flagged
+pub fn run(
+    baseline: &std::path::Path,
+    candidate: &std::path::Path,
+    tolerance: f64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let differences = compare_results(&load_results(baseline)?, &load_results(candidate)?);
+
+    let format_mb = |value: Option<f64>| match value {
+        Some(v) => format!("{:12.6}", v),
+        None => format!("{:>12}", "-"),
+    };
+
+    println!("T(keV)    Baseline(mb)  Candidate(mb)  RelDiff");
+    println!("----------------------------------------------------");
+    let mut flagged = 0;
+    for d in &differences {
+        let relative = match d.relative_difference() {
+            Some(r) => format!("{:+.3e}", r),
+            None => "missing".to_string(),
+        };
+        let marker = if d.exceeds(tolerance) {
+            flagged += 1;
+            "  <-- exceeds tolerance"
+        } else {
+            ""
+        };
+        println!(
+            "{:6.1}    {}  {}   {}{}",
+            d.temperature_kev,
+            format_mb(d.baseline_mb),
+            format_mb(d.candidate_mb),
+            relative,
+            marker
+        );
+    }
+
+    if flagged > 0 {
+        return Err(format!(
+            "{} of {} temperatures differ by more than the tolerance {:e}",
+            flagged,
+            differences.len(),
+            tolerance
+        )
+        .into());
+    }
+    Ok(())
+}
@@ -0,0 +1,216 @@
+//! Reduction of very dense cross section grids
+//!
+//! Some evaluations tabulate hundreds of thousands of points. Downsampling
+//! keeps the points that matter most for the shape of the cross section,
+//! such as resonance peaks and valleys, so that repeated integration stays
+//! fast with a controlled loss of accuracy.
+
+use crate::interpolation::Interpolation;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Segment of the grid between two kept points, with its worst interior point
+struct Segment {
+    /// Relative deviation |interpolated - actual| / max(|interpolated|, |actual|)
+    /// of the worst interior point
+    deviation: f64,
+    /// Index of the first kept point
+    start: usize,
+    /// Index of the last kept point
+    end: usize,
+    /// Index of the worst interior point
+    worst: usize,
+}
+
+impl PartialEq for Segment {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Segment {}
+
+impl PartialOrd for Segment {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Segment {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.deviation.total_cmp(&other.deviation)
+    }
+}
+
+/// Finds the interior point of [start, end] worst represented by interpolating
+/// between the two ends, or `None` if the segment has no interior point
+fn worst_point(
+    energies: &[f64],
+    cross_sections: &[f64],
+    law: &dyn Interpolation,
+    start: usize,
+    end: usize,
+) -> Option<Segment> {
+    (start + 1..end)
+        .map(|k| {
+            let interpolated = law.interpolate(
+                energies[k],
+                energies[start],
+                cross_sections[start],
+                energies[end],
+                cross_sections[end],
+            );
+            let actual = cross_sections[k];
+            // Relative to the larger of the two, so that every point is
+            // ranked on the same scale, zero cross sections included
+            let scale = actual.abs().max(interpolated.abs());
+            let deviation = if scale > 0.0 {
+                (interpolated - actual).abs() / scale
+            } else {
+                0.0
+            };
+            Segment {
+                deviation,
+                start,
+                end,
+                worst: k,
+            }
+        })
+        .max()
+}
+
+/// Reduces a grid to at most `max_points` points
+///
+/// Works like the Douglas-Peucker line simplification run in priority order:
+/// starting from the two end points, the point deviating most (relative to
+/// the larger of its value and the interpolated one) from the current reduced
+/// grid is added until `max_points` points are kept. Peaks and valleys, which
+/// deviate the most, are therefore kept first. Grids already within the limit
+/// are returned as is.
+///
+/// # Arguments
+/// * `energies` - Energy points, increasing
+/// * `cross_sections` - Cross section values
+/// * `law` - Interpolation law used to reconstruct the dropped points
+/// * `max_points` - Maximum number of points to keep (at least 2)
+///
+/// # Returns
+/// The reduced energy and cross section vectors
+pub fn downsample(
+    energies: &[f64],
+    cross_sections: &[f64],
+    law: &dyn Interpolation,
+    max_points: usize,
+) -> (Vec<f64>, Vec<f64>) {
+    let n = energies.len().min(cross_sections.len());
+    if n <= max_points || n < 2 {
+        return (energies[..n].to_vec(), cross_sections[..n].to_vec());
+    }
+
+    let mut kept = vec![false; n];
+    kept[0] = true;
+    kept[n - 1] = true;
+    let mut kept_count = 2;
+
+    let mut segments = BinaryHeap::new();
+    segments.extend(worst_point(energies, cross_sections, law, 0, n - 1));
+    while kept_count < max_points {
+        let Some(segment) = segments.pop() else {
+            break;
+        };
+        kept[segment.worst] = true;
+        kept_count += 1;
+        segments.extend(worst_point(
+            energies,
+            cross_sections,
+            law,
+            segment.start,
+            segment.worst,
+        ));
+        segments.extend(worst_point(
+            energies,
+            cross_sections,
+            law,
+            segment.worst,
+            segment.end,
+        ));
+    }
+
+    (0..n)
+        .filter(|&i| kept[i])
+        .map(|i| (energies[i], cross_sections[i]))
+        .unzip()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpolation::LinLin;
+    use crate::macs::{self, IntegrationMethod, MaxwellianWeighting};
+
+    /// Dense log grid from 1 eV to 1 MeV of a 1/v cross section with
+    /// narrow resonances every half decade
+    fn resonances() -> (Vec<f64>, Vec<f64>) {
+        let energies: Vec<f64> = (0..20_000)
+            .map(|i| 1e-6 * 10f64.powf(6.0 * i as f64 / 19_999.0))
+            .collect();
+        let cross_sections = energies
+            .iter()
+            .map(|&e| {
+                let peaks: f64 = (0..12)
+                    .map(|k| {
+                        let center = 3e-6 * 10f64.powf(k as f64 / 2.0);
+                        let half_width = center * 2e-3;
+                        100.0 / (1.0 + ((e - center) / half_width).powi(2))
+                    })
+                    .sum();
+                (2.53e-8 / e).sqrt() + peaks
+            })
+            .collect();
+        (energies, cross_sections)
+    }
+
+    fn macs_of(energies: &[f64], cross_sections: &[f64]) -> f64 {
+        macs::calculate_macs(
+            energies,
+            cross_sections,
+            94.0,
+            30.0,
+            MaxwellianWeighting::Flux,
+            IntegrationMethod::Trapezoid,
+            &LinLin,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn downsampling_keeps_the_ends_and_the_macs() {
+        let (energies, cross_sections) = resonances();
+        let full = macs_of(&energies, &cross_sections);
+        for max_points in [2000, 5000] {
+            let (reduced_energies, reduced_cross_sections) =
+                downsample(&energies, &cross_sections, &LinLin, max_points);
+            assert!(reduced_energies.len() <= max_points);
+            assert_eq!(reduced_energies[0], energies[0]);
+            assert_eq!(reduced_energies.last(), energies.last());
+            assert!(reduced_energies.windows(2).all(|w| w[0] < w[1]));
+            let reduced = macs_of(&reduced_energies, &reduced_cross_sections);
+            assert!(
+                (reduced / full - 1.0).abs() < 1e-3,
+                "{} points: {} vs {} mb",
+                max_points,
+                reduced,
+                full
+            );
+        }
+    }
+
+    #[test]
+    fn grids_within_the_limit_are_unchanged() {
+        let (energies, cross_sections) = resonances();
+        let (kept_energies, kept_cross_sections) =
+            downsample(&energies, &cross_sections, &LinLin, energies.len());
+        assert_eq!(kept_energies, energies);
+        assert_eq!(kept_cross_sections, cross_sections);
+    }
+}
@@ -0,0 +1,134 @@
+//! Error-weighted smoothing of measured cross sections
+//!
+//! The trapezoidal rule trusts every tabulated point equally. When points
+//! carry uncertainties, a poorly measured point can instead be pulled
+//! towards what its neighbours predict. Each interior point is combined with
+//! the value interpolated from its two neighbours, both weighted by their
+//! inverse variance:
+//!
+//! σ'ᵢ = (σᵢ/δᵢ² + pᵢ/δpᵢ²) / (1/δᵢ² + 1/δpᵢ²)
+//!
+//! where pᵢ is interpolated at Eᵢ between points i-1 and i+1 with the
+//! dataset's law, and δpᵢ² = ((1-t)·δᵢ₋₁)² + (t·δᵢ₊₁)² with
+//! t = (Eᵢ - Eᵢ₋₁)/(Eᵢ₊₁ - Eᵢ₋₁) is the variance of a linear interpolation.
+//! A precise point barely moves while an uncertain one follows its
+//! neighbours. The original values are used throughout, so the result does
+//! not depend on the order in which points are visited.
+
+use crate::interpolation::Interpolation;
+
+/// Cross sections re-weighted by their uncertainties
+///
+/// The two end points, and points where the point itself or one of its
+/// neighbours has no positive uncertainty, are kept as tabulated.
+///
+/// # Arguments
+/// * `energies` - Energy points in MeV
+/// * `cross_sections` - Cross section values in barns
+/// * `uncertainties` - Absolute uncertainty of each cross section in barns
+/// * `law` - Interpolation law of the cross section
+///
+/// # Returns
+/// The weighted cross sections and the number of points that were changed
+pub fn error_weighted(
+    energies: &[f64],
+    cross_sections: &[f64],
+    uncertainties: &[Option<f64>],
+    law: &dyn Interpolation,
+) -> (Vec<f64>, usize) {
+    let mut weighted = cross_sections.to_vec();
+    let mut changed = 0;
+    let uncertainty = |i: usize| uncertainties.get(i).copied().flatten().filter(|&u| u > 0.0);
+    for i in 1..energies.len().saturating_sub(1) {
+        let (Some(own), Some(left), Some(right)) =
+            (uncertainty(i), uncertainty(i - 1), uncertainty(i + 1))
+        else {
+            continue;
+        };
+        let (e1, e2) = (energies[i - 1], energies[i + 1]);
+        let t = (energies[i] - e1) / (e2 - e1);
+        let predicted = law.interpolate(
+            energies[i],
+            e1,
+            cross_sections[i - 1],
+            e2,
+            cross_sections[i + 1],
+        );
+        let predicted_variance = ((1.0 - t) * left).powi(2) + (t * right).powi(2);
+        let (w_own, w_predicted) = (1.0 / own.powi(2), 1.0 / predicted_variance);
+        weighted[i] = (cross_sections[i] * w_own + predicted * w_predicted) / (w_own + w_predicted);
+        changed += 1;
+    }
+    (weighted, changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpolation::LinLin;
+    use crate::macs::{IntegrationMethod, MaxwellianWeighting, calculate_macs};
+
+    #[test]
+    fn uncertain_point_follows_its_neighbours() {
+        let energies = [1.0, 2.0, 3.0];
+        let (weighted, changed) = error_weighted(
+            &energies,
+            &[1.0, 5.0, 1.0],
+            &[Some(0.01), Some(10.0), Some(0.01)],
+            &LinLin,
+        );
+        assert_eq!(changed, 1);
+        assert_eq!((weighted[0], weighted[2]), (1.0, 1.0));
+        assert!((weighted[1] - 1.0).abs() < 1e-5, "{}", weighted[1]);
+
+        // The weights are the inverse variances: 1/δ² = 1 against 1/δp² = 2
+        let (weighted, _) = error_weighted(
+            &energies,
+            &[1.0, 4.0, 1.0],
+            &[Some(1.0), Some(1.0), Some(1.0)],
+            &LinLin,
+        );
+        assert!((weighted[1] - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn points_without_uncertainty_are_kept() {
+        let cross_sections = [1.0, 5.0, 1.0, 5.0];
+        let (weighted, changed) = error_weighted(
+            &[1.0, 2.0, 3.0, 4.0],
+            &cross_sections,
+            &[Some(0.1), None, Some(0.1), Some(0.0)],
+            &LinLin,
+        );
+        assert_eq!(changed, 0);
+        assert_eq!(weighted, cross_sections);
+    }
+
+    #[test]
+    fn weighting_changes_the_macs_of_an_outlier() {
+        // A flat 1 b cross section with one poorly measured spike at 30 keV
+        let energies: Vec<f64> = (1..=200).map(|i| i as f64 * 1e-3).collect();
+        let mut cross_sections = vec![1.0; energies.len()];
+        let mut uncertainties = vec![Some(0.01); energies.len()];
+        cross_sections[29] = 3.0;
+        uncertainties[29] = Some(2.0);
+
+        let macs = |cross_sections: &[f64]| {
+            calculate_macs(
+                &energies,
+                cross_sections,
+                94.0,
+                30.0,
+                MaxwellianWeighting::Flux,
+                IntegrationMethod::Trapezoid,
+                &LinLin,
+            )
+            .unwrap()
+        };
+        let (weighted, _) = error_weighted(&energies, &cross_sections, &uncertainties, &LinLin);
+        let (unweighted, flat) = (macs(&cross_sections), macs(&vec![1.0; energies.len()]));
+        let weighted = macs(&weighted);
+        assert!(weighted < unweighted);
+        assert!((weighted - flat).abs() < 0.01 * (unweighted - flat));
+    }
+}
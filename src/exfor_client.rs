@@ -1,112 +1,567 @@
-/// EXFOR API client for fetching nuclear cross section data
-///
-/// This module provides functionality to query the IAEA EXFOR database
-/// for neutron-induced cross section data from various nuclear data libraries.
+//! EXFOR API client for fetching nuclear cross section data
+//!
+//! This module provides functionality to query the IAEA EXFOR database
+//! for neutron-induced cross section data from various nuclear data libraries.
+
+use crate::macs::EnergyUnit;
+use crate::nuclide;
+use crate::units::{self, CrossSectionUnit};
+use serde::de::{DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use std::collections::BTreeSet;
+use std::io::Read;
+use std::sync::{Mutex, OnceLock};
 
 /// Internal data structure representing a section in the EXFOR database
+///
+/// Only the fields needed to pick and download a section are required: the
+/// nuclide and the library. Descriptive metadata the API sometimes omits
+/// defaults to zero or empty, and missing IDs are handled by
+/// [`find_section`].
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
 struct Section {
-    #[serde(alias = "Targ")]
+    #[serde(alias = "Targ", default)]
     pub target: String,
     #[serde(alias = "ZT")]
     pub z: u32,
     #[serde(alias = "AT")]
     pub a: u32,
-    #[serde(alias = "NSUB")]
+    #[serde(alias = "NSUB", default)]
     pub nsub: u32,
-    #[serde(alias = "MT")]
+    #[serde(alias = "MT", default)]
     pub mt: u32,
-    #[serde(alias = "MF")]
+    #[serde(alias = "MF", default)]
     pub mf: u32,
-    #[serde(alias = "R")]
+    #[serde(alias = "R", default)]
     pub r: String,
-    #[serde(alias = "RC")]
+    #[serde(alias = "RC", default)]
     pub rc: String,
-    #[serde(alias = "EvalID")]
+    #[serde(alias = "EvalID", default)]
     pub eval_id: u32,
-    #[serde(alias = "SectID")]
+    #[serde(alias = "SectID", default, deserialize_with = "deserialize_id")]
     pub sect_id: u32,
-    #[serde(alias = "PenSectID")]
+    #[serde(alias = "PenSectID", default, deserialize_with = "deserialize_id")]
     pub pen_sect_id: u32,
-    #[serde(alias = "LibID")]
+    #[serde(alias = "LibID", default)]
     pub lib_id: u32,
     #[serde(alias = "LibName")]
     pub lib_name: String,
-    #[serde(alias = "DATE")]
+    #[serde(alias = "DATE", default)]
     pub date: String,
-    #[serde(alias = "AUTH")]
+    #[serde(alias = "AUTH", default)]
     pub auth: String,
 }
 
+/// A numeric field given either as a JSON number or as a string
+///
+/// Some IAEA endpoints quote numbers, e.g. "1234" or "2.53e-8" instead of
+/// 1234 or 2.53e-8. Strings are parsed after trimming whitespace.
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum NumberOrText<T> {
+    Number(T),
+    Text(String),
+}
+
+impl<T: std::str::FromStr> NumberOrText<T> {
+    /// The number, parsed from the text if it was quoted
+    fn into_number<E: serde::de::Error>(self, what: &str) -> Result<T, E> {
+        match self {
+            NumberOrText::Number(value) => Ok(value),
+            NumberOrText::Text(text) => text
+                .trim()
+                .parse()
+                .map_err(|_| E::custom(format!("{} '{}' is not a valid number", what, text))),
+        }
+    }
+}
+
+/// Deserializes a section ID given either as a JSON number or a string
+///
+/// The API has been seen to quote numeric IDs; both "1234" and 1234 are
+/// accepted. A missing ID is left at 0 and rejected by [`section_data_url`].
+fn deserialize_id<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    <NumberOrText<u32> as serde::Deserialize>::deserialize(deserializer)?.into_number("section ID")
+}
+
+/// Deserializes a numeric field given either as a JSON number or a string
+fn deserialize_number<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::Deserialize<'de> + std::str::FromStr,
+{
+    <NumberOrText<T> as serde::Deserialize>::deserialize(deserializer)?.into_number("value")
+}
+
+/// Base name (without unit) of the energy column
+const ENERGY_COLUMN: &str = "E";
+
+/// Base name of the cross section column
+const CROSS_SECTION_COLUMN: &str = "Sig";
+
+/// Base name of the cross section uncertainty column
+const UNCERTAINTY_COLUMN: &str = "dSig";
+
+/// Columns assumed for a dataset that declares none
+const DEFAULT_COLUMNS: [&str; 2] = [ENERGY_COLUMN, CROSS_SECTION_COLUMN];
+
+/// One point as tabulated, with its values keyed by column name
+type RawPoint = std::collections::BTreeMap<String, serde_json::Value>;
+
+/// Reads the value of the declared column `header` from a tabulated point
+///
+/// The point may key the value by the full header, e.g. "E(eV)", or by its
+/// name alone. Numbers may be quoted; null or a missing key gives `None`.
+fn raw_value(point: &RawPoint, header: &str) -> Result<Option<f64>, String> {
+    let value = point
+        .get(header)
+        .or_else(|| point.get(units::split_column_header(header).0));
+    match value {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(serde_json::Value::Number(number)) => Ok(number.as_f64()),
+        Some(serde_json::Value::String(text)) => text
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("{} '{}' is not a valid number", header, text)),
+        Some(other) => Err(format!("{} '{}' is not a valid number", header, other)),
+    }
+}
+
+/// Units declared by the energy and cross section headers among `columns`
+/// of dataset `id`, see [`CrossSectionDataset::column_units`]
+fn column_units(
+    columns: &[String],
+    id: &str,
+) -> Result<(Option<EnergyUnit>, Option<CrossSectionUnit>), String> {
+    let mut energy = None;
+    let mut cross_section = None;
+    for header in columns {
+        let (name, Some(unit)) = units::split_column_header(header) else {
+            continue;
+        };
+        let unknown = || {
+            format!(
+                "Unrecognized unit '{}' in column '{}' of dataset {}",
+                unit, header, id
+            )
+        };
+        match name {
+            ENERGY_COLUMN => energy = Some(EnergyUnit::from_symbol(unit).ok_or_else(unknown)?),
+            CROSS_SECTION_COLUMN => {
+                cross_section = Some(CrossSectionUnit::from_symbol(unit).ok_or_else(unknown)?)
+            }
+            _ => {}
+        }
+    }
+    Ok((energy, cross_section))
+}
+
+/// Units to read the points of dataset `id` in, eV and barns where
+/// `columns` declares none, which is noted
+fn point_units(columns: &[String], id: &str) -> Result<(EnergyUnit, CrossSectionUnit), String> {
+    let (energy_unit, cross_section_unit) = column_units(columns, id)?;
+    if energy_unit.is_none() || cross_section_unit.is_none() {
+        crate::report::status(format!(
+            "Dataset {} declares no units in its columns, assuming eV and barns",
+            id
+        ));
+    }
+    Ok((
+        energy_unit.unwrap_or(EnergyUnit::Ev),
+        cross_section_unit.unwrap_or_default(),
+    ))
+}
+
+/// Declared headers of the energy, cross section and uncertainty columns
+struct ColumnHeaders<'a> {
+    energy: &'a str,
+    cross_section: &'a str,
+    uncertainty: Option<&'a str>,
+}
+
+impl<'a> ColumnHeaders<'a> {
+    /// Finds the columns by name among `columns`, taking E and Sig when none
+    /// are declared
+    fn find(columns: &'a [String]) -> Result<Self, String> {
+        if columns.is_empty() {
+            return Ok(ColumnHeaders {
+                energy: ENERGY_COLUMN,
+                cross_section: CROSS_SECTION_COLUMN,
+                uncertainty: None,
+            });
+        }
+        let header = |name: &str| {
+            columns
+                .iter()
+                .map(String::as_str)
+                .find(|c| units::split_column_header(c).0 == name)
+        };
+        let required = |name: &str| {
+            header(name).ok_or_else(|| {
+                format!(
+                    "No {} column among the declared columns {}",
+                    name,
+                    columns.join(", ")
+                )
+            })
+        };
+        Ok(ColumnHeaders {
+            energy: required(ENERGY_COLUMN)?,
+            cross_section: required(CROSS_SECTION_COLUMN)?,
+            uncertainty: header(UNCERTAINTY_COLUMN),
+        })
+    }
+
+    /// Reads a tabulated point, keeping the other declared `columns` as extras
+    fn point(&self, raw: &RawPoint, columns: &[String]) -> Result<CrossSectionPoint, String> {
+        let required = |header: &str| {
+            raw_value(raw, header)?.ok_or_else(|| format!("A point has no {} value", header))
+        };
+        let mut extra = std::collections::BTreeMap::new();
+        for header in columns {
+            let header = header.as_str();
+            if header != self.energy
+                && header != self.cross_section
+                && Some(header) != self.uncertainty
+                && let Some(value) = raw_value(raw, header)?
+            {
+                extra.insert(header.to_string(), value);
+            }
+        }
+        Ok(CrossSectionPoint {
+            energy: required(self.energy)?,
+            cross_section: required(self.cross_section)?,
+            uncertainty: match self.uncertainty {
+                Some(header) => raw_value(raw, header)?,
+                None => None,
+            },
+            extra,
+        })
+    }
+}
+
 /// Represents a single (energy, cross section) data point
-#[derive(Debug, serde::Deserialize, serde::Serialize)]
+///
+/// Values are read from each tabulated point by the column names its
+/// dataset declares in `columns`: the energy from `E`, the cross section
+/// from `Sig`, the uncertainty from `dSig` if declared, and every other
+/// declared column into `extra`.
+#[derive(Debug, Clone)]
 pub struct CrossSectionPoint {
     /// Energy in eV
-    #[serde(alias = "E")]
     pub energy: f64,
     /// Cross section in barns
-    #[serde(alias = "Sig")]
     pub cross_section: f64,
+    /// Absolute cross section uncertainty in barns, if tabulated
+    pub uncertainty: Option<f64>,
+    /// Any other declared columns, keyed by their header
+    pub extra: std::collections::BTreeMap<String, f64>,
+}
+
+impl CrossSectionPoint {
+    /// Returns the value of a column by its name in the dataset's `columns`
+    /// list, with or without a unit in parentheses (e.g. "E(eV)")
+    pub fn column(&self, name: &str) -> Option<f64> {
+        match units::split_column_header(name).0 {
+            ENERGY_COLUMN => Some(self.energy),
+            CROSS_SECTION_COLUMN => Some(self.cross_section),
+            UNCERTAINTY_COLUMN => self.uncertainty,
+            _ => self.extra.get(name).copied(),
+        }
+    }
 }
 
 /// Represents a complete cross section dataset from a nuclear data library
-#[derive(Debug, serde::Deserialize, serde::Serialize)]
+///
+/// The id and points are required; the other metadata is not, since the
+/// API sometimes omits some of it. Missing fields default to zero or empty, columns to
+/// none (read in eV and barns) and the interpolation to lin-lin.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "RawDataset", into = "RawDataset")]
 pub struct CrossSectionDataset {
     pub id: String,
-    #[serde(alias = "FILE")]
     pub file: String,
-    #[serde(alias = "dataType")]
     pub data_type: String,
-    #[serde(alias = "LIBRARY")]
     pub library: String,
-    #[serde(alias = "TARGET")]
     pub target: String,
-    #[serde(alias = "TEMP")]
     pub temp: f64,
-    #[serde(alias = "NSUB")]
     pub nsub: u32,
-    #[serde(alias = "MAT")]
     pub mat: u32,
-    #[serde(alias = "MF")]
     pub mf: u32,
-    #[serde(alias = "MT")]
     pub mt: u32,
-    #[serde(alias = "REACTION")]
     pub reaction: String,
-    #[serde(alias = "COLUMNS")]
     pub columns: Vec<String>,
-    #[serde(alias = "defaultInterpolation")]
     pub default_interpolation: String,
-    #[serde(alias = "nPts")]
     pub n_pts: u32,
     /// Vector of (energy, cross section) data points
-    #[serde(alias = "pts")]
     pub points: Vec<CrossSectionPoint>,
 }
 
+/// A dataset as tabulated, before its points are read by column name
+#[derive(serde::Deserialize, serde::Serialize)]
+struct RawDataset {
+    id: String,
+    #[serde(alias = "FILE", default)]
+    file: String,
+    #[serde(alias = "dataType", default)]
+    data_type: String,
+    #[serde(alias = "LIBRARY", default)]
+    library: String,
+    #[serde(alias = "TARGET", default)]
+    target: String,
+    #[serde(alias = "TEMP", default, deserialize_with = "deserialize_number")]
+    temp: f64,
+    #[serde(alias = "NSUB", default, deserialize_with = "deserialize_number")]
+    nsub: u32,
+    #[serde(alias = "MAT", default, deserialize_with = "deserialize_number")]
+    mat: u32,
+    #[serde(alias = "MF", default, deserialize_with = "deserialize_number")]
+    mf: u32,
+    #[serde(alias = "MT", default, deserialize_with = "deserialize_number")]
+    mt: u32,
+    #[serde(alias = "REACTION", default)]
+    reaction: String,
+    #[serde(alias = "COLUMNS", default)]
+    columns: Vec<String>,
+    #[serde(alias = "defaultInterpolation", default = "default_interpolation")]
+    default_interpolation: String,
+    #[serde(alias = "nPts", default, deserialize_with = "deserialize_number")]
+    n_pts: u32,
+    #[serde(alias = "pts")]
+    points: Vec<RawPoint>,
+}
+
+impl TryFrom<RawDataset> for CrossSectionDataset {
+    type Error = String;
+
+    fn try_from(raw: RawDataset) -> Result<Self, String> {
+        let headers =
+            ColumnHeaders::find(&raw.columns).map_err(|e| format!("Dataset {}: {}", raw.id, e))?;
+        let points = raw
+            .points
+            .iter()
+            .map(|point| headers.point(point, &raw.columns))
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("Dataset {}: {}", raw.id, e))?;
+        Ok(CrossSectionDataset {
+            id: raw.id,
+            file: raw.file,
+            data_type: raw.data_type,
+            library: raw.library,
+            target: raw.target,
+            temp: raw.temp,
+            nsub: raw.nsub,
+            mat: raw.mat,
+            mf: raw.mf,
+            mt: raw.mt,
+            reaction: raw.reaction,
+            columns: raw.columns,
+            default_interpolation: raw.default_interpolation,
+            n_pts: raw.n_pts,
+            points,
+        })
+    }
+}
+
+impl From<CrossSectionDataset> for RawDataset {
+    /// Keys the values of each point by its declared column headers, so that
+    /// the dataset reads back the same
+    fn from(dataset: CrossSectionDataset) -> Self {
+        let default_columns = || DEFAULT_COLUMNS.map(String::from).to_vec();
+        let declared = if dataset.columns.is_empty() {
+            default_columns()
+        } else {
+            dataset.columns.clone()
+        };
+        let headers = ColumnHeaders::find(&declared).ok();
+        let (energy, cross_section, uncertainty) = match &headers {
+            Some(h) => (h.energy, h.cross_section, h.uncertainty),
+            None => (ENERGY_COLUMN, CROSS_SECTION_COLUMN, None),
+        };
+        let number = |value: f64| {
+            serde_json::Number::from_f64(value)
+                .map_or(serde_json::Value::Null, serde_json::Value::Number)
+        };
+        let points = dataset
+            .points
+            .iter()
+            .map(|point| {
+                let mut raw = RawPoint::new();
+                raw.insert(energy.to_string(), number(point.energy));
+                raw.insert(cross_section.to_string(), number(point.cross_section));
+                if let (Some(header), Some(value)) = (uncertainty, point.uncertainty) {
+                    raw.insert(header.to_string(), number(value));
+                }
+                for (header, &value) in &point.extra {
+                    raw.insert(header.clone(), number(value));
+                }
+                raw
+            })
+            .collect();
+        RawDataset {
+            id: dataset.id,
+            file: dataset.file,
+            data_type: dataset.data_type,
+            library: dataset.library,
+            target: dataset.target,
+            temp: dataset.temp,
+            nsub: dataset.nsub,
+            mat: dataset.mat,
+            mf: dataset.mf,
+            mt: dataset.mt,
+            reaction: dataset.reaction,
+            columns: dataset.columns,
+            default_interpolation: dataset.default_interpolation,
+            n_pts: dataset.n_pts,
+            points,
+        }
+    }
+}
+
+/// Interpolation law of a dataset that declares none
+fn default_interpolation() -> String {
+    "Lin-Lin".to_string()
+}
+
+impl CrossSectionDataset {
+    /// Returns the values of every column declared in `columns`, in order
+    ///
+    /// Each entry pairs the column name with one value per point (`None`
+    /// where a point lacks that column).
+    pub fn columns_data(&self) -> Vec<(&str, Vec<Option<f64>>)> {
+        self.columns
+            .iter()
+            .map(|name| {
+                let values = self.points.iter().map(|p| p.column(name)).collect();
+                (name.as_str(), values)
+            })
+            .collect()
+    }
+
+    /// Units declared in parentheses by the energy and cross section column
+    /// headers, e.g. "E(keV)" and "Sig(mb)"
+    ///
+    /// # Returns
+    /// * `Ok((energy, cross_section))` - Each unit, or `None` if not declared
+    /// * `Err(msg)` - If a declared unit is not recognized
+    pub fn column_units(&self) -> Result<(Option<EnergyUnit>, Option<CrossSectionUnit>), String> {
+        column_units(&self.columns, &self.id)
+    }
+
+    /// Energies in MeV and cross sections in barns of every point
+    ///
+    /// Values are converted from the units declared in the column headers.
+    /// Columns without a unit are taken in the EXFOR defaults, eV and barns,
+    /// which is noted.
+    pub fn points_in_mev_barns(&self) -> Result<(Vec<f64>, Vec<f64>), String> {
+        let (energy_unit, cross_section_unit) = point_units(&self.columns, &self.id)?;
+        Ok(self
+            .points
+            .iter()
+            .map(|p| {
+                (
+                    energy_unit.to_mev(p.energy),
+                    cross_section_unit.to_barns(p.cross_section),
+                )
+            })
+            .unzip())
+    }
+
+    /// Cross section uncertainties of every point in barns, `None` where a
+    /// point has none, taken in the unit of the cross section column
+    pub fn uncertainties_in_barns(&self) -> Result<Vec<Option<f64>>, String> {
+        let cross_section_unit = self.column_units()?.1.unwrap_or_default();
+        Ok(self
+            .points
+            .iter()
+            .map(|p| p.uncertainty.map(|u| cross_section_unit.to_barns(u)))
+            .collect())
+    }
+}
+
 /// API response containing cross section datasets
-#[derive(Debug, serde::Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct CrossSectionResponse {
+    #[serde(default)]
     pub format: String,
+    #[serde(default)]
     pub now: String,
+    #[serde(default)]
     pub program: String,
     pub datasets: Vec<CrossSectionDataset>,
+    /// Library name as spelled by the API, set when the section is downloaded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub library: Option<String>,
+    /// Set when the cross section was angle-integrated from differential data
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub angle_integrated: bool,
+}
+
+impl CrossSectionResponse {
+    /// Index of the dataset whose Doppler broadening best matches a stellar
+    /// temperature
+    ///
+    /// Datasets processed at several temperatures (`temp`, in K) are
+    /// candidates; the one closest to kT, converted to K, is chosen.
+    /// Libraries are processed at a few thousand K at most while kT = 1 keV
+    /// is already about 1.2·10⁷ K, so at stellar temperatures this is always
+    /// the hottest dataset: the closest broadening available, if still far
+    /// narrower than the stellar one.
+    ///
+    /// # Returns
+    /// `None` if the response holds no dataset
+    pub fn dataset_broadened_for(&self, temperature_kev: f64) -> Option<usize> {
+        let temperature_k = units::kev_to_kelvin(temperature_kev);
+        self.datasets
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| {
+                let distance = |dataset: &CrossSectionDataset| (dataset.temp - temperature_k).abs();
+                distance(a).total_cmp(&distance(b))
+            })
+            .map(|(index, _)| index)
+    }
 }
 
 /// Internal API response for section listing
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
 struct E4Response {
+    #[serde(default)]
     pub format: String,
+    #[serde(default)]
     pub now: String,
+    #[serde(default)]
     pub program: String,
+    #[serde(default)]
     pub req: u32,
     pub sections: Vec<Section>,
 }
 
+/// EXFOR quantity codes accepted by the section listing
+///
+/// * `SIG` - Cross section
+/// * `MACS` - Maxwellian-averaged cross section
+/// * `RI` - Resonance integral
+/// * `RP` - Resonance parameters
+/// * `DA` - Angular distribution
+/// * `DE` - Energy distribution
+/// * `DAE` - Double-differential distribution
+/// * `FY` - Fission yields
+pub const KNOWN_QUANTITIES: [&str; 8] = ["SIG", "MACS", "RI", "RP", "DA", "DE", "DAE", "FY"];
+
+/// Default quantity requested from EXFOR
+///
+/// Every reaction handled by the MACS calculation needs the
+/// energy-dependent cross section.
+pub const DEFAULT_QUANTITY: &str = "SIG";
+
 /// Fetches available sections from EXFOR database
 ///
+/// Natural-element targets are queried as "Sym-0" whichever way they were
+/// written (see [`nuclide::exfor_target`]).
+///
 /// # Arguments
-/// * `target` - Target nucleus (e.g., "Mo-94")
+/// * `target` - Target nucleus (e.g., "Mo-94", "Mo-nat")
 /// * `reaction` - Reaction type (e.g., "n,g" for neutron capture)
 /// * `quantity` - Physical quantity (e.g., "SIG" for cross section)
 async fn fetch_data(
@@ -116,71 +571,1909 @@ async fn fetch_data(
 ) -> Result<E4Response, Box<dyn std::error::Error>> {
     let url = format!(
         "https://www-nds.iaea.org/exfor/e4list?Target={}&Reaction={}&Quantity={}&json",
-        target, reaction, quantity
+        nuclide::exfor_target(target),
+        reaction,
+        quantity
     );
 
-    let response = reqwest::get(&url).await?.json::<E4Response>().await?;
+    let response: E4Response = get_json(&url).await?;
+    remember_libraries(response.sections.iter().map(|s| s.lib_name.clone()));
+    Ok(response)
+}
+
+/// Library names seen in section listings, or loaded from the library cache
+static KNOWN_LIBRARIES: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// Adds library names to the ones known to exist
+pub fn remember_libraries(names: impl IntoIterator<Item = String>) {
+    if let Ok(mut known) = KNOWN_LIBRARIES.lock() {
+        known.extend(names);
+    }
+}
+
+/// Library names known to exist, in alphabetical order
+pub fn known_libraries() -> Vec<String> {
+    KNOWN_LIBRARIES
+        .lock()
+        .map(|known| known.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Lists the libraries tabulating a reaction of a target
+///
+/// # Arguments
+/// * `target` - Target nucleus (e.g., "Mo-94")
+/// * `reaction` - Reaction type (e.g., "n,g")
+/// * `quantity` - Physical quantity (e.g., "SIG")
+pub async fn list_libraries(
+    target: &str,
+    reaction: &str,
+    quantity: &str,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let response = fetch_data_with_aliases(target, reaction, quantity).await?;
+    let names: BTreeSet<String> = response.sections.into_iter().map(|s| s.lib_name).collect();
+    Ok(names.into_iter().collect())
+}
+
+/// User-Agent identifying this tool to the IAEA servers
+const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// HTTP client shared by all requests
+static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+/// Builds an HTTP client sending the User-Agent and, if given, a `From`
+/// header with the contact address
+fn build_client(contact: Option<&str>) -> Result<reqwest::Client, String> {
+    let mut headers = reqwest::header::HeaderMap::new();
+    if let Some(contact) = contact {
+        let value = reqwest::header::HeaderValue::from_str(contact)
+            .map_err(|_| format!("'{}' cannot be sent as a contact header", contact))?;
+        headers.insert(reqwest::header::FROM, value);
+    }
+    reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .default_headers(headers)
+        .build()
+        .map_err(|e| format!("Cannot create the HTTP client: {}", e))
+}
+
+/// Sets up the HTTP client used for every request to the IAEA
+///
+/// Requests always identify the tool by name and version in their
+/// User-Agent. A `contact` address is sent as the `From` header, so that the
+/// server operators can reach users whose traffic causes problems. Only the
+/// first call has an effect; without one, requests carry no contact.
+pub fn init_client(contact: Option<&str>) -> Result<(), String> {
+    let client = build_client(contact)?;
+    let _ = CLIENT.set(client);
+    Ok(())
+}
+
+/// Returns the shared HTTP client
+fn client() -> &'static reqwest::Client {
+    CLIENT.get_or_init(|| build_client(None).unwrap_or_default())
+}
+
+/// Extra attempts made when a response body arrives truncated
+const TRUNCATED_RETRIES: u32 = 2;
+
+/// Downloads a URL and parses its body as JSON
+///
+/// The body is read completely before parsing so that a body cut short by a
+/// flaky connection can be told apart from a malformed one. Truncated bodies
+/// are downloaded again up to [`TRUNCATED_RETRIES`] times; other parse errors
+/// are reported straight away since retrying would not help.
+async fn get_json<T: serde::de::DeserializeOwned>(
+    url: &str,
+) -> Result<T, Box<dyn std::error::Error>> {
+    let mut attempt = 0;
+    loop {
+        let body = client()
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        match serde_json::from_str(&body) {
+            Ok(value) => return Ok(value),
+            Err(e) if e.is_eof() && attempt < TRUNCATED_RETRIES => {
+                attempt += 1;
+                crate::report::warning(format!(
+                    "response truncated after {} bytes, retrying ({}/{})",
+                    body.len(),
+                    attempt,
+                    TRUNCATED_RETRIES
+                ));
+            }
+            Err(e) if e.is_eof() => {
+                return Err(format!(
+                    "Response from {} was truncated after {} bytes ({}); the connection was probably interrupted",
+                    url,
+                    body.len(),
+                    e
+                )
+                .into());
+            }
+            Err(e) => {
+                return Err(format!(
+                    "Response from {} is complete but malformed ({}); the API format may have changed",
+                    url, e
+                )
+                .into());
+            }
+        }
+    }
+}
+
+/// Spellings of the same reaction that EXFOR may index separately
+///
+/// Each group lists equivalent forms; add a new group to support another reaction.
+/// Case and parenthesized variants are tried for any reaction even without a group.
+const REACTION_ALIASES: &[&[&str]] = &[
+    &["n,g", "n,gamma"],
+    &["n,a", "n,alpha"],
+    &["n,tot", "n,total"],
+    &["n,el", "n,elastic"],
+    &["n,f", "n,fission"],
+];
+
+/// ENDF reaction numbers (MT) of common reactions, keyed by the first
+/// spelling of their [`REACTION_ALIASES`] group
+const REACTION_MT: &[(&str, u32)] = &[
+    ("n,tot", 1),
+    ("n,el", 2),
+    ("n,inl", 4),
+    ("n,2n", 16),
+    ("n,f", 18),
+    ("n,g", 102),
+    ("n,p", 103),
+    ("n,d", 104),
+    ("n,t", 105),
+    ("n,h", 106),
+    ("n,a", 107),
+];
+
+/// Warns if a dataset's TARGET or MT does not match the requested ones
+///
+/// A dataset fetched by --url bypasses the section lookup, so nothing else
+/// ties it to --target and --reaction. Fields the dataset leaves empty are
+/// not checked.
+fn check_dataset_identity(dataset: &CrossSectionDataset, target: &str, reaction: &str) {
+    let same_target = match (
+        nuclide::parse_target(&dataset.target),
+        nuclide::parse_target(target),
+    ) {
+        (Some(found), Some(requested)) => found == requested,
+        _ => dataset.target.trim().eq_ignore_ascii_case(target.trim()),
+    };
+    if !dataset.target.is_empty() && !same_target {
+        crate::report::warning(format!(
+            "dataset {} is for TARGET={} but --target is {}",
+            dataset.id, dataset.target, target
+        ));
+    }
+    if dataset.mt != 0
+        && let Some(mts) = expected_mt(reaction)
+        && !mts.contains(&dataset.mt)
+    {
+        crate::report::warning(format!(
+            "dataset {} has MT={} but ({}) is MT={}, the data may describe another reaction",
+            dataset.id, dataset.mt, reaction, mts[0]
+        ));
+    }
+}
+
+/// Returns the MT numbers a section of a reaction may carry
+///
+/// Besides the reaction's own MT, charged-particle emission channels accept
+/// their partial MTs for individual residual levels (600-849). Returns `None`
+/// for reactions missing from [`REACTION_MT`].
+fn expected_mt(reaction: &str) -> Option<Vec<u32>> {
+    let bare = reaction
+        .trim()
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .to_lowercase();
+    let canonical = REACTION_ALIASES
+        .iter()
+        .find(|group| group.iter().any(|r| r.eq_ignore_ascii_case(&bare)))
+        .map_or(bare.as_str(), |group| group[0]);
+    let &(_, mt) = REACTION_MT.iter().find(|(r, _)| *r == canonical)?;
+
+    let mut mts = vec![mt];
+    if (103..=107).contains(&mt) {
+        let first_level = 600 + 50 * (mt - 103);
+        mts.extend(first_level..first_level + 50);
+    }
+    Some(mts)
+}
+
+/// Warns if a section's MT does not match the requested reaction
+///
+/// The text query can match an unexpected channel; the MT number is the
+/// authoritative identifier of what the data describes. A section listed
+/// without an MT is not checked.
+fn check_section_mt(section: &Section, reaction: &str) {
+    if section.mt != 0
+        && let Some(mts) = expected_mt(reaction)
+        && !mts.contains(&section.mt)
+    {
+        crate::report::warning(format!(
+            "section {} has MT={} but ({}) is MT={}, the data may describe another reaction",
+            section.sect_id, section.mt, reaction, mts[0]
+        ));
+    }
+}
+
+/// Lists alternative spellings of a reaction to try when a query finds nothing
+///
+/// The requested form itself is not included.
+fn reaction_aliases(reaction: &str) -> Vec<String> {
+    let bare = reaction
+        .trim()
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .to_string();
+
+    let mut forms = vec![bare.clone()];
+    if let Some(group) = REACTION_ALIASES
+        .iter()
+        .find(|group| group.iter().any(|r| r.eq_ignore_ascii_case(&bare)))
+    {
+        forms.extend(group.iter().map(|r| r.to_string()));
+    }
+
+    let mut aliases: Vec<String> = Vec::new();
+    for form in forms {
+        for variant in [
+            form.to_lowercase(),
+            form.to_uppercase(),
+            format!("({})", form.to_lowercase()),
+            format!("({})", form.to_uppercase()),
+        ] {
+            if variant != reaction && !aliases.contains(&variant) {
+                aliases.push(variant);
+            }
+        }
+    }
+    aliases
+}
+
+/// Fetches the section listing, falling back to reaction aliases if it is empty
+async fn fetch_data_with_aliases(
+    target: &str,
+    reaction: &str,
+    quantity: &str,
+) -> Result<E4Response, Box<dyn std::error::Error>> {
+    list_with_aliases(reaction, |reaction| async move {
+        fetch_data(target, &reaction, quantity).await
+    })
+    .await
+}
+
+/// Lists the sections of a reaction with `list`, trying the aliases of the
+/// reaction in turn while the listing is empty
+async fn list_with_aliases<F, Fut>(
+    reaction: &str,
+    list: F,
+) -> Result<E4Response, Box<dyn std::error::Error>>
+where
+    F: Fn(String) -> Fut,
+    Fut: std::future::Future<Output = Result<E4Response, Box<dyn std::error::Error>>>,
+{
+    let response = list(reaction.to_string()).await?;
+    if !response.sections.is_empty() {
+        return Ok(response);
+    }
+
+    for alias in reaction_aliases(reaction) {
+        let aliased = list(alias.clone()).await?;
+        if !aliased.sections.is_empty() {
+            crate::report::status(format!(
+                "No sections found for reaction '{}', using alias '{}'",
+                reaction, alias
+            ));
+            return Ok(aliased);
+        }
+    }
     Ok(response)
 }
 
+/// Lists the isotopes of an element for which a reaction is tabulated
+///
+/// Queries the section listing with the wildcard target "Sym-*" and collects
+/// the distinct mass numbers found in any library. Natural-element targets
+/// (mass number 0) are left out.
+///
+/// # Arguments
+/// * `symbol` - Element symbol (e.g., "Mo")
+/// * `reaction` - Reaction type (e.g., "n,g")
+/// * `quantity` - EXFOR quantity code (e.g., "SIG")
+///
+/// # Returns
+/// * `Ok(isotopes)` - (target, mass number) pairs sorted by mass number
+/// * `Err` - Network or parse error
+pub async fn find_isotopes(
+    symbol: &str,
+    reaction: &str,
+    quantity: &str,
+) -> Result<Vec<(String, u32)>, Box<dyn std::error::Error>> {
+    let response = fetch_data_with_aliases(&format!("{}-*", symbol), reaction, quantity).await?;
+
+    let mut isotopes: Vec<u32> = response
+        .sections
+        .iter()
+        .map(|section| section.a)
+        .filter(|&a| a != 0)
+        .collect();
+    isotopes.sort_unstable();
+    isotopes.dedup();
+
+    let z = nuclide::z_for_symbol(symbol).ok_or_else(|| format!("Unknown element {}", symbol))?;
+    isotopes
+        .into_iter()
+        .map(|a| Ok((nuclide::format_target(z, a)?, a)))
+        .collect()
+}
+
+/// Normalizes a library name for matching: lowercase letters and digits only
+///
+/// "endf-b-viii.1", "ENDF/B-VIII.1" and "ENDF B VIII 1" all give "endfbviii1".
+fn library_key(name: &str) -> String {
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
 /// Filters sections by library name
 ///
+/// An exact match is preferred. Otherwise the name is compared ignoring case,
+/// punctuation and spacing, and the canonical name of the match is reported.
+///
 /// # Arguments
 /// * `response` - API response containing sections
 /// * `lib_name` - Library name to filter (e.g., "JEFF-3.1", "JEFF-4.0", "ENDF-B-VIII.1")
-fn filter_by_library(response: E4Response, lib_name: &str) -> E4Response {
-    let filtered_sections: Vec<Section> = response
-        .sections
-        .into_iter()
-        .filter(|section| section.lib_name == lib_name)
-        .collect();
+///
+/// # Returns
+/// * `Ok(response)` - The sections of the library
+/// * `Err(msg)` - If no library matches, listing the available ones and
+///   suggesting the closest known name
+fn filter_by_library(response: E4Response, lib_name: &str) -> Result<E4Response, String> {
+    let key = library_key(lib_name);
+    let exact = response.sections.iter().any(|s| s.lib_name == lib_name);
+    let mut available: Vec<String> = Vec::new();
+    let mut filtered_sections: Vec<Section> = Vec::new();
+    for section in response.sections {
+        let matches = if exact {
+            section.lib_name == lib_name
+        } else {
+            library_key(&section.lib_name) == key
+        };
+        if matches {
+            filtered_sections.push(section);
+        } else if !available.contains(&section.lib_name) {
+            available.push(section.lib_name);
+        }
+    }
+
+    if filtered_sections.is_empty() {
+        available.sort();
+        return Err(if available.is_empty() {
+            "No sections found for the specified library".to_string()
+        } else {
+            format!(
+                "No sections found for library '{}'; available: {}",
+                lib_name,
+                available.join(", ")
+            )
+        } + &match suggest_library(lib_name, &available) {
+            Some(name) => format!(" (did you mean {}?)", name),
+            None => String::new(),
+        });
+    }
+    if !exact {
+        crate::report::status(format!(
+            "Library '{}' matched as '{}'",
+            lib_name, filtered_sections[0].lib_name
+        ));
+    }
 
-    E4Response {
+    Ok(E4Response {
         format: response.format,
         now: response.now,
         program: response.program,
         req: response.req,
         sections: filtered_sections,
+    })
+}
+
+/// Number of single-character edits turning `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Closest library name to a misspelled one
+///
+/// Candidates are the libraries of the listing and all known ones (see
+/// [`known_libraries`]), compared ignoring case and punctuation. A name is
+/// only suggested if it is within a third of the name's length in edits.
+fn suggest_library(lib_name: &str, available: &[String]) -> Option<String> {
+    let key = library_key(lib_name);
+    let max_distance = (key.len() / 3).max(1);
+    available
+        .iter()
+        .cloned()
+        .chain(known_libraries())
+        .map(|name| (edit_distance(&key, &library_key(&name)), name))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, name)| name)
+}
+
+/// Digits of a section date, used to order evaluations chronologically
+///
+/// Dates are compared as digit strings (e.g. "2011-11" becomes "201111"),
+/// so formats listing the year first, then month and day, sort correctly.
+fn date_key(date: &str) -> String {
+    date.chars().filter(|c| c.is_ascii_digit()).collect()
+}
+
+/// Orders the sections of a library by preference
+///
+/// Keeps only the sections of the pinned evaluation if `eval_id` is given,
+/// otherwise sorts them newest first by date, with the larger evaluation ID
+/// winning ties.
+fn rank_evaluations(mut sections: Vec<Section>, eval_id: Option<u32>) -> Vec<Section> {
+    match eval_id {
+        Some(id) => sections.retain(|s| s.eval_id == id),
+        None => sections.sort_by(|x, y| {
+            let (dx, dy) = (date_key(&x.date), date_key(&y.date));
+            dy.cmp(&dx).then_with(|| y.eval_id.cmp(&x.eval_id))
+        }),
+    }
+    sections
+}
+
+/// Whether the listing gave a section both IDs needed to download its data
+fn has_section_ids(section: &Section) -> bool {
+    section.sect_id != 0 && section.pen_sect_id != 0
+}
+
+/// Error of a listing without any section of the requested library
+///
+/// Unlike a network or parse error this means the data does not exist for
+/// the quantity asked, so a caller may try another quantity instead.
+#[derive(Debug)]
+pub struct NoSectionError(pub String);
+
+impl std::fmt::Display for NoSectionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
     }
 }
 
+impl std::error::Error for NoSectionError {}
+
+/// Finds the section of the requested library and evaluation
+///
+/// When the library has several evaluations of the reaction the newest is
+/// used unless `eval_id` pins one. A listing entry without a `SectID` or
+/// `PenSectID` cannot be downloaded, so it is skipped with a warning in
+/// favour of the next matching section. The selected evaluation is reported.
+///
+/// # Arguments
+/// * `target` - Target nucleus (e.g., "Mo-94")
+/// * `reaction` - Reaction type (e.g., "n,g")
+/// * `quantity` - Physical quantity (e.g., "SIG")
+/// * `lib_name` - Nuclear data library name (e.g., "JEFF-4.0")
+/// * `eval_id` - Evaluation to use, or `None` for the newest
+async fn find_section(
+    target: &str,
+    reaction: &str,
+    quantity: &str,
+    lib_name: &str,
+    eval_id: Option<u32>,
+) -> Result<Section, Box<dyn std::error::Error>> {
+    let response = fetch_data_with_aliases(target, reaction, quantity).await?;
+    section_from_listing(response, target, reaction, lib_name, eval_id)
+}
+
+/// Picks the section to download from a listing, as described for
+/// [`find_section`]
+fn section_from_listing(
+    response: E4Response,
+    target: &str,
+    reaction: &str,
+    lib_name: &str,
+    eval_id: Option<u32>,
+) -> Result<Section, Box<dyn std::error::Error>> {
+    let filtered = filter_by_library(response, lib_name).map_err(NoSectionError)?;
+    let evaluations = filtered.sections.len();
+
+    let candidates = rank_evaluations(filtered.sections, eval_id);
+    if candidates.is_empty() {
+        return Err(match eval_id {
+            Some(id) if evaluations > 0 => format!(
+                "Evaluation {} not found among the {} evaluations of the library",
+                id, evaluations
+            ),
+            _ => "No sections found for the specified library".to_string(),
+        }
+        .into());
+    }
+    let matching = candidates.len();
+    let mut candidates = candidates.into_iter();
+    let section = loop {
+        match candidates.next() {
+            Some(section) if has_section_ids(&section) => break section,
+            Some(section) => crate::report::warning(format!(
+                "evaluation {} of {} in {} lists no valid SectID/PenSectID, trying the next section",
+                section.eval_id, section.target, section.lib_name
+            )),
+            None => {
+                return Err(format!(
+                    "None of the {} matching section(s) of {} in {} lists a valid SectID and PenSectID, cannot download the data",
+                    matching, target, lib_name
+                )
+                .into());
+            }
+        }
+    };
+    if evaluations > 1 {
+        crate::report::status(format!(
+            "Using evaluation {} dated {} ({} available)",
+            section.eval_id, section.date, evaluations
+        ));
+    }
+    check_section_mt(&section, reaction);
+    Ok(section)
+}
+
+/// Builds the e4sig URL returning the datasets of a section
+///
+/// # Returns
+/// * `Ok(url)` - The URL for the section's IDs
+/// * `Err(msg)` - If the listing gave no (or a zero) section ID
+fn section_data_url(section: &Section) -> Result<String, String> {
+    for (name, id) in [
+        ("SectID", section.sect_id),
+        ("PenSectID", section.pen_sect_id),
+    ] {
+        if id == 0 {
+            return Err(format!(
+                "Section of {} in {} has no valid {}, cannot download its data",
+                section.target, section.lib_name, name
+            ));
+        }
+    }
+    Ok(format!(
+        "https://www-nds.iaea.org/exfor/e4sig?SectID={}&PenSectID={}&json",
+        section.sect_id, section.pen_sect_id
+    ))
+}
+
+/// Downloads the cross section datasets belonging to a section
+///
+/// See [`section_data`] for the checks made on the downloaded datasets.
+async fn fetch_section_data(
+    section: &Section,
+) -> Result<CrossSectionResponse, Box<dyn std::error::Error>> {
+    let url = section_data_url(section)?;
+    Ok(section_data(section, get_json(&url).await?)?)
+}
+
+/// Labels the datasets downloaded for a section with its library
+///
+/// A section whose datasets hold no point at all is an error of its own,
+/// distinct from a library without sections: the library lists the
+/// reaction but has no cross section data to integrate.
+fn section_data(
+    section: &Section,
+    mut response: CrossSectionResponse,
+) -> Result<CrossSectionResponse, String> {
+    if response.datasets.iter().all(|d| d.points.is_empty()) {
+        return Err(format!(
+            "{} has a section for {} (MT={}) but no cross section data: {} dataset(s) without points",
+            section.lib_name,
+            section.target,
+            section.mt,
+            response.datasets.len()
+        ));
+    }
+    response.library = Some(section.lib_name.clone());
+    Ok(response)
+}
+
 /// Fetches cross section data from EXFOR database
 ///
 /// # Arguments
 /// * `target` - Target nucleus (e.g., "Mo-94", "Zr-92")
 /// * `reaction` - Reaction type (e.g., "n,g" for neutron capture, "n,p" for (n,p) reaction)
+/// * `quantity` - EXFOR quantity code (see [`KNOWN_QUANTITIES`], usually "SIG")
 /// * `lib_name` - Nuclear data library name (e.g., "JEFF-3.1", "JEFF-4.0", "ENDF-B-VIII.1", "JENDL-5")
+/// * `eval_id` - Evaluation to use, or `None` for the library's newest
 ///
 /// # Returns
 /// * `Ok(CrossSectionResponse)` - Response containing the cross section datasets
-/// * `Err` - Error if no data found or network error
+/// * `Err` - Error if no section is found, the section's datasets are all
+///   empty, or on a network error
 ///
 /// # Example
-/// ```
-/// let data = fetch_cross_section("Mo-94", "n,g", "JEFF-4.0").await?;
+/// ```no_run
+/// # use macs_rs::exfor_client::fetch_cross_section;
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let data = fetch_cross_section("Mo-94", "n,g", "SIG", "JEFF-4.0", None).await?;
+/// # Ok(())
+/// # }
 /// ```
 pub async fn fetch_cross_section(
     target: &str,
     reaction: &str,
+    quantity: &str,
+    lib_name: &str,
+    eval_id: Option<u32>,
+) -> Result<CrossSectionResponse, Box<dyn std::error::Error>> {
+    let section = find_section(target, reaction, quantity, lib_name, eval_id).await?;
+    fetch_section_data(&section).await
+}
+
+/// Fetches cross section data for a nuclide given by its (Z, A) pair
+///
+/// The EXFOR target string is built from Z and A, and the returned section
+/// is checked to actually belong to the requested nuclide.
+///
+/// # Arguments
+/// * `z` - Atomic number (e.g., 42 for molybdenum)
+/// * `a` - Mass number (e.g., 94)
+/// * `reaction` - Reaction type (e.g., "n,g")
+/// * `quantity` - EXFOR quantity code (e.g., "SIG")
+/// * `lib_name` - Nuclear data library name (e.g., "JEFF-4.0")
+/// * `eval_id` - Evaluation to use, or `None` for the library's newest
+///
+/// # Example
+/// ```no_run
+/// # use macs_rs::exfor_client::fetch_cross_section_by_za;
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let data = fetch_cross_section_by_za(42, 94, "n,g", "SIG", "JEFF-4.0", None).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn fetch_cross_section_by_za(
+    z: u32,
+    a: u32,
+    reaction: &str,
+    quantity: &str,
     lib_name: &str,
+    eval_id: Option<u32>,
 ) -> Result<CrossSectionResponse, Box<dyn std::error::Error>> {
-    let quantity = "SIG";
-    let response = fetch_data(target, reaction, quantity).await?;
-    let filtered = filter_by_library(response, lib_name);
+    let target = nuclide::format_target(z, a)?;
+    let section = find_section(&target, reaction, quantity, lib_name, eval_id).await?;
 
-    if filtered.sections.is_empty() {
-        return Err("No sections found for the specified library".into());
+    if section.z != z || section.a != a {
+        return Err(format!(
+            "Section returned for {} has Z={}, A={} but Z={}, A={} was requested",
+            target, section.z, section.a, z, a
+        )
+        .into());
     }
 
-    let section = &filtered.sections[0];
-    let url = format!(
-        "https://www-nds.iaea.org/exfor/e4sig?SectID={}&PenSectID={}&json",
-        section.sect_id, section.pen_sect_id
-    );
+    fetch_section_data(&section).await
+}
+
+/// Identifies the cross section to fetch from a [`CrossSectionSource`]
+#[derive(Debug, Clone, Copy)]
+pub struct FetchRequest<'a> {
+    /// Target nuclide (e.g., "Mo-94")
+    pub target: &'a str,
+    /// (Z, A) pair when the target was given by numbers, checked against the data
+    pub za: Option<(u32, u32)>,
+    /// Reaction type (e.g., "n,g")
+    pub reaction: &'a str,
+    /// EXFOR quantity code (e.g., "SIG")
+    pub quantity: &'a str,
+    /// Nuclear data library name (e.g., "JEFF-4.0")
+    pub lib_name: &'a str,
+    /// Evaluation to use, or `None` for the library's newest
+    pub eval_id: Option<u32>,
+}
+
+/// Future returned by [`CrossSectionSource::fetch`]
+pub type FetchFuture<'a> = std::pin::Pin<
+    Box<
+        dyn std::future::Future<Output = Result<CrossSectionResponse, Box<dyn std::error::Error>>>
+            + 'a,
+    >,
+>;
 
-    let cross_section_data = reqwest::get(&url)
+/// Future returned by [`CrossSectionSource::stream`], resolving to the
+/// processing temperature of the streamed dataset
+pub type StreamFuture<'a> = std::pin::Pin<
+    Box<dyn std::future::Future<Output = Result<f64, Box<dyn std::error::Error>>> + 'a>,
+>;
+
+/// Future returned by [`CrossSectionSource::isotopes`]
+pub type IsotopesFuture<'a> = std::pin::Pin<
+    Box<
+        dyn std::future::Future<Output = Result<Vec<(String, u32)>, Box<dyn std::error::Error>>>
+            + 'a,
+    >,
+>;
+
+/// Provider of cross section data
+///
+/// The CLI fetches through this trait so the EXFOR client can be swapped for
+/// another implementation, e.g. canned data when testing.
+pub trait CrossSectionSource {
+    /// Fetches the cross section described by `request`
+    fn fetch<'a>(&'a self, request: FetchRequest<'a>) -> FetchFuture<'a>;
+
+    /// Pushes the points of the first dataset described by `request` into
+    /// `sink`, in MeV and barns
+    ///
+    /// By default the whole response is fetched first; a source that can
+    /// parse the data while downloading it overrides this to keep memory
+    /// bounded.
+    fn stream<'a>(
+        &'a self,
+        request: FetchRequest<'a>,
+        sink: &'a mut dyn PointSink,
+    ) -> StreamFuture<'a> {
+        Box::pin(async move {
+            let response = self.fetch(request).await?;
+            let dataset = response
+                .datasets
+                .first()
+                .ok_or("No dataset found in API response")?;
+            let (energies, cross_sections) = dataset.points_in_mev_barns()?;
+            for (energy, cross_section) in energies.into_iter().zip(cross_sections) {
+                sink.push(energy, cross_section);
+            }
+            Ok(dataset.temp)
+        })
+    }
+
+    /// Lists the isotopes of element `symbol` for which a reaction is
+    /// tabulated, as (target, mass number) pairs
+    ///
+    /// Sources holding the data of a single target cannot, and fail by default.
+    fn isotopes<'a>(
+        &'a self,
+        symbol: &'a str,
+        reaction: &'a str,
+        quantity: &'a str,
+    ) -> IsotopesFuture<'a> {
+        let _ = (reaction, quantity);
+        Box::pin(async move {
+            Err(format!("This data source cannot list the isotopes of {}", symbol).into())
+        })
+    }
+}
+
+/// [`CrossSectionSource`] backed by the IAEA EXFOR web API
+pub struct ExforSource;
+
+impl CrossSectionSource for ExforSource {
+    fn fetch<'a>(&'a self, request: FetchRequest<'a>) -> FetchFuture<'a> {
+        Box::pin(async move {
+            match request.za {
+                Some((z, a)) => {
+                    fetch_cross_section_by_za(
+                        z,
+                        a,
+                        request.reaction,
+                        request.quantity,
+                        request.lib_name,
+                        request.eval_id,
+                    )
+                    .await
+                }
+                None => {
+                    fetch_cross_section(
+                        request.target,
+                        request.reaction,
+                        request.quantity,
+                        request.lib_name,
+                        request.eval_id,
+                    )
+                    .await
+                }
+            }
+        })
+    }
+
+    fn stream<'a>(
+        &'a self,
+        request: FetchRequest<'a>,
+        sink: &'a mut dyn PointSink,
+    ) -> StreamFuture<'a> {
+        Box::pin(stream_cross_section(
+            request.target,
+            request.reaction,
+            request.quantity,
+            request.lib_name,
+            request.eval_id,
+            sink,
+        ))
+    }
+
+    fn isotopes<'a>(
+        &'a self,
+        symbol: &'a str,
+        reaction: &'a str,
+        quantity: &'a str,
+    ) -> IsotopesFuture<'a> {
+        Box::pin(find_isotopes(symbol, reaction, quantity))
+    }
+}
+
+/// Host serving the EXFOR API
+pub const API_HOST: &str = "www-nds.iaea.org";
+
+/// Library label of results computed from a `--url` whose datasets name none
+pub const URL_LIBRARY: &str = "url";
+
+/// Checks a user-supplied e4sig URL and makes it ask for JSON
+///
+/// URLs copied from the IAEA web interface may lack the `json` parameter,
+/// which is added. Unless `any_host` is set, the URL must point at the e4sig
+/// endpoint on [`API_HOST`], so that a typo does not send the request
+/// elsewhere.
+///
+/// # Returns
+/// * `Ok(url)` - The URL to download
+/// * `Err(msg)` - If the URL cannot be parsed or points elsewhere
+pub fn dataset_url(url: &str, any_host: bool) -> Result<String, String> {
+    let mut parsed =
+        reqwest::Url::parse(url.trim()).map_err(|e| format!("Invalid URL '{}': {}", url, e))?;
+    if !any_host {
+        if parsed.host_str() != Some(API_HOST) {
+            return Err(format!(
+                "{} does not point at {}, pass --allow-any-host to use it anyway",
+                url, API_HOST
+            ));
+        }
+        if !parsed.path().ends_with("/e4sig") {
+            return Err(format!(
+                "{} is not an e4sig dataset URL, pass --allow-any-host to use it anyway",
+                url
+            ));
+        }
+    }
+    if !parsed.query_pairs().any(|(key, _)| key == "json") {
+        let query = match parsed.query() {
+            Some(query) if !query.is_empty() => format!("{}&json", query),
+            _ => "json".to_string(),
+        };
+        parsed.set_query(Some(&query));
+    }
+    Ok(parsed.to_string())
+}
+
+/// [`CrossSectionSource`] downloading one dataset URL given by the user
+///
+/// Section discovery is skipped: every request gets the datasets at the URL,
+/// whatever target, reaction or library it names.
+pub struct UrlSource {
+    /// e4sig URL, as returned by [`dataset_url`]
+    pub url: String,
+}
+
+impl CrossSectionSource for UrlSource {
+    fn fetch<'a>(&'a self, request: FetchRequest<'a>) -> FetchFuture<'a> {
+        Box::pin(async move {
+            let mut response: CrossSectionResponse = get_json(&self.url).await?;
+            for dataset in &response.datasets {
+                check_dataset_identity(dataset, request.target, request.reaction);
+            }
+            if response.datasets.iter().all(|d| d.points.is_empty()) {
+                return Err(format!(
+                    "{} holds no cross section data: {} dataset(s) without points",
+                    self.url,
+                    response.datasets.len()
+                )
+                .into());
+            }
+            let library = response
+                .datasets
+                .iter()
+                .map(|d| d.library.as_str())
+                .find(|library| !library.is_empty())
+                .unwrap_or(URL_LIBRARY);
+            response.library = Some(library.to_string());
+            Ok(response)
+        })
+    }
+}
+
+/// Builds the [`CachedSource`] key of a request
+fn cache_key(request: &FetchRequest) -> String {
+    format!(
+        "{}|{}|{}|{}|{:?}|{:?}",
+        request.target,
+        request.reaction,
+        request.quantity,
+        request.lib_name,
+        request.eval_id,
+        request.za
+    )
+}
+
+/// [`CrossSectionSource`] keeping the responses it fetched in memory
+///
+/// A run may need the same dataset more than once, e.g. when a library is
+/// listed twice or both reactions of a ratio coincide. Responses are keyed by
+/// target, reaction, quantity, library and evaluation, so unrelated targets
+/// never share an entry. They live as long as the cache, which is meant to
+/// last a single run, or until [`CachedSource::clear`] is called.
+pub struct CachedSource<'s> {
+    inner: &'s dyn CrossSectionSource,
+    responses: std::cell::RefCell<std::collections::HashMap<String, CrossSectionResponse>>,
+}
+
+impl<'s> CachedSource<'s> {
+    /// Creates an empty cache in front of `inner`
+    pub fn new(inner: &'s dyn CrossSectionSource) -> Self {
+        CachedSource {
+            inner,
+            responses: Default::default(),
+        }
+    }
+
+    /// Drops every cached response
+    pub fn clear(&self) {
+        self.responses.borrow_mut().clear();
+    }
+}
+
+impl CrossSectionSource for CachedSource<'_> {
+    fn fetch<'a>(&'a self, request: FetchRequest<'a>) -> FetchFuture<'a> {
+        Box::pin(async move {
+            let key = cache_key(&request);
+            if let Some(response) = self.responses.borrow().get(&key) {
+                crate::report::status(format!(
+                    "Reusing the {} data for {}({}) fetched earlier in this run",
+                    request.lib_name, request.target, request.reaction
+                ));
+                return Ok(response.clone());
+            }
+
+            let response = self.inner.fetch(request).await?;
+            self.responses.borrow_mut().insert(key, response.clone());
+            Ok(response)
+        })
+    }
+
+    // Streamed points are not kept, so there is nothing to cache
+    fn stream<'a>(
+        &'a self,
+        request: FetchRequest<'a>,
+        sink: &'a mut dyn PointSink,
+    ) -> StreamFuture<'a> {
+        self.inner.stream(request, sink)
+    }
+
+    fn isotopes<'a>(
+        &'a self,
+        symbol: &'a str,
+        reaction: &'a str,
+        quantity: &'a str,
+    ) -> IsotopesFuture<'a> {
+        self.inner.isotopes(symbol, reaction, quantity)
+    }
+}
+
+/// Receives cross section points one at a time while a response is parsed
+pub trait PointSink {
+    /// Called for each point of the first dataset, in file order
+    ///
+    /// # Arguments
+    /// * `energy` - Energy in MeV
+    /// * `cross_section` - Cross section in barns
+    fn push(&mut self, energy: f64, cross_section: f64);
+}
+
+/// Number of downloaded chunks buffered ahead of the parser
+const STREAM_BUFFER_CHUNKS: usize = 16;
+
+/// Number of parsed points handed from the parser to the sink at once
+const STREAM_BATCH_POINTS: usize = 4096;
+
+/// Collects the points parsed on the blocking thread into batches sent back
+/// to the async task, which pushes them into the caller's sink
+struct BatchSender {
+    batch: Vec<(f64, f64)>,
+    sender: tokio::sync::mpsc::Sender<Vec<(f64, f64)>>,
+}
+
+impl BatchSender {
+    fn flush(&mut self) {
+        if !self.batch.is_empty() {
+            let batch = std::mem::replace(&mut self.batch, Vec::with_capacity(STREAM_BATCH_POINTS));
+            // The receiver only hangs up when the download failed, which is reported instead
+            let _ = self.sender.blocking_send(batch);
+        }
+    }
+}
+
+impl PointSink for BatchSender {
+    fn push(&mut self, energy: f64, cross_section: f64) {
+        self.batch.push((energy, cross_section));
+        if self.batch.len() >= STREAM_BATCH_POINTS {
+            self.flush();
+        }
+    }
+}
+
+/// Blocking reader over body chunks sent from the async download task
+struct ChannelReader {
+    chunks: tokio::sync::mpsc::Receiver<Vec<u8>>,
+    current: Vec<u8>,
+    offset: usize,
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.offset >= self.current.len() {
+            match self.chunks.blocking_recv() {
+                Some(chunk) => {
+                    self.current = chunk;
+                    self.offset = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.current.len() - self.offset);
+        buf[..n].copy_from_slice(&self.current[self.offset..self.offset + n]);
+        self.offset += n;
+        Ok(n)
+    }
+}
+
+/// Visits the top-level e4sig response, descending only into `datasets`
+///
+/// Produces the processing temperature of the first dataset.
+struct ResponseSeed<'a, S: ?Sized>(&'a mut S);
+
+/// Visits the `datasets` array, streaming the first and skipping the rest
+struct DatasetsSeed<'a, S: ?Sized>(&'a mut S);
+
+/// Visits one dataset, streaming its `pts` array into the sink
+struct DatasetSeed<'a, S: ?Sized>(&'a mut S);
+
+/// Visits the `pts` array, pushing each point into the sink
+///
+/// The energy and cross section are read by the headers declared in the
+/// dataset's `columns` when that key comes before `pts`, else as E and Sig,
+/// and converted from the declared units to MeV and barns.
+struct PointsSeed<'a, 'c, S: ?Sized> {
+    sink: &'a mut S,
+    headers: &'c ColumnHeaders<'c>,
+    units: (EnergyUnit, CrossSectionUnit),
+}
+
+impl<'de, S: PointSink + ?Sized> DeserializeSeed<'de> for ResponseSeed<'_, S> {
+    type Value = f64;
+
+    fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<f64, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, S: PointSink + ?Sized> Visitor<'de> for ResponseSeed<'_, S> {
+    type Value = f64;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("an e4sig response object")
+    }
+
+    fn visit_map<M: MapAccess<'de>>(self, mut map: M) -> Result<f64, M::Error> {
+        let mut temp = 0.0;
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "datasets" {
+                temp = map.next_value_seed(DatasetsSeed(&mut *self.0))?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(temp)
+    }
+}
+
+impl<'de, S: PointSink + ?Sized> DeserializeSeed<'de> for DatasetsSeed<'_, S> {
+    type Value = f64;
+
+    fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<f64, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, S: PointSink + ?Sized> Visitor<'de> for DatasetsSeed<'_, S> {
+    type Value = f64;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("an array of datasets")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<f64, A::Error> {
+        let temp = seq
+            .next_element_seed(DatasetSeed(&mut *self.0))?
+            .ok_or_else(|| serde::de::Error::custom("No dataset found in API response"))?;
+        while seq.next_element::<IgnoredAny>()?.is_some() {}
+        Ok(temp)
+    }
+}
+
+impl<'de, S: PointSink + ?Sized> DeserializeSeed<'de> for DatasetSeed<'_, S> {
+    type Value = f64;
+
+    fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<f64, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, S: PointSink + ?Sized> Visitor<'de> for DatasetSeed<'_, S> {
+    type Value = f64;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a dataset object")
+    }
+
+    fn visit_map<M: MapAccess<'de>>(self, mut map: M) -> Result<f64, M::Error> {
+        let mut temp = 0.0;
+        let mut columns: Vec<String> = Vec::new();
+        let mut id = String::new();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "COLUMNS" | "columns" => columns = map.next_value()?,
+                "id" => id = map.next_value()?,
+                "pts" | "points" => {
+                    let headers =
+                        ColumnHeaders::find(&columns).map_err(serde::de::Error::custom)?;
+                    let units = point_units(&columns, &id).map_err(serde::de::Error::custom)?;
+                    map.next_value_seed(PointsSeed {
+                        sink: &mut *self.0,
+                        headers: &headers,
+                        units,
+                    })?
+                }
+                "TEMP" | "temp" => {
+                    temp = map
+                        .next_value::<NumberOrText<f64>>()?
+                        .into_number("temperature")?
+                }
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(temp)
+    }
+}
+
+impl<'de, S: PointSink + ?Sized> DeserializeSeed<'de> for PointsSeed<'_, '_, S> {
+    type Value = ();
+
+    fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, S: PointSink + ?Sized> Visitor<'de> for PointsSeed<'_, '_, S> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("an array of points")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(point) = seq.next_element::<RawPoint>()? {
+            let value = |header: &str| {
+                raw_value(&point, header)?.ok_or_else(|| format!("A point has no {} value", header))
+            };
+            let energy = value(self.headers.energy).map_err(serde::de::Error::custom)?;
+            let cross_section =
+                value(self.headers.cross_section).map_err(serde::de::Error::custom)?;
+            let (energy_unit, cross_section_unit) = self.units;
+            self.sink.push(
+                energy_unit.to_mev(energy),
+                cross_section_unit.to_barns(cross_section),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Parses a saved or downloading e4sig response, pushing the points of its
+/// first dataset into a sink as they are read
+///
+/// The points are never collected, so memory use does not grow with the
+/// dataset; [`stream_cross_section`] runs it over the download.
+///
+/// # Returns
+/// * `Ok(temp)` - The dataset's processing temperature, once all points were
+///   pushed
+/// * `Err` - If the response is malformed or holds no dataset
+pub fn stream_points<R: Read, S: PointSink + ?Sized>(
+    reader: R,
+    sink: &mut S,
+) -> serde_json::Result<f64> {
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    ResponseSeed(sink).deserialize(&mut deserializer)
+}
+
+/// Streams the cross section points of a dataset into a sink
+///
+/// Unlike [`fetch_cross_section`], the response body is parsed while it is
+/// downloaded and the points are never collected into a vector, so peak
+/// memory stays bounded regardless of the dataset size. Only the first
+/// dataset of the response is streamed.
+///
+/// # Arguments
+/// * `target` - Target nucleus (e.g., "Mo-94")
+/// * `reaction` - Reaction type (e.g., "n,g")
+/// * `quantity` - EXFOR quantity code (e.g., "SIG")
+/// * `lib_name` - Nuclear data library name (e.g., "JEFF-4.0")
+/// * `eval_id` - Evaluation to use, or `None` for the library's newest
+/// * `sink` - Receiver of the points
+///
+/// # Returns
+/// * `Ok(temp)` - The dataset's processing temperature, once all points were
+///   pushed
+/// * `Err` - Error if no data found, network error or malformed response
+pub async fn stream_cross_section(
+    target: &str,
+    reaction: &str,
+    quantity: &str,
+    lib_name: &str,
+    eval_id: Option<u32>,
+    sink: &mut dyn PointSink,
+) -> Result<f64, Box<dyn std::error::Error>> {
+    let section = find_section(target, reaction, quantity, lib_name, eval_id).await?;
+    let mut response = client()
+        .get(section_data_url(&section)?)
+        .send()
         .await?
-        .json::<CrossSectionResponse>()
-        .await?;
-    Ok(cross_section_data)
+        .error_for_status()?;
+
+    let (sender, chunks) = tokio::sync::mpsc::channel(STREAM_BUFFER_CHUNKS);
+    let (batch_sender, mut batches) = tokio::sync::mpsc::channel(STREAM_BUFFER_CHUNKS);
+    let parser = tokio::task::spawn_blocking(move || {
+        let mut batcher = BatchSender {
+            batch: Vec::with_capacity(STREAM_BATCH_POINTS),
+            sender: batch_sender,
+        };
+        let reader = std::io::BufReader::new(ChannelReader {
+            chunks,
+            current: Vec::new(),
+            offset: 0,
+        });
+        let temp = stream_points(reader, &mut batcher).map_err(|e| e.to_string())?;
+        batcher.flush();
+        Ok::<_, String>(temp)
+    });
+
+    let download = async move {
+        while let Some(chunk) = response.chunk().await? {
+            // The parser hangs up early only when it failed; its error is reported below
+            if sender.send(chunk.to_vec()).await.is_err() {
+                break;
+            }
+        }
+        Ok::<_, reqwest::Error>(())
+    };
+    let receive = async {
+        while let Some(batch) = batches.recv().await {
+            for (energy, cross_section) in batch {
+                sink.push(energy, cross_section);
+            }
+        }
+    };
+    let (downloaded, ()) = tokio::join!(download, receive);
+    downloaded?;
+
+    Ok(parser.await??)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sink collecting the streamed points
+    struct Collect(Vec<(f64, f64)>);
+
+    impl PointSink for Collect {
+        fn push(&mut self, energy: f64, cross_section: f64) {
+            self.0.push((energy, cross_section));
+        }
+    }
+
+    fn dataset_at(id: &str, temp: f64) -> CrossSectionDataset {
+        CrossSectionDataset {
+            id: id.to_string(),
+            file: String::new(),
+            data_type: String::new(),
+            library: "JEFF-3.1".to_string(),
+            target: "Mo-94".to_string(),
+            temp,
+            nsub: 0,
+            mat: 0,
+            mf: 3,
+            mt: 102,
+            reaction: "n,g".to_string(),
+            columns: Vec::new(),
+            default_interpolation: String::new(),
+            n_pts: 0,
+            points: Vec::new(),
+        }
+    }
+
+    fn response(datasets: Vec<CrossSectionDataset>) -> CrossSectionResponse {
+        CrossSectionResponse {
+            format: String::new(),
+            now: String::new(),
+            program: String::new(),
+            datasets,
+            library: None,
+            angle_integrated: false,
+        }
+    }
+
+    fn listing(sections: &str) -> E4Response {
+        serde_json::from_str(&format!(r#"{{"sections": [{}]}}"#, sections)).unwrap()
+    }
+
+    const MO94_SECTION: &str = r#"{"Targ": "Mo-94", "ZT": 42, "AT": 94, "MT": 102,
+        "EvalID": 1, "SectID": 10, "PenSectID": 11, "LibName": "JEFF-3.1", "DATE": "2005"}"#;
+
+    #[tokio::test]
+    async fn empty_listing_falls_back_to_an_alias() {
+        let queried = Mutex::new(Vec::new());
+        let response = list_with_aliases("n,g", |reaction| {
+            queried.lock().unwrap().push(reaction.clone());
+            async move {
+                Ok(if reaction == "(N,G)" {
+                    listing(MO94_SECTION)
+                } else {
+                    listing("")
+                })
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(response.sections.len(), 1);
+        assert_eq!(response.sections[0].lib_name, "JEFF-3.1");
+        let queried = queried.into_inner().unwrap();
+        assert_eq!(queried.first().map(String::as_str), Some("n,g"));
+        assert_eq!(queried.last().map(String::as_str), Some("(N,G)"));
+    }
+
+    #[tokio::test]
+    async fn listing_without_aliases_stays_empty() {
+        let response = list_with_aliases("n,g", |_| async { Ok(listing("")) })
+            .await
+            .unwrap();
+        assert!(response.sections.is_empty());
+    }
+
+    const THREE_COLUMNS: &str = r#"{"datasets": [{"id": "1", "TEMP": 293.6,
+        "COLUMNS": ["E(eV)", "Sig(b)", "dSig(b)"],
+        "pts": [{"E(eV)": 1000.0, "Sig(b)": 2.0, "dSig(b)": 0.1},
+                {"E": 2000.0, "Sig": 1.5, "dSig": 0.05}]}]}"#;
+
+    fn evaluation(eval_id: u32, date: &str, sect_id: u32) -> String {
+        format!(
+            r#"{{"Targ": "Mo-94", "ZT": 42, "AT": 94, "MT": 102, "EvalID": {}, "DATE": "{}",
+                "SectID": {}, "PenSectID": {}, "LibName": "JEFF-3.1"}}"#,
+            eval_id,
+            date,
+            sect_id,
+            sect_id + 1
+        )
+    }
+
+    fn select(sections: &[String], eval_id: Option<u32>) -> Result<Section, String> {
+        let response = listing(&sections.join(", "));
+        section_from_listing(response, "Mo-94", "n,g", "JEFF-3.1", eval_id)
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn newest_evaluation_is_selected_by_date() {
+        let sections = [evaluation(7, "2005-03", 10), evaluation(3, "2011-11", 20)];
+        let section = select(&sections, None).unwrap();
+        assert_eq!((section.eval_id, section.sect_id), (3, 20));
+    }
+
+    #[test]
+    fn same_date_prefers_the_larger_evaluation_id() {
+        let sections = [evaluation(4, "2011-11", 10), evaluation(5, "2011-11", 20)];
+        assert_eq!(select(&sections, None).unwrap().eval_id, 5);
+    }
+
+    #[test]
+    fn pinned_evaluation_overrides_the_date() {
+        let sections = [evaluation(7, "2005-03", 10), evaluation(3, "2011-11", 20)];
+        assert_eq!(select(&sections, Some(7)).unwrap().sect_id, 10);
+        let error = select(&sections, Some(9)).unwrap_err();
+        assert!(error.contains("Evaluation 9 not found"), "{}", error);
+    }
+
+    #[test]
+    fn three_column_dataset_keeps_the_uncertainty() {
+        let response: CrossSectionResponse = serde_json::from_str(THREE_COLUMNS).unwrap();
+        let points = &response.datasets[0].points;
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].uncertainty, Some(0.1));
+        assert_eq!(points[1].uncertainty, Some(0.05));
+
+        let (energies, cross_sections) = response.datasets[0].points_in_mev_barns().unwrap();
+        assert_eq!(energies, vec![1e-3, 2e-3]);
+        assert_eq!(cross_sections, vec![2.0, 1.5]);
+    }
+
+    #[test]
+    fn three_column_dataset_round_trips_by_its_headers() {
+        let response: CrossSectionResponse = serde_json::from_str(THREE_COLUMNS).unwrap();
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["datasets"][0]["points"][1]["dSig(b)"], 0.05);
+
+        let again: CrossSectionResponse = serde_json::from_value(json).unwrap();
+        assert_eq!(again.datasets[0].points[1].uncertainty, Some(0.05));
+    }
+
+    #[test]
+    fn three_column_dataset_streams_in_mev() {
+        let mut sink = Collect(Vec::new());
+        let temp = stream_points(THREE_COLUMNS.as_bytes(), &mut sink).unwrap();
+        assert_eq!(temp, 293.6);
+        assert_eq!(sink.0, vec![(1e-3, 2.0), (2e-3, 1.5)]);
+    }
+
+    #[test]
+    fn streamed_points_are_converted_from_the_header_units() {
+        let declared = r#"{"datasets": [{"id": "2", "TEMP": 0.0,
+            "COLUMNS": ["E(MeV)", "Sig(mb)"],
+            "pts": [{"E": 1.0, "Sig": 500.0}, {"E": 2.0, "Sig": 250.0}]}]}"#;
+        let mut sink = Collect(Vec::new());
+        stream_points(declared.as_bytes(), &mut sink).unwrap();
+        assert_eq!(sink.0, vec![(1.0, 0.5), (2.0, 0.25)]);
+
+        let response: CrossSectionResponse = serde_json::from_str(declared).unwrap();
+        let (energies, cross_sections) = response.datasets[0].points_in_mev_barns().unwrap();
+        let full: Vec<(f64, f64)> = energies.into_iter().zip(cross_sections).collect();
+        assert_eq!(sink.0, full);
+
+        let unknown = declared.replace("Sig(mb)", "Sig(furlong)");
+        let err = stream_points(unknown.as_bytes(), &mut Collect(Vec::new())).unwrap_err();
+        assert!(err.to_string().contains("'furlong'"), "{}", err);
+    }
+
+    #[test]
+    fn broadened_dataset_is_the_closest_temperature() {
+        let response = response(vec![
+            dataset_at("hot", 3000.0),
+            dataset_at("room", 293.6),
+            dataset_at("cold", 0.0),
+        ]);
+        let room_kev = units::kelvin_to_kev(293.6);
+        assert_eq!(response.dataset_broadened_for(room_kev), Some(1));
+        assert_eq!(
+            response.dataset_broadened_for(units::kelvin_to_kev(100.0)),
+            Some(2)
+        );
+        assert_eq!(
+            response.dataset_broadened_for(units::kelvin_to_kev(2000.0)),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn stellar_temperatures_pick_the_hottest_dataset() {
+        let response = response(vec![dataset_at("room", 293.6), dataset_at("hot", 3000.0)]);
+        for kt in [1.0, 30.0, 100.0] {
+            assert_eq!(response.dataset_broadened_for(kt), Some(1));
+        }
+    }
+
+    #[test]
+    fn broadened_dataset_needs_a_dataset() {
+        assert_eq!(response(Vec::new()).dataset_broadened_for(30.0), None);
+    }
+
+    fn libraries(names: &[&str]) -> E4Response {
+        let sections: Vec<String> = names
+            .iter()
+            .map(|name| MO94_SECTION.replace("JEFF-3.1", name))
+            .collect();
+        listing(&sections.join(","))
+    }
+
+    #[test]
+    fn library_names_match_ignoring_case_and_punctuation() {
+        for variant in [
+            "endf-b-viii.1",
+            "ENDF/B-VIII.1",
+            "endf b viii 1",
+            "EndfBVIII1",
+        ] {
+            let filtered =
+                filter_by_library(libraries(&["JEFF-3.1", "ENDF-B-VIII.1"]), variant).unwrap();
+            assert_eq!(filtered.sections.len(), 1);
+            assert_eq!(filtered.sections[0].lib_name, "ENDF-B-VIII.1");
+        }
+    }
+
+    #[test]
+    fn exact_library_name_wins_over_a_variant() {
+        let filtered = filter_by_library(libraries(&["JEFF-3.1", "jeff 3.1"]), "JEFF-3.1").unwrap();
+        assert_eq!(filtered.sections.len(), 1);
+        assert_eq!(filtered.sections[0].lib_name, "JEFF-3.1");
+    }
+
+    #[test]
+    fn unknown_library_lists_the_available_ones() {
+        let err =
+            filter_by_library(libraries(&["JEFF-3.1", "TENDL-2019"]), "JEFF-3.2").unwrap_err();
+        assert!(err.contains("available: JEFF-3.1, TENDL-2019"), "{}", err);
+        assert!(err.contains("did you mean"), "{}", err);
+    }
+
+    #[test]
+    fn section_ids_may_be_strings() {
+        let quoted = MO94_SECTION
+            .replace(r#""SectID": 10"#, r#""SectID": "10""#)
+            .replace(r#""PenSectID": 11"#, r#""PenSectID": " 11 ""#);
+        let section = &listing(&quoted).sections[0];
+        assert_eq!((section.sect_id, section.pen_sect_id), (10, 11));
+        assert_eq!(
+            section_data_url(section).unwrap(),
+            "https://www-nds.iaea.org/exfor/e4sig?SectID=10&PenSectID=11&json"
+        );
+    }
+
+    #[test]
+    fn malformed_section_ids_fail_to_parse() {
+        let text = MO94_SECTION.replace(r#""SectID": 10"#, r#""SectID": "ten""#);
+        let json = format!(r#"{{"sections": [{}]}}"#, text);
+        assert!(serde_json::from_str::<E4Response>(&json).is_err());
+    }
+
+    #[test]
+    fn missing_or_zero_section_ids_are_rejected() {
+        let missing = MO94_SECTION.replace(r#""SectID": 10, "#, "");
+        let err = section_data_url(&listing(&missing).sections[0]).unwrap_err();
+        assert!(err.contains("no valid SectID"), "{}", err);
+
+        let zero = MO94_SECTION.replace(r#""PenSectID": 11"#, r#""PenSectID": "0""#);
+        let err = section_data_url(&listing(&zero).sections[0]).unwrap_err();
+        assert!(err.contains("no valid PenSectID"), "{}", err);
+    }
+
+    /// A one-point dataset, E = 30 and σ = 250 in the units of its headers
+    fn with_columns(columns: &[&str]) -> CrossSectionDataset {
+        let (energy, cross_section) = (columns[0], columns[1]);
+        let json = format!(
+            r#"{{"datasets": [{{"id": "1", "COLUMNS": {:?}, "pts": [{{"{}": 30.0, "{}": 250.0}}]}}]}}"#,
+            columns, energy, cross_section
+        );
+        let mut response: CrossSectionResponse = serde_json::from_str(&json).unwrap();
+        response.datasets.remove(0)
+    }
+
+    #[test]
+    fn column_headers_declare_the_units() {
+        let dataset = with_columns(&["E(keV)", "Sig(mb)"]);
+        assert_eq!(
+            dataset.column_units().unwrap(),
+            (Some(EnergyUnit::Kev), Some(CrossSectionUnit::Millibarn))
+        );
+        assert_eq!(
+            dataset.points_in_mev_barns().unwrap(),
+            (vec![0.03], vec![0.25])
+        );
+
+        let dataset = with_columns(&["E (MeV)", "Sig ( b )"]);
+        assert_eq!(
+            dataset.points_in_mev_barns().unwrap(),
+            (vec![30.0], vec![250.0])
+        );
+    }
+
+    #[test]
+    fn headers_without_units_default_to_ev_and_barns() {
+        let dataset = with_columns(&["E", "Sig"]);
+        assert_eq!(dataset.column_units().unwrap(), (None, None));
+        assert_eq!(
+            dataset.points_in_mev_barns().unwrap(),
+            (vec![units::ev_to_mev(30.0)], vec![250.0])
+        );
+
+        // A unit on one column only leaves the other at its default
+        let dataset = with_columns(&["E(keV)", "Sig"]);
+        assert_eq!(
+            dataset.points_in_mev_barns().unwrap(),
+            (vec![0.03], vec![250.0])
+        );
+    }
+
+    #[test]
+    fn unknown_column_units_are_rejected() {
+        let err = with_columns(&["E(furlong)", "Sig(b)"])
+            .column_units()
+            .unwrap_err();
+        assert!(err.contains("'furlong' in column 'E(furlong)'"), "{}", err);
+    }
+
+    #[test]
+    fn section_with_only_empty_datasets_has_no_data() {
+        let section = &listing(MO94_SECTION).sections[0];
+        let empty: CrossSectionResponse = serde_json::from_str(
+            r#"{"datasets": [{"id": "1", "pts": []}, {"id": "2", "pts": []}]}"#,
+        )
+        .unwrap();
+        let err = section_data(section, empty).unwrap_err();
+        assert_eq!(
+            err,
+            "JEFF-3.1 has a section for Mo-94 (MT=102) but no cross section data: 2 dataset(s) without points"
+        );
+        assert!(section_data(section, response(Vec::new())).is_err());
+    }
+
+    #[test]
+    fn section_data_is_labelled_with_its_library() {
+        let section = &listing(MO94_SECTION).sections[0];
+        let data: CrossSectionResponse = serde_json::from_str(
+            r#"{"datasets": [{"id": "1", "pts": []}, {"id": "2", "pts": [{"E": 1.0, "Sig": 2.0}]}]}"#,
+        )
+        .unwrap();
+        let labelled = section_data(section, data).unwrap();
+        assert_eq!(labelled.library.as_deref(), Some("JEFF-3.1"));
+        assert_eq!(labelled.datasets.len(), 2);
+    }
+
+    /// Serves `body` as JSON to a single request on a local port
+    ///
+    /// Returns the URL to request and a handle yielding the request head as
+    /// received, header names in lowercase.
+    fn serve_once(body: &'static str) -> (String, std::thread::JoinHandle<String>) {
+        use std::io::{BufRead, Write};
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/e4sig?json", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+            let mut head = String::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                head.push_str(&line.to_ascii_lowercase());
+            }
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+            head
+        });
+        (url, handle)
+    }
+
+    #[tokio::test]
+    async fn requests_identify_the_tool() {
+        let (url, server) = serve_once(r#"{"sections": []}"#);
+        let listing: E4Response = get_json(&url).await.unwrap();
+        assert!(listing.sections.is_empty());
+        let head = server.join().unwrap();
+        let expected = format!("user-agent: {}\r\n", USER_AGENT.to_ascii_lowercase());
+        assert!(head.contains(&expected), "{}", head);
+        assert!(USER_AGENT.starts_with("macs-rs/"));
+    }
+
+    #[tokio::test]
+    async fn contact_is_sent_as_the_from_header() {
+        let (url, server) = serve_once("{}");
+        let client = build_client(Some("someone@example.org")).unwrap();
+        client.get(&url).send().await.unwrap();
+        let head = server.join().unwrap();
+        assert!(head.contains("from: someone@example.org\r\n"), "{}", head);
+        assert!(head.contains("user-agent: macs-rs/"), "{}", head);
+    }
+
+    #[test]
+    fn contact_must_be_a_valid_header_value() {
+        assert!(build_client(Some("someone@example.org\nX-Injected: 1")).is_err());
+    }
+
+    const QUOTED_NUMBERS: &str = r#"{"datasets": [{"id": "1", "TEMP": "293.6", "MT": "102",
+        "nPts": " 2 ", "COLUMNS": ["E(eV)", "Sig(b)", "dSig(b)"],
+        "pts": [{"E": "1.0e3", "Sig": " 2.5E-1 ", "dSig": "0.01"},
+                {"E": "2000", "Sig": 0.125, "dSig": null}]}]}"#;
+
+    #[test]
+    fn quoted_numbers_are_parsed() {
+        let response: CrossSectionResponse = serde_json::from_str(QUOTED_NUMBERS).unwrap();
+        let dataset = &response.datasets[0];
+        assert_eq!((dataset.temp, dataset.mt, dataset.n_pts), (293.6, 102, 2));
+        assert_eq!(dataset.points[0].uncertainty, Some(0.01));
+        assert_eq!(dataset.points[1].uncertainty, None);
+        assert_eq!(
+            dataset.points_in_mev_barns().unwrap(),
+            (vec![1e-3, 2e-3], vec![0.25, 0.125])
+        );
+    }
+
+    #[test]
+    fn quoted_numbers_stream_like_plain_ones() {
+        let mut sink = Collect(Vec::new());
+        let temp = stream_points(QUOTED_NUMBERS.as_bytes(), &mut sink).unwrap();
+        assert_eq!(temp, 293.6);
+        assert_eq!(sink.0, vec![(1e-3, 0.25), (2e-3, 0.125)]);
+    }
+
+    #[test]
+    fn malformed_quoted_numbers_name_the_value() {
+        let bad_point = QUOTED_NUMBERS.replace(r#""1.0e3""#, r#""1.0e3 eV""#);
+        let err = serde_json::from_str::<CrossSectionResponse>(&bad_point).unwrap_err();
+        assert!(err.to_string().contains("'1.0e3 eV'"), "{}", err);
+
+        let bad_field = QUOTED_NUMBERS.replace(r#""102""#, r#""n,g""#);
+        let err = serde_json::from_str::<CrossSectionResponse>(&bad_field).unwrap_err();
+        assert!(
+            err.to_string().contains("'n,g' is not a valid number"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn section_without_ids_falls_back_to_the_next() {
+        // The newest evaluation lacks its IDs: one is zero, the other absent
+        let zero = evaluation(9, "2020-01", 0);
+        let absent = evaluation(8, "2015-06", 30).replace(r#""PenSectID": 31, "#, "");
+        let sections = [zero, absent, evaluation(3, "2011-11", 20)];
+        let section = select(&sections, None).unwrap();
+        assert_eq!(
+            (section.eval_id, section.sect_id, section.pen_sect_id),
+            (3, 20, 21)
+        );
+        assert_eq!(
+            section_data_url(&section).unwrap(),
+            "https://www-nds.iaea.org/exfor/e4sig?SectID=20&PenSectID=21&json"
+        );
+    }
+
+    #[test]
+    fn no_section_with_ids_is_an_error() {
+        let sections = [evaluation(9, "2020-01", 0), evaluation(3, "2011-11", 0)];
+        let err = select(&sections, None).unwrap_err();
+        assert!(
+            err.starts_with("None of the 2 matching section(s) of Mo-94 in JEFF-3.1"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn section_without_optional_metadata_parses() {
+        let section = &listing(r#"{"ZT": 42, "AT": 94, "LibName": "JEFF-3.1"}"#).sections[0];
+        assert_eq!((section.z, section.a), (42, 94));
+        assert_eq!((section.sect_id, section.mt), (0, 0));
+        assert!(section.auth.is_empty() && section.rc.is_empty() && section.date.is_empty());
+
+        let unnamed = r#"{"sections": [{"ZT": 42, "AT": 94}]}"#;
+        assert!(serde_json::from_str::<E4Response>(unnamed).is_err());
+    }
+
+    #[test]
+    fn dataset_without_optional_metadata_parses() {
+        let response: CrossSectionResponse =
+            serde_json::from_str(r#"{"datasets": [{"id": "1", "pts": [{"E": 1e3, "Sig": 2}]}]}"#)
+                .unwrap();
+        let dataset = &response.datasets[0];
+        assert_eq!(dataset.default_interpolation, "Lin-Lin");
+        assert!(dataset.columns.is_empty() && dataset.library.is_empty());
+        assert_eq!((dataset.temp, dataset.mt), (0.0, 0));
+        assert_eq!(
+            dataset.points_in_mev_barns().unwrap(),
+            (vec![1e-3], vec![2.0])
+        );
+        assert!(response.format.is_empty() && response.library.is_none());
+    }
+
+    #[test]
+    fn dataset_still_needs_its_id_and_points() {
+        for json in [
+            r#"{"datasets": [{"pts": [{"E": 1e3, "Sig": 2}]}]}"#,
+            r#"{"datasets": [{"id": "1"}]}"#,
+            r#"{"datasets": [{"id": "1", "pts": [{"E": 1e3}]}]}"#,
+        ] {
+            assert!(
+                serde_json::from_str::<CrossSectionResponse>(json).is_err(),
+                "{}",
+                json
+            );
+        }
+    }
+
+    #[test]
+    fn dataset_urls_must_point_at_e4sig_unless_overridden() {
+        assert_eq!(
+            dataset_url("https://www-nds.iaea.org/exfor/e4sig?PenSectID=11", false).unwrap(),
+            "https://www-nds.iaea.org/exfor/e4sig?PenSectID=11&json"
+        );
+        assert_eq!(
+            dataset_url(" https://www-nds.iaea.org/exfor/e4sig ", false).unwrap(),
+            "https://www-nds.iaea.org/exfor/e4sig?json"
+        );
+        let elsewhere = "https://example.org/exfor/e4sig?PenSectID=11&json";
+        assert!(dataset_url(elsewhere, false).is_err());
+        assert_eq!(dataset_url(elsewhere, true).unwrap(), elsewhere);
+        assert!(dataset_url("https://www-nds.iaea.org/exfor/x4get?json", false).is_err());
+        assert!(dataset_url("not a url", true).is_err());
+    }
+
+    /// Request for the Mo-94 capture cross section from the URL library
+    fn url_request() -> FetchRequest<'static> {
+        FetchRequest {
+            target: "Mo-94",
+            za: None,
+            reaction: "n,g",
+            quantity: "SIG",
+            lib_name: URL_LIBRARY,
+            eval_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn url_source_downloads_the_given_dataset() {
+        let (url, server) = serve_once(
+            r#"{"datasets": [{"id": "7", "LIBRARY": "JEFF-3.1", "TARGET": "Mo-94", "MT": 102,
+                "COLUMNS": ["E(eV)", "Sig(b)"],
+                "pts": [{"E": 1000.0, "Sig": 2.0}, {"E": 2000.0, "Sig": 1.5}]}]}"#,
+        );
+        let source = UrlSource { url };
+        let response = source.fetch(url_request()).await.unwrap();
+        assert!(server.join().unwrap().starts_with("get /e4sig?json "));
+        assert_eq!(response.library.as_deref(), Some("JEFF-3.1"));
+        assert_eq!(response.datasets[0].id, "7");
+        assert_eq!(response.datasets[0].points.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn url_datasets_without_library_get_the_url_label() {
+        let (url, server) = serve_once(THREE_COLUMNS);
+        let response = UrlSource { url }.fetch(url_request()).await.unwrap();
+        server.join().unwrap();
+        assert_eq!(response.library.as_deref(), Some(URL_LIBRARY));
+    }
+
+    #[tokio::test]
+    async fn url_without_points_is_an_error() {
+        let (url, server) = serve_once(r#"{"datasets": [{"id": "1", "pts": []}]}"#);
+        let error = UrlSource { url }
+            .fetch(url_request())
+            .await
+            .unwrap_err()
+            .to_string();
+        server.join().unwrap();
+        assert!(error.contains("holds no cross section data"), "{}", error);
+    }
 }
@@ -0,0 +1,219 @@
+//! Analytic fits of the MACS as a function of temperature
+//!
+//! Reaction network codes take the MACS as a smooth function of kT rather
+//! than a table. The computed points are fitted by least squares with a
+//! polynomial in the logarithms:
+//!
+//! ln(MACS / mb) = c₀ + c₁·x + c₂·x² + ... with x = ln(kT / 30 keV)
+//!
+//! A cross section σ ∝ E^p gives MACS ∝ (kT)^p, a straight line in this form,
+//! so low degrees already describe most curves. Centering x on the reference
+//! temperature keeps the fit well conditioned and makes e^c₀ the fitted
+//! MACS at 30 keV. No external fitting library is needed.
+
+use crate::macs::{MacsResult, REFERENCE_TEMPERATURE_KEV};
+
+/// Largest polynomial degree accepted for a fit
+pub const MAX_FIT_DEGREE: usize = 6;
+
+/// Least-squares fit of ln(MACS) against ln(kT / 30 keV)
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct MacsFit {
+    /// Polynomial coefficients c₀, c₁, ... in increasing order of degree
+    pub coefficients: Vec<f64>,
+    /// Relative residual (fit - MACS) / MACS at each fitted temperature
+    pub residuals: Vec<(f64, f64)>,
+    /// Root mean square of the relative residuals
+    pub rms_residual: f64,
+}
+
+impl MacsFit {
+    /// Evaluates the fitted MACS in millibarns at a temperature in keV
+    pub fn evaluate(&self, temperature_kev: f64) -> f64 {
+        let x = (temperature_kev / REFERENCE_TEMPERATURE_KEV).ln();
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(0.0, |sum, &c| sum * x + c)
+            .exp()
+    }
+}
+
+/// Solves the linear system `matrix · x = rhs` by Gaussian elimination with
+/// partial pivoting
+///
+/// Returns `None` if the matrix is singular.
+pub fn solve(mut matrix: Vec<Vec<f64>>, mut rhs: Vec<f64>) -> Option<Vec<f64>> {
+    let n = rhs.len();
+    for col in 0..n {
+        let pivot =
+            (col..n).max_by(|&i, &j| matrix[i][col].abs().total_cmp(&matrix[j][col].abs()))?;
+        if matrix[pivot][col].abs() < f64::EPSILON {
+            return None;
+        }
+        matrix.swap(col, pivot);
+        rhs.swap(col, pivot);
+        let (upper, lower) = matrix.split_at_mut(col + 1);
+        let pivot_row = &upper[col];
+        for (row, rhs_offset) in lower.iter_mut().zip(1..) {
+            let factor = row[col] / pivot_row[col];
+            for (value, pivot_value) in row[col..].iter_mut().zip(&pivot_row[col..]) {
+                *value -= factor * pivot_value;
+            }
+            rhs[col + rhs_offset] -= factor * rhs[col];
+        }
+    }
+
+    let mut solution = vec![0.0; n];
+    for row in (0..n).rev() {
+        let known: f64 = (row + 1..n).map(|k| matrix[row][k] * solution[k]).sum();
+        solution[row] = (rhs[row] - known) / matrix[row][row];
+    }
+    Some(solution)
+}
+
+/// Fits the MACS curve of a set of results
+///
+/// # Arguments
+/// * `results` - MACS at several temperatures
+/// * `degree` - Degree of the polynomial in ln(kT / 30 keV), at most
+///   [`MAX_FIT_DEGREE`]
+///
+/// # Returns
+/// * `Ok(fit)` - Coefficients and residuals of the fit
+/// * `Err(msg)` - If there are not more temperatures than coefficients, a
+///   MACS is not positive, or the temperatures do not determine the fit
+///
+/// # Example
+/// ```no_run
+/// # use macs_rs::{fit, macs::MacsTable};
+/// # fn example(table: &MacsTable) -> Result<(), String> {
+/// let fit = fit::fit_macs_curve(&table.results, 2)?;
+/// println!("MACS at 45 keV: {} mb", fit.evaluate(45.0));
+/// # Ok(())
+/// # }
+/// ```
+pub fn fit_macs_curve(results: &[MacsResult], degree: usize) -> Result<MacsFit, String> {
+    if degree > MAX_FIT_DEGREE {
+        return Err(format!(
+            "Fit degree {} exceeds the maximum of {}",
+            degree, MAX_FIT_DEGREE
+        ));
+    }
+    let terms = degree + 1;
+    if results.len() <= terms {
+        return Err(format!(
+            "A degree {} fit needs more than {} temperatures, got {}",
+            degree,
+            terms,
+            results.len()
+        ));
+    }
+    if let Some(result) = results
+        .iter()
+        .find(|r| r.macs_mb <= 0.0 || r.macs_mb.is_nan())
+    {
+        return Err(format!(
+            "Cannot fit the MACS of {} mb at {} keV, it must be positive",
+            result.macs_mb, result.temperature_kev
+        ));
+    }
+
+    let points: Vec<(f64, f64)> = results
+        .iter()
+        .map(|r| {
+            (
+                (r.temperature_kev / REFERENCE_TEMPERATURE_KEV).ln(),
+                r.macs_mb.ln(),
+            )
+        })
+        .collect();
+
+    // Normal equations Σ x^(i+j) c_j = Σ x^i y
+    let mut matrix = vec![vec![0.0; terms]; terms];
+    let mut rhs = vec![0.0; terms];
+    for &(x, y) in &points {
+        for (i, (row, rhs_i)) in matrix.iter_mut().zip(&mut rhs).enumerate() {
+            let xi = x.powi(i as i32);
+            *rhs_i += xi * y;
+            for (j, value) in row.iter_mut().enumerate() {
+                *value += xi * x.powi(j as i32);
+            }
+        }
+    }
+    let coefficients = solve(matrix, rhs)
+        .ok_or("The temperatures do not determine the fit, use more distinct values")?;
+
+    let mut fit = MacsFit {
+        coefficients,
+        residuals: Vec::with_capacity(results.len()),
+        rms_residual: 0.0,
+    };
+    fit.residuals = results
+        .iter()
+        .map(|r| {
+            let residual = (fit.evaluate(r.temperature_kev) - r.macs_mb) / r.macs_mb;
+            (r.temperature_kev, residual)
+        })
+        .collect();
+    fit.rms_residual =
+        (fit.residuals.iter().map(|(_, r)| r * r).sum::<f64>() / fit.residuals.len() as f64).sqrt();
+    Ok(fit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results(curve: impl Fn(f64) -> f64, temperatures: &[f64]) -> Vec<MacsResult> {
+        temperatures
+            .iter()
+            .map(|&kt| {
+                serde_json::from_str(&format!(
+                    r#"{{"temperature_kev": {}, "macs_mb": {}}}"#,
+                    kt,
+                    curve(kt)
+                ))
+                .unwrap()
+            })
+            .collect()
+    }
+
+    const TEMPERATURES: [f64; 8] = [5.0, 8.0, 10.0, 15.0, 20.0, 30.0, 50.0, 100.0];
+
+    #[test]
+    fn fit_recovers_a_known_curve() {
+        // ln MACS = ln 200 - x/2 + x²/10, exactly of the fitted form
+        let curve = |kt: f64| {
+            let x = (kt / 30.0).ln();
+            200.0 * (-0.5 * x + 0.1 * x * x).exp()
+        };
+        let fit = fit_macs_curve(&results(curve, &TEMPERATURES), 2).unwrap();
+        let expected = [200f64.ln(), -0.5, 0.1];
+        for (c, expected) in fit.coefficients.iter().zip(expected) {
+            assert!((c - expected).abs() < 1e-10, "{:?}", fit.coefficients);
+        }
+        assert!(fit.rms_residual < 1e-12);
+        assert!((fit.evaluate(30.0) - 200.0).abs() < 1e-9);
+        assert!((fit.evaluate(45.0) / curve(45.0) - 1.0).abs() < 1e-10);
+    }
+
+    #[test]
+    fn lower_degree_fit_reports_its_residuals() {
+        let curve = |kt: f64| 200.0 * (30.0 / kt).sqrt() * (1.0 + kt / 100.0);
+        let fit = fit_macs_curve(&results(curve, &TEMPERATURES), 1).unwrap();
+        assert_eq!(fit.residuals.len(), TEMPERATURES.len());
+        assert!(fit.rms_residual > 1e-3 && fit.rms_residual < 0.1);
+        let quadratic = fit_macs_curve(&results(curve, &TEMPERATURES), 2).unwrap();
+        assert!(quadratic.rms_residual < fit.rms_residual);
+    }
+
+    #[test]
+    fn fit_needs_enough_positive_points() {
+        let flat = |_: f64| 100.0;
+        assert!(fit_macs_curve(&results(flat, &[10.0, 30.0, 50.0]), 2).is_err());
+        assert!(fit_macs_curve(&results(flat, &TEMPERATURES), MAX_FIT_DEGREE + 1).is_err());
+        assert!(fit_macs_curve(&results(|kt| kt - 10.0, &TEMPERATURES), 1).is_err());
+        assert!(fit_macs_curve(&results(flat, &[30.0; 4]), 1).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+//! Export of library cross sections on a common energy grid
+//!
+//! When several libraries are compared, their cross sections are resampled
+//! onto the union of their energy grids so they can be plotted together.
+
+use crate::interpolation::{Interpolation, interpolate_at};
+use std::io::Write;
+
+/// Cross section of one library as it was integrated
+pub struct Curve {
+    /// Energy points in MeV
+    pub energies: Vec<f64>,
+    /// Cross section values in barns
+    pub cross_sections: Vec<f64>,
+    /// Interpolation law between the points
+    pub law: Box<dyn Interpolation>,
+}
+
+/// Writes the cross sections of several libraries on their common grid as CSV
+///
+/// The grid is the union of all energy points. Each library is resampled with
+/// its own interpolation law; energies outside a library's tabulated range
+/// are left empty.
+///
+/// # Arguments
+/// * `out` - Destination of the CSV
+/// * `curves` - Library names with their cross sections
+pub fn write_common_grid(
+    out: &mut dyn Write,
+    curves: &[(String, Curve)],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut grid: Vec<f64> = curves
+        .iter()
+        .flat_map(|(_, curve)| curve.energies.iter().copied())
+        .collect();
+    grid.sort_by(f64::total_cmp);
+    grid.dedup();
+
+    let mut header = String::from("energy_mev");
+    for (library, _) in curves {
+        header.push(',');
+        header.push_str(library);
+    }
+    writeln!(out, "{}", header)?;
+
+    for &energy in &grid {
+        let mut line = energy.to_string();
+        for (_, curve) in curves {
+            line.push(',');
+            if let Some(value) =
+                interpolate_at(&curve.energies, &curve.cross_sections, &*curve.law, energy)
+            {
+                line.push_str(&value.to_string());
+            }
+        }
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
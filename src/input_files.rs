@@ -0,0 +1,297 @@
+//! Cross sections read from local files instead of the EXFOR API
+//!
+//! A cross section pieced together from several measurements often lives in
+//! separate files, one per energy region. Every file given to `--input` is
+//! read and all their datasets are returned as one response, to be stitched
+//! into a single grid by [`crate::merge::merge_datasets`], whose rules decide
+//! overlapping regions.
+//!
+//! A `.json` file holds a saved API response (`CrossSectionResponse`), with
+//! units declared in its column headers as usual. It may be gzipped
+//! (`.json.gz`, with the `gzip` feature) to keep archived responses compact. Any other file is a
+//! two-column text table of energies in MeV and cross sections in barns, as
+//! read by [`crate::columns::read_two_columns`]. Files may mix the two kinds:
+//! each dataset is converted to MeV and barns from its own units before the
+//! datasets are ordered and stitched.
+
+use crate::columns;
+use crate::exfor_client::{
+    CrossSectionDataset, CrossSectionPoint, CrossSectionResponse, CrossSectionSource, FetchFuture,
+    FetchRequest,
+};
+use std::path::{Path, PathBuf};
+
+/// Library label of results computed from `--input` files
+pub const INPUT_LIBRARY: &str = "input";
+
+/// [`CrossSectionSource`] serving the datasets of local files
+///
+/// Every request gets the same response, whatever target, reaction or
+/// library it names.
+pub struct InputFiles {
+    response: CrossSectionResponse,
+}
+
+/// Reads one two-column text file as a dataset in MeV and barns
+fn read_text_file(path: &Path) -> Result<CrossSectionDataset, Box<dyn std::error::Error>> {
+    let (energies, cross_sections) =
+        columns::read_two_columns(path, "input file", "cross section")?;
+    let points: Vec<CrossSectionPoint> = energies
+        .into_iter()
+        .zip(cross_sections)
+        .map(|(energy, cross_section)| CrossSectionPoint {
+            energy,
+            cross_section,
+            uncertainty: None,
+            extra: Default::default(),
+        })
+        .collect();
+    Ok(CrossSectionDataset {
+        id: path.display().to_string(),
+        file: path.display().to_string(),
+        data_type: "input".to_string(),
+        library: INPUT_LIBRARY.to_string(),
+        target: String::new(),
+        temp: 0.0,
+        nsub: 0,
+        mat: 0,
+        mf: 0,
+        mt: 0,
+        reaction: String::new(),
+        columns: vec!["E(MeV)".to_string(), "Sig(b)".to_string()],
+        default_interpolation: "Lin-Lin".to_string(),
+        n_pts: points.len() as u32,
+        points,
+    })
+}
+
+/// First bytes of a gzip stream
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Whether a path has the given extension, ignoring case
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case(extension))
+}
+
+/// Decompresses the content of a gzipped file
+#[cfg(feature = "gzip")]
+fn gunzip(path: &Path, bytes: &[u8]) -> Result<Vec<u8>, String> {
+    use std::io::Read;
+    let mut content = Vec::new();
+    flate2::read::GzDecoder::new(bytes)
+        .read_to_end(&mut content)
+        .map_err(|e| format!("Cannot decompress input file {}: {}", path.display(), e))?;
+    Ok(content)
+}
+
+#[cfg(not(feature = "gzip"))]
+fn gunzip(path: &Path, _bytes: &[u8]) -> Result<Vec<u8>, String> {
+    Err(format!(
+        "Input file {} is gzipped, but this build lacks the gzip feature",
+        path.display()
+    ))
+}
+
+/// Reads a saved API response, decompressing it if it is gzipped
+///
+/// Compression is recognized by the `.gz` extension or the gzip magic bytes.
+fn read_response(path: &Path) -> Result<CrossSectionResponse, String> {
+    let bytes = std::fs::read(path)
+        .map_err(|e| format!("Cannot read input file {}: {}", path.display(), e))?;
+    let content = if has_extension(path, "gz") || bytes.starts_with(&GZIP_MAGIC) {
+        gunzip(path, &bytes)?
+    } else {
+        bytes
+    };
+    serde_json::from_slice(&content)
+        .map_err(|e| format!("Cannot parse input file {}: {}", path.display(), e))
+}
+
+/// Reads the datasets of one file, chosen by its extension
+///
+/// `.json` and `.json.gz` files are saved responses, anything else a text table.
+fn read_file(path: &Path) -> Result<Vec<CrossSectionDataset>, Box<dyn std::error::Error>> {
+    let inner = path.file_stem().map(Path::new);
+    if has_extension(path, "json")
+        || (has_extension(path, "gz") && inner.is_some_and(|p| has_extension(p, "json")))
+    {
+        Ok(read_response(path)?.datasets)
+    } else if has_extension(path, "gz") {
+        Err(format!(
+            "Input file {} is gzipped; only JSON responses (.json.gz) can be compressed",
+            path.display()
+        )
+        .into())
+    } else {
+        Ok(vec![read_text_file(path)?])
+    }
+}
+
+impl InputFiles {
+    /// Reads every file and checks that each declares recognized units
+    ///
+    /// # Returns
+    /// * `Ok(files)` - The datasets of all files, in the order given
+    /// * `Err` - If a file cannot be read, holds no point, or a unit of its
+    ///   column headers is not recognized
+    pub fn load(paths: &[PathBuf]) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut datasets = Vec::new();
+        for path in paths {
+            for dataset in read_file(path)? {
+                if dataset.points.is_empty() {
+                    return Err(format!(
+                        "Dataset {} in {} has no points",
+                        dataset.id,
+                        path.display()
+                    )
+                    .into());
+                }
+                dataset.column_units()?;
+                datasets.push(dataset);
+            }
+        }
+        Ok(InputFiles {
+            response: CrossSectionResponse {
+                format: "input".to_string(),
+                now: String::new(),
+                program: env!("CARGO_PKG_NAME").to_string(),
+                datasets,
+                library: None,
+                angle_integrated: false,
+            },
+        })
+    }
+}
+
+impl CrossSectionSource for InputFiles {
+    fn fetch<'a>(&'a self, _request: FetchRequest<'a>) -> FetchFuture<'a> {
+        Box::pin(async move { Ok(self.response.clone()) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merge::merge_datasets;
+
+    /// Writes `content` to a file of the temporary directory unique to this
+    /// test run
+    fn write_temp(name: &str, content: &[u8]) -> PathBuf {
+        static COUNT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "macs-rs-{}-{}-{}",
+            std::process::id(),
+            COUNT.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
+            name
+        ));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn load(files: &[(&str, &[u8])]) -> Result<InputFiles, Box<dyn std::error::Error>> {
+        let paths: Vec<PathBuf> = files
+            .iter()
+            .map(|(name, content)| write_temp(name, content))
+            .collect();
+        let loaded = InputFiles::load(&paths);
+        for path in &paths {
+            std::fs::remove_file(path).unwrap();
+        }
+        loaded
+    }
+
+    #[test]
+    fn adjacent_files_merge_into_one_grid() {
+        // Given high range first; the shared 0.1 MeV point is kept once
+        let files = load(&[
+            ("high.txt", b"# E(MeV) Sig(b)\n0.1 2.0\n1.0 1.0\n10.0 0.5\n"),
+            ("low.txt", b"0.001 8.0\n0.01, 4.0\n0.1 2.0\n"),
+        ])
+        .unwrap();
+        assert_eq!(files.response.datasets.len(), 2);
+
+        let merged = merge_datasets(&files.response.datasets).unwrap();
+        assert_eq!(merged.energies, vec![0.001, 0.01, 0.1, 1.0, 10.0]);
+        assert_eq!(merged.cross_sections, vec![8.0, 4.0, 2.0, 1.0, 0.5]);
+        assert_eq!(merged.ranges.len(), 2);
+        assert_eq!(merged.ranges[0].source, 1);
+    }
+
+    #[test]
+    fn saved_response_and_text_file_mix_their_units() {
+        let json = br#"{"datasets": [{"id": "low", "COLUMNS": ["E(eV)", "Sig(mb)"],
+            "pts": [{"E": 1000.0, "Sig": 4000.0}, {"E": 10000.0, "Sig": 2000.0}]}]}"#;
+        let files = load(&[("low.json", json), ("high.dat", b"0.1 1.0\n1.0 0.5\n")]).unwrap();
+        let merged = merge_datasets(&files.response.datasets).unwrap();
+        assert_eq!(merged.energies, vec![1e-3, 1e-2, 0.1, 1.0]);
+        assert_eq!(merged.cross_sections, vec![4.0, 2.0, 1.0, 0.5]);
+    }
+
+    #[test]
+    fn unreadable_units_and_empty_datasets_are_rejected() {
+        let unknown = br#"{"datasets": [{"id": "1", "COLUMNS": ["E(parsec)", "Sig(b)"],
+            "pts": [{"E": 1.0, "Sig": 1.0}]}]}"#;
+        let err = load(&[("unknown.json", unknown)]).err().unwrap();
+        assert!(err.to_string().contains("'parsec'"), "{}", err);
+
+        let empty = br#"{"datasets": [{"id": "1", "pts": []}]}"#;
+        let err = load(&[("empty.json", empty)]).err().unwrap();
+        assert!(err.to_string().contains("has no points"), "{}", err);
+    }
+
+    const SAVED_RESPONSE: &[u8] = br#"{"datasets": [{"id": "1", "COLUMNS": ["E(eV)", "Sig(b)"],
+        "pts": [{"E": 1000.0, "Sig": 2.0}, {"E": 2000.0, "Sig": 1.5}]}]}"#;
+
+    #[cfg(feature = "gzip")]
+    fn gzip(content: &[u8]) -> Vec<u8> {
+        use std::io::Write;
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(content).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn points(files: &InputFiles) -> (Vec<f64>, Vec<f64>) {
+        files.response.datasets[0].points_in_mev_barns().unwrap()
+    }
+
+    #[test]
+    fn plain_response_is_read() {
+        let plain = load(&[("plain.json", SAVED_RESPONSE)]).unwrap();
+        assert_eq!(points(&plain), (vec![1e-3, 2e-3], vec![2.0, 1.5]));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn gzipped_response_reads_like_the_plain_one() {
+        let compressed = gzip(SAVED_RESPONSE);
+        let plain = load(&[("plain.json", SAVED_RESPONSE)]).unwrap();
+
+        // Recognized by the extension, or by the magic bytes alone
+        for name in ["compressed.json.gz", "compressed-misnamed.json"] {
+            let gzipped = load(&[(name, &compressed)]).unwrap();
+            assert_eq!(points(&gzipped), points(&plain), "{}", name);
+        }
+    }
+
+    #[cfg(not(feature = "gzip"))]
+    #[test]
+    fn gzipped_response_needs_the_gzip_feature() {
+        let err = load(&[("compressed.json.gz", &[0x1f, 0x8b, 0x08])])
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string().contains("lacks the gzip feature"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn only_json_responses_may_be_gzipped() {
+        let err = load(&[("table.txt.gz", &[0x1f, 0x8b, 0x08])])
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("only JSON responses"), "{}", err);
+    }
+}
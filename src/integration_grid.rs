@@ -0,0 +1,166 @@
+//! User-supplied integration grids
+//!
+//! The Maxwellian integral normally runs over the energy points of the data,
+//! so datasets with different native grids are integrated with different
+//! discretizations. With an explicit grid the cross section is first
+//! resampled onto the given points with its own interpolation law, and the
+//! integral then runs over those points only.
+//!
+//! The result is only as accurate as the grid: points must be dense enough
+//! to follow resonances and the Maxwellian, or structure between them is
+//! lost. Grid points outside the tabulated range are dropped.
+
+use crate::columns;
+use crate::interpolation::{Interpolation, interpolate_at};
+use crate::macs;
+use std::path::Path;
+
+/// Variable the grid points are given in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum GridVariable {
+    /// Laboratory neutron energy in MeV
+    #[default]
+    Energy,
+    /// Neutron-target relative velocity in cm/s
+    Velocity,
+}
+
+/// Integration points read from a one-column file
+#[derive(Debug, Clone)]
+pub struct IntegrationGrid {
+    /// Grid points, increasing, in the units of their [`GridVariable`]
+    points: Vec<f64>,
+}
+
+impl IntegrationGrid {
+    /// Reads a grid file with one positive value per line
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let points = columns::read_one_column(path, "integration grid")?;
+        if let Some(point) = points.iter().find(|&&p| p <= 0.0) {
+            return Err(format!(
+                "Integration grid {} holds {}, points must be positive",
+                path.display(),
+                point
+            )
+            .into());
+        }
+        Ok(IntegrationGrid { points })
+    }
+
+    /// Grid energies in MeV
+    pub fn energies(&self, variable: GridVariable) -> Vec<f64> {
+        match variable {
+            GridVariable::Energy => self.points.clone(),
+            GridVariable::Velocity => self
+                .points
+                .iter()
+                .map(|&v| macs::neutron_energy_at_velocity(v))
+                .collect(),
+        }
+    }
+
+    /// Resamples a cross section onto the grid
+    ///
+    /// # Arguments
+    /// * `energies` - Cross section energy points in MeV
+    /// * `cross_sections` - Cross section values in barns
+    /// * `law` - Interpolation law of the cross section
+    /// * `variable` - Variable the grid points are given in
+    ///
+    /// # Returns
+    /// * `Ok((energies, cross_sections, dropped))` - The resampled cross
+    ///   section and the number of grid points outside the tabulated range
+    /// * `Err(msg)` - If fewer than two grid points lie inside the range
+    pub fn resample(
+        &self,
+        energies: &[f64],
+        cross_sections: &[f64],
+        law: &dyn Interpolation,
+        variable: GridVariable,
+    ) -> Result<(Vec<f64>, Vec<f64>, usize), String> {
+        let grid = self.energies(variable);
+        let (inside, values): (Vec<f64>, Vec<f64>) = grid
+            .iter()
+            .filter_map(|&e| Some((e, interpolate_at(energies, cross_sections, law, e)?)))
+            .unzip();
+        if inside.len() < 2 {
+            return Err(format!(
+                "Only {} of the {} integration grid points lie within the cross section's {:.3e} - {:.3e} MeV",
+                inside.len(),
+                grid.len(),
+                energies.first().copied().unwrap_or(f64::NAN),
+                energies.last().copied().unwrap_or(f64::NAN)
+            ));
+        }
+        let dropped = grid.len() - inside.len();
+        Ok((inside, values, dropped))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpolation::LogLog;
+    use crate::macs::{IntegrationMethod, MaxwellianWeighting, calculate_macs};
+
+    fn log_points(low: f64, high: f64, n: usize) -> Vec<f64> {
+        (0..n)
+            .map(|i| low * (high / low).powf(i as f64 / (n - 1) as f64))
+            .collect()
+    }
+
+    fn macs(energies: &[f64], cross_sections: &[f64], method: IntegrationMethod) -> f64 {
+        calculate_macs(
+            energies,
+            cross_sections,
+            94.0,
+            30.0,
+            MaxwellianWeighting::Flux,
+            method,
+            &LogLog,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn dense_grid_matches_the_native_grid_integrated_exactly() {
+        // A coarse 1/v table, exact under log-log interpolation
+        let energies = log_points(1e-8, 10.0, 20);
+        let cross_sections: Vec<f64> = energies.iter().map(|e| (2.53e-8 / e).sqrt()).collect();
+        let native_exact = macs(&energies, &cross_sections, IntegrationMethod::Adaptive);
+        let native_trapezoid = macs(&energies, &cross_sections, IntegrationMethod::Trapezoid);
+
+        let grid = IntegrationGrid {
+            points: log_points(1e-9, 20.0, 5000),
+        };
+        let (dense, resampled, dropped) = grid
+            .resample(&energies, &cross_sections, &LogLog, GridVariable::Energy)
+            .unwrap();
+        assert!(dropped > 0 && dense.len() + dropped == 5000);
+        let supplied = macs(&dense, &resampled, IntegrationMethod::Trapezoid);
+
+        assert!((supplied / native_exact - 1.0).abs() < 1e-4);
+        assert!((native_trapezoid / native_exact - 1.0).abs() > 1e-2);
+    }
+
+    #[test]
+    fn velocity_grid_converts_to_lab_energies() {
+        // A 1 MeV neutron moves at about 1.383e9 cm/s
+        let grid = IntegrationGrid {
+            points: vec![1.383e9],
+        };
+        let energy = grid.energies(GridVariable::Velocity)[0];
+        assert!((energy - 1.0).abs() < 1e-3, "{}", energy);
+    }
+
+    #[test]
+    fn grid_needs_two_points_inside_the_data() {
+        let grid = IntegrationGrid {
+            points: vec![0.5, 20.0, 30.0],
+        };
+        let err = grid
+            .resample(&[1e-3, 1.0], &[1.0, 1.0], &LogLog, GridVariable::Energy)
+            .unwrap_err();
+        assert!(err.starts_with("Only 1 of the 3"), "{}", err);
+    }
+}
@@ -0,0 +1,221 @@
+//! Interpolation laws for tabulated cross sections
+//!
+//! Evaluated data libraries declare how a cross section varies between
+//! tabulated points using the ENDF interpolation schemes. This module
+//! implements those laws and a diagnostic that checks how well a law
+//! reproduces the tabulated grid.
+
+use num_traits::Float;
+
+/// An ENDF interpolation law between two tabulated points
+///
+/// The ENDF laws work in any float precision `T`, like the MACS integration;
+/// laws that only make sense for the CLI's data, such as
+/// [`PiecewiseInterpolation`], are implemented for the default `f64`.
+pub trait Interpolation<T = f64> {
+    /// Interpolates the value at `x` between (x1, y1) and (x2, y2)
+    fn interpolate(&self, x: T, x1: T, y1: T, x2: T, y2: T) -> T;
+}
+
+/// Constant value equal to the left point (ENDF INT=1)
+pub struct Histogram;
+
+/// y linear in x (ENDF INT=2)
+pub struct LinLin;
+
+/// y linear in ln(x) (ENDF INT=3)
+pub struct LinLog;
+
+/// ln(y) linear in x (ENDF INT=4)
+pub struct LogLin;
+
+/// ln(y) linear in ln(x) (ENDF INT=5)
+pub struct LogLog;
+
+impl<T: Float> Interpolation<T> for Histogram {
+    fn interpolate(&self, _x: T, _x1: T, y1: T, _x2: T, _y2: T) -> T {
+        y1
+    }
+}
+
+impl<T: Float> Interpolation<T> for LinLin {
+    fn interpolate(&self, x: T, x1: T, y1: T, x2: T, y2: T) -> T {
+        if x2 == x1 {
+            return y1;
+        }
+        y1 + (y2 - y1) * (x - x1) / (x2 - x1)
+    }
+}
+
+impl<T: Float> Interpolation<T> for LinLog {
+    fn interpolate(&self, x: T, x1: T, y1: T, x2: T, y2: T) -> T {
+        // Logarithmic axes need strictly positive values
+        let zero = T::zero();
+        if x <= zero || x1 <= zero || x2 <= zero || x2 == x1 {
+            return LinLin.interpolate(x, x1, y1, x2, y2);
+        }
+        y1 + (y2 - y1) * (x / x1).ln() / (x2 / x1).ln()
+    }
+}
+
+impl<T: Float> Interpolation<T> for LogLin {
+    fn interpolate(&self, x: T, x1: T, y1: T, x2: T, y2: T) -> T {
+        if y1 <= T::zero() || y2 <= T::zero() || x2 == x1 {
+            return LinLin.interpolate(x, x1, y1, x2, y2);
+        }
+        y1 * (y2 / y1).powf((x - x1) / (x2 - x1))
+    }
+}
+
+impl<T: Float> Interpolation<T> for LogLog {
+    fn interpolate(&self, x: T, x1: T, y1: T, x2: T, y2: T) -> T {
+        let zero = T::zero();
+        if x <= zero || x1 <= zero || x2 <= zero || y1 <= zero || y2 <= zero || x2 == x1 {
+            return LinLin.interpolate(x, x1, y1, x2, y2);
+        }
+        y1 * (y2 / y1).powf((x / x1).ln() / (x2 / x1).ln())
+    }
+}
+
+/// Interpolation with a different law on each energy range
+///
+/// Used for grids stitched together from several datasets, where each
+/// original dataset keeps its own law over its own energy range. Intervals
+/// spanning two ranges (the junction between datasets) are interpolated
+/// linearly.
+#[derive(Default)]
+pub struct PiecewiseInterpolation {
+    /// Inclusive (start, end) energy bounds with the law used inside them
+    ranges: Vec<(f64, f64, Box<dyn Interpolation>)>,
+}
+
+impl PiecewiseInterpolation {
+    /// Creates an empty piecewise law (lin-lin everywhere)
+    pub fn new() -> Self {
+        PiecewiseInterpolation { ranges: Vec::new() }
+    }
+
+    /// Adds a law for the energies between `start` and `end`
+    pub fn push_range(&mut self, start: f64, end: f64, law: Box<dyn Interpolation>) {
+        self.ranges.push((start, end, law));
+    }
+
+    /// Returns the law whose range contains the whole interval [x1, x2]
+    fn law_for(&self, x1: f64, x2: f64) -> Option<&dyn Interpolation> {
+        self.ranges
+            .iter()
+            .find(|(start, end, _)| *start <= x1 && x2 <= *end)
+            .map(|(_, _, law)| law.as_ref())
+    }
+}
+
+impl Interpolation for PiecewiseInterpolation {
+    fn interpolate(&self, x: f64, x1: f64, y1: f64, x2: f64, y2: f64) -> f64 {
+        match self.law_for(x1.min(x2), x1.max(x2)) {
+            Some(law) => law.interpolate(x, x1, y1, x2, y2),
+            None => LinLin.interpolate(x, x1, y1, x2, y2),
+        }
+    }
+}
+
+/// Interpolates a tabulated function at `x`, returning `None` outside the grid
+///
+/// # Arguments
+/// * `xs` - Increasing abscissae
+/// * `ys` - Values at `xs`
+/// * `law` - Interpolation law between neighbouring points
+/// * `x` - Abscissa to evaluate
+pub fn interpolate_at(xs: &[f64], ys: &[f64], law: &dyn Interpolation, x: f64) -> Option<f64> {
+    let i = xs.partition_point(|&v| v < x);
+    if i == xs.len() || (i == 0 && xs[0] != x) {
+        return None;
+    }
+    if xs[i] == x {
+        return Some(ys[i]);
+    }
+    Some(law.interpolate(x, xs[i - 1], ys[i - 1], xs[i], ys[i]))
+}
+
+/// Returns the interpolation law matching a dataset's declared interpolation
+///
+/// Accepts the usual spellings ("lin-lin", "LogLog", "histogram") as well as
+/// the numeric ENDF codes ("1" to "5"). Returns `None` for unknown names.
+///
+/// # Arguments
+/// * `name` - Interpolation name as found in `CrossSectionDataset.default_interpolation`
+pub fn from_name(name: &str) -> Option<Box<dyn Interpolation>> {
+    let normalized: String = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase();
+
+    match normalized.as_str() {
+        "1" | "histogram" | "hist" | "constant" | "flat" => Some(Box::new(Histogram)),
+        "2" | "linlin" | "linear" | "lin" => Some(Box::new(LinLin)),
+        "3" | "linlog" => Some(Box::new(LinLog)),
+        "4" | "loglin" => Some(Box::new(LogLin)),
+        "5" | "loglog" => Some(Box::new(LogLog)),
+        _ => None,
+    }
+}
+
+/// Relative deviation above which an interior point is flagged as poorly
+/// represented by the interpolation law
+pub const DEVIATION_THRESHOLD: f64 = 0.1;
+
+/// Deviation between a tabulated point and the value interpolated from its neighbors
+#[derive(Debug)]
+pub struct InterpolationDeviation {
+    /// Energy of the tabulated point
+    pub energy: f64,
+    /// Tabulated cross section
+    pub actual: f64,
+    /// Cross section interpolated from the two neighboring points
+    pub interpolated: f64,
+    /// |interpolated - actual| / |actual|
+    pub relative_deviation: f64,
+}
+
+/// Checks how well an interpolation law reproduces each interior grid point
+///
+/// For every interior point the cross section is interpolated from its two
+/// neighbors and compared with the tabulated value. Large deviations mean the
+/// grid under-samples the curvature (typically around a resonance).
+/// Points with a zero cross section are skipped.
+///
+/// # Arguments
+/// * `energies` - Energy points
+/// * `cross_sections` - Cross section values
+/// * `law` - Interpolation law of the dataset
+///
+/// # Returns
+/// Deviations sorted from worst to best
+pub fn interpolation_deviations(
+    energies: &[f64],
+    cross_sections: &[f64],
+    law: &dyn Interpolation,
+) -> Vec<InterpolationDeviation> {
+    let n = energies.len().min(cross_sections.len());
+    let mut deviations: Vec<InterpolationDeviation> = (1..n.saturating_sub(1))
+        .filter(|&i| cross_sections[i] != 0.0)
+        .map(|i| {
+            let interpolated = law.interpolate(
+                energies[i],
+                energies[i - 1],
+                cross_sections[i - 1],
+                energies[i + 1],
+                cross_sections[i + 1],
+            );
+            InterpolationDeviation {
+                energy: energies[i],
+                actual: cross_sections[i],
+                interpolated,
+                relative_deviation: ((interpolated - cross_sections[i]) / cross_sections[i]).abs(),
+            }
+        })
+        .collect();
+
+    deviations.sort_by(|a, b| b.relative_deviation.total_cmp(&a.relative_deviation));
+    deviations
+}
@@ -0,0 +1,101 @@
+//! MACS calculation library
+//!
+//! Computes the Maxwellian-Averaged Cross Section (MACS) of a tabulated
+//! neutron-induced cross section, the reaction rate averaged over the
+//! Maxwellian neutron energy distribution of a stellar temperature.
+//!
+//! The entry point is [`calculate_macs_with_options`], configured by
+//! [`MacsOptions`] (averaging convention, integration method, interpolation
+//! law, energy unit and extrapolation). It works on slices already in memory
+//! and performs no I/O; the `macs-rs` command line downloads the data and
+//! integrates it through the same function.
+//!
+//! # Example
+//! ```
+//! use macs_rs::{EnergyUnit, IntegrationMethod, LogLog, MacsOptions, calculate_macs_with_options};
+//!
+//! // A 1/v cross section of 1 b at 25.3 meV, tabulated in eV
+//! let energies: Vec<f64> = (0..=60).map(|i| 10f64.powf(i as f64 / 10.0)).collect();
+//! let cross_sections: Vec<f64> = energies.iter().map(|e| (0.0253 / e).sqrt()).collect();
+//! let options = MacsOptions {
+//!     energy_unit: EnergyUnit::Ev,
+//!     method: IntegrationMethod::Adaptive,
+//!     law: &LogLog,
+//!     extrapolate: true,
+//!     ..MacsOptions::default()
+//! };
+//! let macs = calculate_macs_with_options(&energies, &cross_sections, 94.0, 30.0, &options)?;
+//!
+//! // A 1/v cross section averages to its value at kT in the centre-of-mass
+//! // frame, i.e. at the laboratory energy kT·(A + 1)/A
+//! let expected = 1000.0 * (0.0253 * 94.0 / (95.0 * 30e3f64)).sqrt();
+//! assert!((macs / expected - 1.0).abs() < 1e-4);
+//! # Ok::<(), String>(())
+//! ```
+//!
+//! [`calculate_macs_table`] computes a set of temperatures at once and
+//! reports its progress to an optional callback, e.g. for a GUI.
+//!
+//! [`macs_to_rate`] and [`rate_to_macs`] convert between a MACS and the
+//! reaction rate N_A⟨σv⟩, e.g. to compare with a published rate.
+//!
+//! The modules below serve the command line (EXFOR access, caching, output
+//! and the many reporting options) and are not a stable interface.
+
+pub use interpolation::{Histogram, Interpolation, LinLin, LinLog, LogLin, LogLog};
+pub use macs::{
+    EnergyUnit, IntegrationMethod, MacsOptions, MacsResult, MaxwellianWeighting, ProgressCallback,
+    calculate_macs, calculate_macs_table, calculate_macs_with_options, macs_to_rate,
+    neutron_reduced_mass, rate_to_macs,
+};
+
+#[doc(hidden)]
+pub mod angular;
+#[doc(hidden)]
+pub mod charged;
+#[doc(hidden)]
+pub mod columns;
+#[doc(hidden)]
+pub mod compare;
+#[doc(hidden)]
+pub mod downsample;
+#[doc(hidden)]
+pub mod error_weighting;
+#[doc(hidden)]
+pub mod exfor_client;
+#[doc(hidden)]
+pub mod fit;
+#[doc(hidden)]
+pub mod grid_export;
+#[doc(hidden)]
+pub mod input_files;
+#[doc(hidden)]
+pub mod integration_grid;
+#[doc(hidden)]
+pub mod interpolation;
+#[doc(hidden)]
+pub mod library_cache;
+#[doc(hidden)]
+pub mod macs;
+#[doc(hidden)]
+pub mod merge;
+#[doc(hidden)]
+pub mod montecarlo;
+#[doc(hidden)]
+pub mod natural;
+#[doc(hidden)]
+pub mod nuclide;
+#[doc(hidden)]
+pub mod offline_db;
+#[doc(hidden)]
+pub mod overlay;
+#[doc(hidden)]
+pub mod reaclib;
+#[doc(hidden)]
+pub mod report;
+#[doc(hidden)]
+pub mod sef;
+#[doc(hidden)]
+pub mod spectrum;
+#[doc(hidden)]
+pub mod units;
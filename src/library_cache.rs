@@ -0,0 +1,62 @@
+//! On-disk cache of the library names seen in EXFOR listings
+//!
+//! Every section listing names the libraries that tabulate a reaction. The
+//! distinct names are kept in a small JSON file so that `--list-libraries`
+//! and the suggestions for a misspelled `--library` work without querying the
+//! API again. The file lives in `$XDG_CACHE_HOME/macs-rs/libraries.json`
+//! (`~/.cache/macs-rs/libraries.json` if the variable is not set). It only
+//! ever grows, unless it is refreshed. The datasets themselves are stored
+//! separately, in the offline snapshot.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// Library names seen so far
+#[derive(Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct LibraryCache {
+    libraries: BTreeSet<String>,
+}
+
+/// Location of the cache file, or `None` if no cache directory is known
+pub fn default_path() -> Option<PathBuf> {
+    let cache_dir = match std::env::var_os("XDG_CACHE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".cache"),
+    };
+    Some(cache_dir.join("macs-rs").join("libraries.json"))
+}
+
+impl LibraryCache {
+    /// Reads the cache file, starting empty if it does not exist
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(format!("Cannot read {}: {}", path.display(), e)),
+        };
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Cannot parse {}: {}", path.display(), e))
+    }
+
+    /// Writes the cache file, creating its directory if needed
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Cannot create {}: {}", parent.display(), e))?;
+        }
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, content).map_err(|e| format!("Cannot write {}: {}", path.display(), e))
+    }
+
+    /// Library names in alphabetical order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.libraries.iter().map(String::as_str)
+    }
+
+    /// Adds library names, returning whether any was new
+    pub fn extend(&mut self, names: impl IntoIterator<Item = String>) -> bool {
+        let before = self.libraries.len();
+        self.libraries.extend(names);
+        self.libraries.len() > before
+    }
+}
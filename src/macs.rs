@@ -1,7 +1,63 @@
+use crate::exfor_client::CrossSectionDataset;
+use crate::fit::MacsFit;
+use crate::interpolation::{Interpolation, LinLin, LogLog, interpolate_at};
+use crate::overlay::SourceRange;
+use crate::units::{self, float};
+use num_traits::Float;
 use std::f64::consts::PI;
 
-/// Boltzmann constant in MeV/K
-const KB: f64 = 8.617e-11;
+/// Conventional reference temperature (kT) in keV at which MACS are quoted
+pub const REFERENCE_TEMPERATURE_KEV: f64 = 30.0;
+
+/// Whether a temperature is the conventional reference temperature
+pub fn is_reference_temperature(temperature_kev: f64) -> bool {
+    (temperature_kev - REFERENCE_TEMPERATURE_KEV).abs() < 1e-9
+}
+
+/// Smallest accepted atomic mass (a single nucleon)
+const MIN_ATOMIC_MASS: f64 = 1.0;
+
+/// Largest accepted atomic mass, above any known nuclide
+const MAX_ATOMIC_MASS: f64 = 300.0;
+
+/// Smallest accepted temperature kT in keV (1 meV, about 12 K)
+const MIN_TEMPERATURE_KEV: f64 = 1e-6;
+
+/// Largest accepted temperature kT in keV (10 MeV, about 10¹¹ K)
+const MAX_TEMPERATURE_KEV: f64 = 1e4;
+
+/// Checks that a temperature is physically sensible for a MACS
+///
+/// kT far outside this range is almost always a unit mistake, e.g. Kelvin
+/// read as keV, and would make the normalization overflow or underflow.
+pub fn validate_temperature<T: Float + std::fmt::Display>(
+    temperature_kev: T,
+) -> Result<(), String> {
+    if temperature_kev <= T::zero() {
+        return Err("Temperature must be positive".to_string());
+    }
+    if !(float(MIN_TEMPERATURE_KEV)..=float(MAX_TEMPERATURE_KEV)).contains(&temperature_kev) {
+        return Err(format!(
+            "kT = {} keV ({:.3e} K) is outside the plausible range [{:e}, {:e}] keV, check the temperature unit",
+            temperature_kev,
+            units::kev_to_kelvin(temperature_kev.to_f64().unwrap_or(f64::NAN)),
+            MIN_TEMPERATURE_KEV,
+            MAX_TEMPERATURE_KEV
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that an atomic mass lies in the range of known nuclides
+pub fn validate_atomic_mass<T: Float + std::fmt::Display>(atomic_mass: T) -> Result<(), String> {
+    if !(float(MIN_ATOMIC_MASS)..=float(MAX_ATOMIC_MASS)).contains(&atomic_mass) {
+        return Err(format!(
+            "Atomic mass {} is outside the plausible range [{}, {}]",
+            atomic_mass, MIN_ATOMIC_MASS, MAX_ATOMIC_MASS
+        ));
+    }
+    Ok(())
+}
 
 /// Calculates the trapezoidal area for numerical integration
 ///
@@ -14,10 +70,409 @@ const KB: f64 = 8.617e-11;
 ///
 /// # Returns
 /// The area under the curve between (x1,y1) and (x2,y2)
-fn trapezoid_area(f: &dyn Fn(f64, f64) -> f64, x1: f64, x2: f64, y1: f64, y2: f64) -> f64 {
+fn trapezoid_area<T: Float>(f: &dyn Fn(T, T) -> T, x1: T, x2: T, y1: T, y2: T) -> T {
     let f1 = f(x1, y1);
     let f2 = f(x2, y2);
-    0.5 * (f1 + f2) * (x2 - x1)
+    float::<T>(0.5) * (f1 + f2) * (x2 - x1)
+}
+
+/// Relative tolerance between one- and two-panel estimates in adaptive integration
+const ADAPTIVE_TOLERANCE: f64 = 1e-6;
+
+/// Absolute tolerance of adaptive integration, as a fraction of the
+/// trapezoid integral over the whole grid
+const ADAPTIVE_ABSOLUTE_TOLERANCE: f64 = 1e-6;
+
+/// Maximum recursion depth of the adaptive trapezoid
+const MAX_ADAPTIVE_DEPTH: u32 = 20;
+
+/// Absolute tolerance per MeV of the adaptive trapezoid on a grid
+///
+/// The relative tolerance alone cannot be met where the integrand nearly
+/// vanishes, far in the Maxwellian tail or where σ(E) drops to zero, and
+/// such intervals would be subdivided down to `MAX_ADAPTIVE_DEPTH`. An
+/// interval is therefore also accepted once its change is below its share,
+/// by width, of `ADAPTIVE_ABSOLUTE_TOLERANCE` times the whole integral, so
+/// the absolute error over the grid stays within that fraction.
+fn adaptive_tolerance_per_mev<T: Float>(
+    f: &dyn Fn(T, T) -> T,
+    energies: &[T],
+    cross_sections: &[T],
+) -> T {
+    let total = energies
+        .windows(2)
+        .zip(cross_sections.windows(2))
+        .fold(T::zero(), |sum, (e, cs)| {
+            sum + trapezoid_area(f, e[0], e[1], cs[0], cs[1])
+        });
+    let span = energies[energies.len() - 1] - energies[0];
+    if span > T::zero() {
+        float::<T>(ADAPTIVE_ABSOLUTE_TOLERANCE) * total.abs() / span
+    } else {
+        T::zero()
+    }
+}
+
+/// Integrates one grid interval with the adaptive trapezoidal rule
+///
+/// The interval is split in two and the two-panel estimate compared with
+/// `whole`, the one-panel estimate. If they differ by more than both the
+/// relative tolerance and the absolute tolerance of the interval, each half
+/// is subdivided again, up to `MAX_ADAPTIVE_DEPTH`.
+/// Midpoint cross sections are evaluated with the dataset's interpolation law,
+/// in the same precision as the integral.
+///
+/// Each accepted two-panel estimate is off by about a third of its
+/// difference from the one-panel estimate (Richardson), and these are summed
+/// into the error estimate returned with the integral.
+///
+/// # Arguments
+/// * `f` - The function to integrate
+/// * `law` - Interpolation law of the cross section
+/// * `x1`, `x2` - Interval bounds (energy)
+/// * `y1`, `y2` - Cross sections at the bounds
+/// * `whole` - Trapezoid estimate over the whole interval
+/// * `tolerance_per_mev` - Absolute tolerance per unit energy (see
+///   [`adaptive_tolerance_per_mev`])
+/// * `depth` - Current recursion depth
+#[allow(clippy::too_many_arguments)]
+fn adaptive_trapezoid<T: Float>(
+    f: &dyn Fn(T, T) -> T,
+    law: &dyn Interpolation<T>,
+    x1: T,
+    x2: T,
+    y1: T,
+    y2: T,
+    whole: T,
+    tolerance_per_mev: T,
+    depth: u32,
+) -> (T, T) {
+    let xm = float::<T>(0.5) * (x1 + x2);
+    let ym = law.interpolate(xm, x1, y1, x2, y2);
+    let left = trapezoid_area(f, x1, xm, y1, ym);
+    let right = trapezoid_area(f, xm, x2, ym, y2);
+    let refined = left + right;
+
+    let change = (refined - whole).abs();
+    if depth >= MAX_ADAPTIVE_DEPTH
+        || change <= float::<T>(ADAPTIVE_TOLERANCE) * refined.abs()
+        || change <= tolerance_per_mev * (x2 - x1)
+    {
+        return (refined, change / float(3.0));
+    }
+
+    let (left, left_error) =
+        adaptive_trapezoid(f, law, x1, xm, y1, ym, left, tolerance_per_mev, depth + 1);
+    let (right, right_error) =
+        adaptive_trapezoid(f, law, xm, x2, ym, y2, right, tolerance_per_mev, depth + 1);
+    (left + right, left_error + right_error)
+}
+
+/// Numerical integration scheme used for the Maxwellian integral
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum IntegrationMethod {
+    /// Trapezoidal rule on the tabulated grid
+    #[default]
+    Trapezoid,
+    /// Trapezoidal rule that subdivides grid intervals until the estimate
+    /// converges, evaluating the cross section with the interpolation law
+    Adaptive,
+}
+
+/// Weight function used to average the cross section over the Maxwellian
+///
+/// The two conventions differ in which distribution σ(E) is averaged over:
+///
+/// - `Flux`: the Maxwellian flux v·n(v), giving the standard astrophysical MACS
+///   ⟨σv⟩/v_T = (2*a²/(√π * (kT)²)) * ∫ σ(E) * E * exp(-a*E/(kT)) dE.
+///   A constant cross section σ₀ yields 2σ₀/√π.
+/// - `NumberDensity`: the Maxwell-Boltzmann number density n(E) alone, giving
+///   ⟨σ⟩ = (2*a^(3/2)/(√π * (kT)^(3/2))) * ∫ σ(E) * √E * exp(-a*E/(kT)) dE.
+///   A constant cross section σ₀ yields σ₀.
+///
+/// The ratio Flux/NumberDensity depends on the shape of σ(E): it is √π/2 for
+/// a 1/v cross section and 2/√π for a constant one, because the flux weight
+/// favours higher energies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum MaxwellianWeighting {
+    /// Maxwellian flux weight E·exp(-aE/kT) (standard MACS)
+    #[default]
+    Flux,
+    /// Number-density weight √E·exp(-aE/kT)
+    NumberDensity,
+}
+
+/// Reduced mass factor a = A/(A+m) of a projectile of mass m on a target of mass A
+///
+/// Both masses are in the same units. For a neutron (m = [`NEUTRON_MASS`])
+/// this is the usual A/(1+A).
+pub fn reduced_mass_factor<T: Float>(atomic_mass: T, projectile_mass: T) -> T {
+    atomic_mass / (projectile_mass + atomic_mass)
+}
+
+/// Neutron mass in the units of the atomic mass, as in [`reduced_mass_factor`]
+pub const NEUTRON_MASS: f64 = 1.0;
+
+/// Reference frame in which cross section energies are tabulated
+///
+/// EXFOR and the evaluated libraries use the laboratory frame (target at
+/// rest), which is what the Maxwellian integral expects. A neutron of lab
+/// energy E_lab has the center-of-mass energy E_cm = a * E_lab with
+/// a = A/(1+A), so CM energies are converted back with E_lab = E_cm / a.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum EnergyFrame {
+    /// Laboratory-frame neutron energy (EXFOR convention)
+    #[default]
+    Lab,
+    /// Center-of-mass energy
+    Cm,
+}
+
+/// Converts an energy tabulated in `frame` to the laboratory frame
+///
+/// # Arguments
+/// * `energy` - Energy in the given frame
+/// * `atomic_mass` - Atomic mass number of the target
+/// * `frame` - Frame of `energy`
+pub fn to_lab_energy(energy: f64, atomic_mass: f64, frame: EnergyFrame) -> f64 {
+    match frame {
+        EnergyFrame::Lab => energy,
+        EnergyFrame::Cm => energy / reduced_mass_factor(atomic_mass, NEUTRON_MASS),
+    }
+}
+
+/// Thermal energy kT in MeV for a temperature given in keV
+pub fn thermal_energy<T: Float>(temperature_kev: T) -> T {
+    let temperature_k = units::kev_to_kelvin(temperature_kev);
+    units::kelvin_to_mev(temperature_k)
+}
+
+/// Maxwellian weight w(E) * exp(-a*E/(kT)) at a laboratory energy
+///
+/// w(E) = E for flux weighting and √E for number-density weighting, with
+/// `energy` and `kt` in MeV and `a` the [`reduced_mass_factor`]. The weight
+/// is not normalized: multiplied by [`normalization`] its integral over all
+/// energies is 2/√π (flux) or 1 (number density), the MACS of a constant
+/// 1 b cross section. It peaks at E = kT/a (flux) or E = kT/(2a) (number
+/// density).
+///
+/// Every Maxwellian average here (the integral, its tails, the coverage and
+/// the diagnostics) goes through this function.
+pub fn maxwellian_weight<T: Float>(
+    energy: T,
+    reduced_mass_factor: T,
+    kt: T,
+    weighting: MaxwellianWeighting,
+) -> T {
+    let weight = match weighting {
+        MaxwellianWeighting::Flux => energy,
+        MaxwellianWeighting::NumberDensity => energy.sqrt(),
+    };
+    weight * (-(reduced_mass_factor * energy) / kt).exp()
+}
+
+/// Integrand function: σ(E) * w(E) * exp(-a*E/(kT)), see [`maxwellian_weight`]
+fn integrand<T: Float>(e: T, cs: T, a: T, kt: T, weighting: MaxwellianWeighting) -> T {
+    cs * maxwellian_weight(e, a, kt, weighting)
+}
+
+/// Normalization factor of the Maxwellian integral
+///
+/// 2*a²/(√π * (kT)²) for flux weighting,
+/// 2*a^(3/2)/(√π * (kT)^(3/2)) for number-density weighting
+fn normalization<T: Float>(a: T, kt: T, weighting: MaxwellianWeighting) -> T {
+    let two = float::<T>(2.0);
+    let sqrt_pi = float::<T>(PI).sqrt();
+    match weighting {
+        MaxwellianWeighting::Flux => (two * a.powi(2)) / (sqrt_pi * kt.powi(2)),
+        MaxwellianWeighting::NumberDensity => {
+            let three_halves = float::<T>(1.5);
+            (two * a.powf(three_halves)) / (sqrt_pi * kt.powf(three_halves))
+        }
+    }
+}
+
+/// MACS in millibarns of a cross section that is constant in energy
+///
+/// The Maxwellian average of a constant σ is analytic: 2σ/√π with flux
+/// weighting and σ itself with number-density weighting, independent of the
+/// temperature and of the masses.
+pub fn constant_cross_section_macs(cross_section_b: f64, weighting: MaxwellianWeighting) -> f64 {
+    let macs_b = match weighting {
+        MaxwellianWeighting::Flux => 2.0 * cross_section_b / PI.sqrt(),
+        MaxwellianWeighting::NumberDensity => cross_section_b,
+    };
+    units::barns_to_millibarns(macs_b)
+}
+
+/// Thermal neutron energy in MeV (0.0253 eV, the energy at 2200 m/s)
+pub const THERMAL_ENERGY_MEV: f64 = 2.53e-8;
+
+/// Lower limit of the resonance integral in MeV (the 0.5 eV cadmium cutoff)
+pub const CADMIUM_CUTOFF_MEV: f64 = 5e-7;
+
+/// Thermal cross section σ(0.0253 eV) in barns
+///
+/// Returns `None` if the grid does not reach the thermal energy.
+pub fn thermal_cross_section(
+    energies: &[f64],
+    cross_sections: &[f64],
+    law: &dyn Interpolation,
+) -> Option<f64> {
+    interpolate_at(energies, cross_sections, law, THERMAL_ENERGY_MEV)
+}
+
+/// Integration of the resonance integral between grid points
+#[derive(Debug, Clone, Copy, Default, PartialEq, clap::ValueEnum)]
+pub enum RiMethod {
+    /// Trapezoidal rule in ln E
+    #[default]
+    Trapezoid,
+    /// Exact integral of a power law σ ∝ E^b through each pair of points,
+    /// as for log-log interpolated data
+    LogLog,
+}
+
+/// Default |b| below which a log-log segment is integrated by the trapezoid
+pub const LOGLOG_EPSILON: f64 = 1e-6;
+
+/// ∫ σ(E) dE/E over one segment
+///
+/// In log-log mode σ = σ₁(E/E₁)^b with b = ln(σ₂/σ₁)/ln(E₂/E₁), whose
+/// integral is (σ₂ - σ₁)/b. Near b = 0, where the integrand σ/E approaches
+/// the logarithmic case E^-1, this difference of nearly equal values divided
+/// by a tiny b loses all precision, and for nearly equal energies b itself is
+/// a ratio of tiny logarithms; the trapezoid in ln E, exact for b = 0, is
+/// used when |b| or ln(E₂/E₁) is below `epsilon`, or a σ is not positive.
+fn ri_segment(e1: f64, s1: f64, e2: f64, s2: f64, method: RiMethod, epsilon: f64) -> f64 {
+    let log_ratio = (e2 / e1).ln();
+    let trapezoid = 0.5 * (s1 + s2) * log_ratio;
+    if method == RiMethod::Trapezoid || s1 <= 0.0 || s2 <= 0.0 || log_ratio < epsilon {
+        return trapezoid;
+    }
+    let b = (s2 / s1).ln() / log_ratio;
+    if b.abs() < epsilon {
+        return trapezoid;
+    }
+    (s2 - s1) / b
+}
+
+/// Resonance integral ∫ σ(E) dE/E from the cadmium cutoff up in barns
+///
+/// The integral runs to the last grid energy, segment by segment between
+/// grid points (see [`RiMethod`]), starting from the cross section
+/// interpolated at 0.5 eV.
+///
+/// # Arguments
+/// * `energies` - Energy points in MeV
+/// * `cross_sections` - Cross section values in barns
+/// * `law` - Interpolation law, used at the cutoff
+/// * `method` - Integration between grid points
+/// * `epsilon` - Switchover to the trapezoid in log-log mode (see [`LOGLOG_EPSILON`])
+///
+/// Returns `None` if the grid does not cover the cutoff.
+pub fn resonance_integral(
+    energies: &[f64],
+    cross_sections: &[f64],
+    law: &dyn Interpolation,
+    method: RiMethod,
+    epsilon: f64,
+) -> Option<f64> {
+    let start = interpolate_at(energies, cross_sections, law, CADMIUM_CUTOFF_MEV)?;
+    let first = energies.partition_point(|&e| e <= CADMIUM_CUTOFF_MEV);
+    let points = std::iter::once((CADMIUM_CUTOFF_MEV, start)).chain(
+        energies[first..]
+            .iter()
+            .copied()
+            .zip(cross_sections[first..].iter().copied()),
+    );
+    let mut previous: Option<(f64, f64)> = None;
+    let mut integral = 0.0;
+    for (energy, cross_section) in points {
+        if let Some((e1, s1)) = previous {
+            integral += ri_segment(e1, s1, energy, cross_section, method, epsilon);
+        }
+        previous = Some((energy, cross_section));
+    }
+    Some(integral)
+}
+
+/// Neutron mass in amu
+const NEUTRON_MASS_AMU: f64 = 1.00866491606;
+
+/// Reduced mass in amu of a neutron and a target of `atomic_mass` amu
+pub fn neutron_reduced_mass(atomic_mass: f64) -> f64 {
+    NEUTRON_MASS_AMU * atomic_mass / (NEUTRON_MASS_AMU + atomic_mass)
+}
+
+/// Laboratory energy in MeV of a neutron with relative velocity `velocity` in cm/s
+///
+/// The center-of-mass energy ½μv² divided by the reduced mass factor gives
+/// the laboratory energy ½m_n v², independent of the target mass.
+pub fn neutron_energy_at_velocity(velocity: f64) -> f64 {
+    let beta = velocity / units::SPEED_OF_LIGHT_CM_PER_S;
+    0.5 * NEUTRON_MASS_AMU * units::AMU_MEV * beta * beta
+}
+
+/// Thermal velocity v_T = √(2kT/μ) in cm/s
+fn thermal_velocity(temperature_kev: f64, reduced_mass_amu: f64) -> f64 {
+    let kt = units::kev_to_mev(temperature_kev);
+    units::SPEED_OF_LIGHT_CM_PER_S * (2.0 * kt / (reduced_mass_amu * units::AMU_MEV)).sqrt()
+}
+
+/// Converts a MACS to the astrophysical reaction rate
+///
+/// The MACS is ⟨σv⟩/v_T, so the rate per mole is N_A⟨σv⟩ = N_A·MACS·v_T with
+/// the thermal velocity v_T = √(2kT/μ). This is the inverse of
+/// [`rate_to_macs`].
+///
+/// # Arguments
+/// * `macs_mb` - MACS in millibarns
+/// * `temperature_kev` - Temperature (kT) in keV
+/// * `reduced_mass_amu` - Reduced mass of the projectile and target in amu
+///   (see [`neutron_reduced_mass`])
+///
+/// # Returns
+/// The rate N_A⟨σv⟩ in cm³ mol⁻¹ s⁻¹
+pub fn macs_to_rate(macs_mb: f64, temperature_kev: f64, reduced_mass_amu: f64) -> f64 {
+    units::AVOGADRO_PER_MOL
+        * units::millibarns_to_cm2(macs_mb)
+        * thermal_velocity(temperature_kev, reduced_mass_amu)
+}
+
+/// Converts an astrophysical reaction rate to the MACS it implies
+///
+/// This is the inverse of [`macs_to_rate`], e.g. to compare a published rate
+/// with a computed MACS without integrating anything.
+///
+/// # Arguments
+/// * `rate` - Rate N_A⟨σv⟩ in cm³ mol⁻¹ s⁻¹
+/// * `temperature_kev` - Temperature (kT) in keV
+/// * `reduced_mass_amu` - Reduced mass of the projectile and target in amu
+///
+/// # Returns
+/// The MACS in millibarns
+pub fn rate_to_macs(rate: f64, temperature_kev: f64, reduced_mass_amu: f64) -> f64 {
+    rate / macs_to_rate(1.0, temperature_kev, reduced_mass_amu)
+}
+
+/// Checks the inputs shared by the MACS integrators
+fn validate_inputs<T: Float + std::fmt::Display>(
+    energies: &[T],
+    cross_sections: &[T],
+    atomic_mass: T,
+    temperature_kev: T,
+) -> Result<(), String> {
+    if energies.len() != cross_sections.len() {
+        return Err("Energy and cross section vectors must have the same length".to_string());
+    }
+
+    if energies.is_empty() {
+        return Err("Input vectors cannot be empty".to_string());
+    }
+
+    validate_temperature(temperature_kev)?;
+    validate_atomic_mass(atomic_mass)
 }
 
 /// Calculates the Maxwellian-Averaged Cross Section (MACS)
@@ -26,7 +481,7 @@ fn trapezoid_area(f: &dyn Fn(f64, f64) -> f64, x1: f64, x2: f64, y1: f64, y2: f6
 /// MACS = (2*a²/(√π * (kT)²)) * ∫ σ(E) * E * exp(-a*E/(kT)) dE
 ///
 /// where:
-/// - a = A/(1+A) is the reduced mass factor
+/// - a = A/(1+A) is the reduced mass factor of a neutron (see [`reduced_mass_factor`])
 /// - A is the atomic mass number
 /// - kT is the thermal energy (Boltzmann constant × temperature)
 /// - σ(E) is the energy-dependent cross section
@@ -36,6 +491,12 @@ fn trapezoid_area(f: &dyn Fn(f64, f64) -> f64, x1: f64, x2: f64, y1: f64, y2: f6
 /// * `cross_sections` - Cross section values in barns
 /// * `atomic_mass` - Atomic mass number (e.g., 94 for Mo-94)
 /// * `temperature_kev` - Temperature in keV
+/// * `weighting` - Averaging convention (see [`MaxwellianWeighting`])
+/// * `method` - Numerical integration scheme
+/// * `law` - Interpolation law of the cross section between grid points
+///
+/// The calculation runs in the precision of the inputs, e.g. `f32` for speed
+/// and memory or `f64` (the default used by the CLI).
 ///
 /// # Returns
 /// * `Ok(macs)` - MACS value in millibarns
@@ -43,60 +504,2352 @@ fn trapezoid_area(f: &dyn Fn(f64, f64) -> f64, x1: f64, x2: f64, y1: f64, y2: f6
 ///
 /// # Example
 /// ```
+/// # use macs_rs::{IntegrationMethod, LinLin, MaxwellianWeighting, calculate_macs};
 /// let energies = vec![0.001, 0.002, 0.003]; // MeV
 /// let cross_sections = vec![10.0, 8.0, 6.0]; // barns
-/// let macs = calculate_macs(&energies, &cross_sections, 94.0, 30.0)?;
+/// let macs = calculate_macs(
+///     &energies,
+///     &cross_sections,
+///     94.0,
+///     30.0,
+///     MaxwellianWeighting::Flux,
+///     IntegrationMethod::Trapezoid,
+///     &LinLin,
+/// )?;
 /// println!("MACS at 30 keV: {} mb", macs);
+/// # Ok::<(), String>(())
 /// ```
-pub fn calculate_macs(
+pub fn calculate_macs<T: Float + std::fmt::Display>(
+    energies: &[T],
+    cross_sections: &[T],
+    atomic_mass: T,
+    temperature_kev: T,
+    weighting: MaxwellianWeighting,
+    method: IntegrationMethod,
+    law: &dyn Interpolation<T>,
+) -> Result<T, String> {
+    calculate_macs_for_projectile(
+        energies,
+        cross_sections,
+        atomic_mass,
+        float(NEUTRON_MASS),
+        temperature_kev,
+        weighting,
+        method,
+        law,
+    )
+}
+
+/// Calculates the MACS for a projectile of any mass
+///
+/// Same as [`calculate_macs`] with the reduced mass factor a = A/(A+m), where
+/// `projectile_mass` m is in the units of `atomic_mass`. Charged projectiles
+/// go through [`crate::charged`], which supplies the matching interpolation.
+///
+/// Only the MACS itself takes the projectile mass. The tails, edge ramps,
+/// coverage, error estimate, breakdowns and [`StreamingMacs`] use the neutron
+/// factor A/(1+A); the command line rejects them for charged projectiles.
+#[allow(clippy::too_many_arguments)]
+pub fn calculate_macs_for_projectile<T: Float + std::fmt::Display>(
+    energies: &[T],
+    cross_sections: &[T],
+    atomic_mass: T,
+    projectile_mass: T,
+    temperature_kev: T,
+    weighting: MaxwellianWeighting,
+    method: IntegrationMethod,
+    law: &dyn Interpolation<T>,
+) -> Result<T, String> {
+    validate_inputs(energies, cross_sections, atomic_mass, temperature_kev)?;
+
+    let a = reduced_mass_factor(atomic_mass, projectile_mass);
+    let kt = thermal_energy(temperature_kev);
+    let macs_integral =
+        maxwellian_integral(energies, cross_sections, a, kt, weighting, method, law);
+
+    let normalization = normalization(a, kt, weighting);
+
+    // MACS in barns
+    let macs_barns = normalization * macs_integral;
+
+    Ok(units::barns_to_millibarns(macs_barns))
+}
+
+/// Integrates σ(E)·w(E)·exp(-aE/kT) over the grid, without normalization
+fn maxwellian_integral<T: Float>(
+    energies: &[T],
+    cross_sections: &[T],
+    a: T,
+    kt: T,
+    weighting: MaxwellianWeighting,
+    method: IntegrationMethod,
+    law: &dyn Interpolation<T>,
+) -> T {
+    let f = |e: T, cs: T| -> T { integrand(e, cs, a, kt, weighting) };
+    let tolerance = interval_tolerance(&f, method, energies, cross_sections);
+
+    // Calculate the integral using the trapezoidal rule
+    let mut macs_integral = T::zero();
+    for i in 1..energies.len() {
+        macs_integral = macs_integral
+            + interval_area(
+                &f,
+                law,
+                method,
+                tolerance,
+                (energies[i - 1], energies[i]),
+                (cross_sections[i - 1], cross_sections[i]),
+            );
+    }
+    macs_integral
+}
+
+/// Absolute tolerance per MeV passed to [`interval_area`], only computed for
+/// the adaptive rule
+fn interval_tolerance<T: Float>(
+    f: &dyn Fn(T, T) -> T,
+    method: IntegrationMethod,
+    energies: &[T],
+    cross_sections: &[T],
+) -> T {
+    match method {
+        IntegrationMethod::Trapezoid => T::zero(),
+        IntegrationMethod::Adaptive => adaptive_tolerance_per_mev(f, energies, cross_sections),
+    }
+}
+
+/// Integrates one grid interval [x1, x2] with the given method
+fn interval_area<T: Float>(
+    f: &dyn Fn(T, T) -> T,
+    law: &dyn Interpolation<T>,
+    method: IntegrationMethod,
+    tolerance_per_mev: T,
+    (x1, x2): (T, T),
+    (y1, y2): (T, T),
+) -> T {
+    let area = trapezoid_area(f, x1, x2, y1, y2);
+    match method {
+        IntegrationMethod::Trapezoid => area,
+        IntegrationMethod::Adaptive => {
+            adaptive_trapezoid(f, law, x1, x2, y1, y2, area, tolerance_per_mev, 0).0
+        }
+    }
+}
+
+/// One grid interval of the Maxwellian integral, as summed by [`calculate_macs`]
+#[derive(Debug, Clone, Copy)]
+pub struct IntervalRecord {
+    /// Energies of the two grid points in MeV
+    pub energies: (f64, f64),
+    /// Cross sections at the two grid points in barns
+    pub cross_sections: (f64, f64),
+    /// Integrand σ(E)·w(E)·exp(-aE/kT) at the two grid points
+    pub integrand: (f64, f64),
+    /// Integral over the interval
+    pub area: f64,
+    /// Integral from the first grid point to the end of this interval
+    pub cumulative: f64,
+}
+
+/// Intermediate values of the Maxwellian integral of [`calculate_macs`]
+pub struct IntegralBreakdown {
+    /// Normalization factor turning the integral into the MACS in barns
+    pub normalization: f64,
+    /// Every grid interval in order of energy
+    pub intervals: Vec<IntervalRecord>,
+}
+
+impl IntegralBreakdown {
+    /// MACS accumulated up to each grid energy
+    ///
+    /// Each pair is an upper energy bound in MeV and the MACS in millibarns
+    /// of the grid up to it, starting from zero at the first grid point. The
+    /// last value is the MACS over the whole grid.
+    pub fn cumulative_macs(&self) -> Vec<(f64, f64)> {
+        let to_mb = |integral: f64| units::barns_to_millibarns(self.normalization * integral);
+        self.intervals
+            .first()
+            .map(|first| (first.energies.0, 0.0))
+            .into_iter()
+            .chain(
+                self.intervals
+                    .iter()
+                    .map(|interval| (interval.energies.1, to_mb(interval.cumulative))),
+            )
+            .collect()
+    }
+}
+
+/// Records the per-interval terms of the Maxwellian integral
+///
+/// The intervals are integrated exactly as by [`calculate_macs`], so the
+/// last `cumulative` times `normalization` is its MACS in barns. This makes
+/// the sum reproducible by hand and comparable with other codes.
+///
+/// # Arguments
+/// Same as [`calculate_macs`]
+///
+/// # Returns
+/// * `Ok(breakdown)` - Normalization and per-interval terms
+/// * `Err(msg)` - Error message if inputs are invalid
+pub fn integral_breakdown(
+    energies: &[f64],
+    cross_sections: &[f64],
+    atomic_mass: f64,
+    temperature_kev: f64,
+    weighting: MaxwellianWeighting,
+    method: IntegrationMethod,
+    law: &dyn Interpolation,
+) -> Result<IntegralBreakdown, String> {
+    validate_inputs(energies, cross_sections, atomic_mass, temperature_kev)?;
+
+    let a = reduced_mass_factor(atomic_mass, float(NEUTRON_MASS));
+    let kt = thermal_energy(temperature_kev);
+    let f = |e: f64, cs: f64| integrand(e, cs, a, kt, weighting);
+    let tolerance = interval_tolerance(&f, method, energies, cross_sections);
+    let mut cumulative = 0.0;
+    let intervals = energies
+        .windows(2)
+        .zip(cross_sections.windows(2))
+        .map(|(e, cs)| {
+            let area = interval_area(&f, law, method, tolerance, (e[0], e[1]), (cs[0], cs[1]));
+            cumulative += area;
+            IntervalRecord {
+                energies: (e[0], e[1]),
+                cross_sections: (cs[0], cs[1]),
+                integrand: (f(e[0], cs[0]), f(e[1], cs[1])),
+                area,
+                cumulative,
+            }
+        })
+        .collect();
+    Ok(IntegralBreakdown {
+        normalization: normalization(a, kt, weighting),
+        intervals,
+    })
+}
+
+/// Calculates the raw Maxwellian integral behind the MACS
+///
+/// This is ∫ σ(E) * E * exp(-a*E/(kT)) dE (with √E for number-density
+/// weighting) over the tabulated grid, i.e. the MACS of [`calculate_macs`]
+/// before the 2*a²/(√π * (kT)²) normalization and the conversion to mb.
+/// It is useful to check the normalization against other codes.
+///
+/// # Arguments
+/// Same as [`calculate_macs`]
+///
+/// # Returns
+/// * `Ok(integral)` - In barn·MeV² (barn·MeV^(3/2) for number-density weighting)
+/// * `Err(msg)` - Error message if inputs are invalid
+pub fn raw_integral<T: Float + std::fmt::Display>(
+    energies: &[T],
+    cross_sections: &[T],
+    atomic_mass: T,
+    temperature_kev: T,
+    weighting: MaxwellianWeighting,
+    method: IntegrationMethod,
+    law: &dyn Interpolation<T>,
+) -> Result<T, String> {
+    validate_inputs(energies, cross_sections, atomic_mass, temperature_kev)?;
+
+    let a = reduced_mass_factor(atomic_mass, float(NEUTRON_MASS));
+    let kt = thermal_energy(temperature_kev);
+    Ok(maxwellian_integral(
+        energies,
+        cross_sections,
+        a,
+        kt,
+        weighting,
+        method,
+        law,
+    ))
+}
+
+/// Estimates the numerical error of the MACS integrated with `method`
+///
+/// For the trapezoidal rule, which converges as O(h²), the error on the full
+/// grid is about (T(h) - T(2h)) / 3, where T(2h) is the integral over every
+/// other grid point (Richardson extrapolation). On non-uniform grids this is
+/// an order of magnitude estimate. The adaptive rule instead sums the same
+/// Richardson estimate over the subintervals it accepted. Either measures
+/// numerical error only, not the uncertainty of the data.
+///
+/// # Arguments
+/// * `energies` - Energy points in MeV
+/// * `cross_sections` - Cross section values in barns
+/// * `atomic_mass` - Atomic mass number
+/// * `temperature_kev` - Temperature in keV
+/// * `weighting` - Averaging convention (see [`MaxwellianWeighting`])
+/// * `method` - Integration scheme whose error is estimated
+/// * `law` - Interpolation law of the cross section, used by the adaptive rule
+///
+/// # Returns
+/// * `Ok(error)` - Absolute error estimate in millibarns (0 for fewer than three
+///   trapezoid points)
+/// * `Err(msg)` - Error message if inputs are invalid
+pub fn integration_error_estimate(
     energies: &[f64],
     cross_sections: &[f64],
     atomic_mass: f64,
     temperature_kev: f64,
+    weighting: MaxwellianWeighting,
+    method: IntegrationMethod,
+    law: &dyn Interpolation,
 ) -> Result<f64, String> {
-    if energies.len() != cross_sections.len() {
-        return Err("Energy and cross section vectors must have the same length".to_string());
+    validate_inputs(energies, cross_sections, atomic_mass, temperature_kev)?;
+
+    let a = reduced_mass_factor(atomic_mass, float(NEUTRON_MASS));
+    let kt = thermal_energy(temperature_kev);
+    let f = |e: f64, cs: f64| -> f64 { integrand(e, cs, a, kt, weighting) };
+    let error = match method {
+        IntegrationMethod::Trapezoid => {
+            if energies.len() < 3 {
+                return Ok(0.0);
+            }
+            let integrate = |indices: &[usize]| -> f64 {
+                indices
+                    .windows(2)
+                    .map(|w| {
+                        trapezoid_area(
+                            &f,
+                            energies[w[0]],
+                            energies[w[1]],
+                            cross_sections[w[0]],
+                            cross_sections[w[1]],
+                        )
+                    })
+                    .sum()
+            };
+
+            let last = energies.len() - 1;
+            let full: Vec<usize> = (0..=last).collect();
+            let mut half: Vec<usize> = (0..=last).step_by(2).collect();
+            if last % 2 == 1 {
+                half.push(last);
+            }
+            ((integrate(&full) - integrate(&half)) / 3.0).abs()
+        }
+        IntegrationMethod::Adaptive => {
+            let tolerance = adaptive_tolerance_per_mev(&f, energies, cross_sections);
+            energies
+                .windows(2)
+                .zip(cross_sections.windows(2))
+                .map(|(e, cs)| {
+                    let whole = trapezoid_area(&f, e[0], e[1], cs[0], cs[1]);
+                    adaptive_trapezoid(&f, law, e[0], e[1], cs[0], cs[1], whole, tolerance, 0).1
+                })
+                .sum()
+        }
+    };
+    Ok(units::barns_to_millibarns(
+        normalization(a, kt, weighting) * error,
+    ))
+}
+
+/// Energy window the MACS is sensitive to
+///
+/// The integrand σ(E)·w(E)·exp(-aE/kT) is treated as a distribution over
+/// energy; its centroid is the mean contributing energy and its spread the
+/// standard deviation about it. Both use the trapezoidal rule on the grid.
+///
+/// # Arguments
+/// * `energies` - Energy points in MeV
+/// * `cross_sections` - Cross section values in barns
+/// * `atomic_mass` - Atomic mass number
+/// * `temperature_kev` - Temperature in keV
+/// * `weighting` - Averaging convention (see [`MaxwellianWeighting`])
+///
+/// # Returns
+/// * `Ok((centroid, spread))` - Both in MeV
+/// * `Err(msg)` - Error message if inputs are invalid or the integral vanishes
+pub fn sensitivity_window(
+    energies: &[f64],
+    cross_sections: &[f64],
+    atomic_mass: f64,
+    temperature_kev: f64,
+    weighting: MaxwellianWeighting,
+) -> Result<(f64, f64), String> {
+    validate_inputs(energies, cross_sections, atomic_mass, temperature_kev)?;
+
+    let a = reduced_mass_factor(atomic_mass, float(NEUTRON_MASS));
+    let kt = thermal_energy(temperature_kev);
+    let moment = |power: i32| -> f64 {
+        let f = |e: f64, cs: f64| -> f64 { e.powi(power) * integrand(e, cs, a, kt, weighting) };
+        (1..energies.len())
+            .map(|i| {
+                trapezoid_area(
+                    &f,
+                    energies[i - 1],
+                    energies[i],
+                    cross_sections[i - 1],
+                    cross_sections[i],
+                )
+            })
+            .sum()
+    };
+
+    let total = moment(0);
+    if total <= 0.0 {
+        return Err("The Maxwellian integral vanishes, no sensitivity window".to_string());
     }
+    let centroid = moment(1) / total;
+    let variance = moment(2) / total - centroid.powi(2);
+    Ok((centroid, variance.max(0.0).sqrt()))
+}
 
-    if energies.is_empty() {
-        return Err("Input vectors cannot be empty".to_string());
+/// Share of the MACS coming from one energy window
+#[derive(Debug, Clone, Copy)]
+pub struct WindowContribution {
+    /// Contribution of the window to the MACS in millibarns
+    pub contribution_mb: f64,
+    /// Contribution divided by the MACS over the whole grid
+    pub fraction: f64,
+}
+
+/// Computes how much an energy window, e.g. around a resonance, adds to the MACS
+///
+/// The Maxwellian-weighted cross section is integrated with `method` over the
+/// grid points inside the window, with end points interpolated at its edges
+/// using `law`, and compared with the same integral over the whole grid.
+/// Extrapolated tails are not included in either.
+///
+/// # Arguments
+/// * `energies` - Energy points in MeV
+/// * `cross_sections` - Cross section values in barns
+/// * `atomic_mass` - Atomic mass number
+/// * `temperature_kev` - Temperature in keV
+/// * `weighting` - Averaging convention (see [`MaxwellianWeighting`])
+/// * `method` - Integration scheme, as for the MACS itself
+/// * `law` - Interpolation law of the cross section
+/// * `window` - (start, end) of the window in MeV, e.g. E_r ∓ Γ/2
+///
+/// # Returns
+/// * `Ok(contribution)` - Contribution in mb and fraction of the total
+/// * `Err(msg)` - If inputs are invalid, the window misses the grid or the
+///   total MACS vanishes
+///
+/// # Example
+/// ```no_run
+/// # use macs_rs::{IntegrationMethod, LinLin, MaxwellianWeighting, macs::resonance_contribution};
+/// # fn example(energies: &[f64], cross_sections: &[f64]) -> Result<(), String> {
+/// let resonance = resonance_contribution(
+///     energies, cross_sections, 94.0, 30.0,
+///     MaxwellianWeighting::Flux, IntegrationMethod::Trapezoid, &LinLin, (1.9e-3, 2.1e-3),
+/// )?;
+/// println!("{:.1}% of the MACS", resonance.fraction * 100.0);
+/// # Ok(())
+/// # }
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn resonance_contribution(
+    energies: &[f64],
+    cross_sections: &[f64],
+    atomic_mass: f64,
+    temperature_kev: f64,
+    weighting: MaxwellianWeighting,
+    method: IntegrationMethod,
+    law: &dyn Interpolation,
+    (start, end): (f64, f64),
+) -> Result<WindowContribution, String> {
+    validate_inputs(energies, cross_sections, atomic_mass, temperature_kev)?;
+
+    let low = start.max(energies[0]);
+    let high = end.min(energies[energies.len() - 1]);
+    if low >= high {
+        return Err(format!(
+            "The window {:.3e} - {:.3e} MeV lies outside the tabulated energies",
+            start, end
+        ));
     }
+    let mut window_energies = vec![low];
+    let mut window_cross_sections =
+        vec![interpolate_at(energies, cross_sections, law, low).expect("low lies within the grid")];
+    for (&e, &cs) in energies.iter().zip(cross_sections) {
+        if e > low && e < high {
+            window_energies.push(e);
+            window_cross_sections.push(cs);
+        }
+    }
+    window_energies.push(high);
+    window_cross_sections.push(
+        interpolate_at(energies, cross_sections, law, high).expect("high lies within the grid"),
+    );
 
-    if temperature_kev <= 0.0 {
-        return Err("Temperature must be positive".to_string());
+    let a = reduced_mass_factor(atomic_mass, float(NEUTRON_MASS));
+    let kt = thermal_energy(temperature_kev);
+    let integral = |energies: &[f64], cross_sections: &[f64]| {
+        maxwellian_integral(energies, cross_sections, a, kt, weighting, method, law)
+    };
+    let total = integral(energies, cross_sections);
+    if total == 0.0 {
+        return Err("The Maxwellian integral vanishes, no contribution to compare".to_string());
     }
+    let window = integral(&window_energies, &window_cross_sections);
 
-    // Convert temperature from keV to Kelvin
-    // kT [MeV] = temperature_kev * 1e-3
-    // T [K] = kT [MeV] / KB
-    let temperature_k = (temperature_kev * 1e-3) / KB;
+    Ok(WindowContribution {
+        contribution_mb: units::barns_to_millibarns(normalization(a, kt, weighting) * window),
+        fraction: window / total,
+    })
+}
 
-    // Reduced mass factor: a = A/(1+A)
-    let a = atomic_mass / (1.0 + atomic_mass);
+/// Scaling of the cross section over an energy window
+///
+/// Comparing the MACS with and without it shows how sensitive the MACS is to
+/// the data in the window, e.g. to a possible normalization error of the
+/// measurements there.
+#[derive(Debug, Clone, Copy)]
+pub struct Perturbation {
+    /// Lower edge of the window in MeV
+    pub start: f64,
+    /// Upper edge of the window in MeV
+    pub end: f64,
+    /// Factor applied to the cross sections tabulated inside the window
+    pub factor: f64,
+}
 
-    // Integrand function: σ(E) * E * exp(-a*E/(kT))
-    // where kT = KB * T
-    let f = |e: f64, cs: f64| -> f64 { cs * e * (-(a * e) / (KB * temperature_k)).exp() };
+impl Perturbation {
+    /// Cross sections with the points inside the window scaled by the factor
+    ///
+    /// Only grid points are scaled, so the intervals crossing the window
+    /// edges change linearly between the scaled and unscaled values.
+    pub fn apply(&self, energies: &[f64], cross_sections: &[f64]) -> Vec<f64> {
+        energies
+            .iter()
+            .zip(cross_sections)
+            .map(|(&e, &cs)| {
+                if (self.start..=self.end).contains(&e) {
+                    cs * self.factor
+                } else {
+                    cs
+                }
+            })
+            .collect()
+    }
+}
 
-    // Calculate the integral using the trapezoidal rule
-    let mut macs_integral = 0.0;
-    for i in 1..energies.len() {
-        macs_integral += trapezoid_area(
-            &f,
-            energies[i - 1],
-            energies[i],
-            cross_sections[i - 1],
-            cross_sections[i],
-        );
+/// Smooth background cross section added to the data
+///
+/// Stands in for a reaction mechanism missing from the evaluation, e.g.
+/// direct capture, to see how much it would change the MACS.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Background {
+    /// Constant cross section in barns
+    Constant(f64),
+    /// 1/v cross section in barns at the thermal energy of 0.0253 eV
+    InverseVelocity(f64),
+}
+
+impl Background {
+    /// Background cross section in barns at a laboratory energy in MeV
+    pub fn value_at(&self, energy: f64) -> f64 {
+        match *self {
+            Background::Constant(value) => value,
+            Background::InverseVelocity(thermal) => thermal * (THERMAL_ENERGY_MEV / energy).sqrt(),
+        }
     }
 
-    // Normalization factor: 2*a²/(√π * (kT)²)
-    let kt = KB * temperature_k;
-    let normalization = (2.0 * a.powi(2)) / (PI.sqrt() * kt.powi(2));
+    /// Cross sections with the background added at each grid point
+    ///
+    /// Between grid points the sum follows the interpolation law of the
+    /// data, which is exact for a constant background and close to a 1/v
+    /// one on a grid dense enough for the data itself.
+    pub fn apply(&self, energies: &[f64], cross_sections: &[f64]) -> Vec<f64> {
+        energies
+            .iter()
+            .zip(cross_sections)
+            .map(|(&e, &cs)| cs + self.value_at(e))
+            .collect()
+    }
+}
 
-    // MACS in barns
-    let macs_barns = normalization * macs_integral;
+impl std::fmt::Display for Background {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Background::Constant(value) => write!(f, "constant {} b", value),
+            Background::InverseVelocity(thermal) => write!(f, "1/v, {} b at 0.0253 eV", thermal),
+        }
+    }
+}
+
+/// Default fraction of its peak value below which the Maxwellian weight is
+/// considered negligible when extrapolating to high energies
+pub const WEIGHT_CUTOFF: f64 = 1e-6;
 
-    // Convert from barns to millibarns
-    Ok(macs_barns * 1000.0)
+/// Value of a·E/kT at which exp(-a·E/kT) underflows, bounding the
+/// high-energy extrapolation when no cutoff is applied
+const MAX_EXPONENT: f64 = 700.0;
+
+/// Number of Simpson intervals used to integrate the low-energy tail
+const TAIL_INTERVALS: usize = 4096;
+
+/// Number of Simpson intervals per e-folding (kT/a) of the Maxwellian
+/// used to integrate the high-energy tail
+const TAIL_INTERVALS_PER_KT: f64 = 64.0;
+
+/// Integrates `f` over [x1, x2] with the composite Simpson rule on `n` (even) intervals
+fn simpson(f: &dyn Fn(f64) -> f64, x1: f64, x2: f64, n: usize) -> f64 {
+    let h = (x2 - x1) / n as f64;
+    let interior: f64 = (1..n)
+        .map(|i| {
+            let weight = if i % 2 == 1 { 4.0 } else { 2.0 };
+            weight * f(x1 + i as f64 * h)
+        })
+        .sum();
+    (f(x1) + interior + f(x2)) * h / 3.0
+}
+
+/// Contributions to the MACS from outside the tabulated energy range
+#[derive(Debug, Clone, Copy)]
+pub struct ExtrapolatedTails {
+    /// Contribution below the first grid point in millibarns
+    pub low_mb: f64,
+    /// Contribution above the last grid point in millibarns
+    pub high_mb: f64,
+    /// Energy in MeV up to which the high-energy tail was integrated
+    pub upper_energy: f64,
+    /// Whether the weight cutoff lay beyond the underflow limit, so the tail
+    /// was stopped there instead
+    pub capped: bool,
+}
+
+impl ExtrapolatedTails {
+    /// Shares of the MACS from below the grid, the grid and above the grid
+    ///
+    /// # Arguments
+    /// * `grid_mb` - MACS from the tabulated region alone in millibarns
+    ///
+    /// # Returns
+    /// The (low, tabulated, high) fractions of the total MACS, or `None` if
+    /// the total vanishes
+    pub fn fractions(&self, grid_mb: f64) -> Option<(f64, f64, f64)> {
+        let total = self.low_mb + grid_mb + self.high_mb;
+        (total != 0.0).then(|| (self.low_mb / total, grid_mb / total, self.high_mb / total))
+    }
+}
+
+/// Computes the MACS contributions of the cross section extrapolated beyond its grid
+///
+/// Below the first point the cross section is extended as 1/v,
+/// σ(E) = σ₀·√(E₀/E), down to zero energy, unless the reaction has a
+/// `threshold`: a 1/v rise is unphysical there, so nothing is added below the
+/// grid. Above the last point the cross section is held constant. The high-energy tail is integrated up to where the Maxwellian
+/// weight falls below the `cutoff` fraction of its peak (usually
+/// [`WEIGHT_CUTOFF`]), or, without a cutoff, up to where the exponential
+/// underflows. A cutoff so small that it lies beyond the underflow limit is
+/// capped there.
+///
+/// # Arguments
+/// * `energies` - Energy points in MeV
+/// * `cross_sections` - Cross section values in barns
+/// * `atomic_mass` - Atomic mass number
+/// * `temperature_kev` - Temperature in keV
+/// * `weighting` - Averaging convention (see [`MaxwellianWeighting`])
+/// * `cutoff` - Fraction of the peak weight at which to stop the high-energy
+///   tail, or `None` to integrate up to the underflow limit
+/// * `threshold` - Threshold energy of the reaction in MeV, if it has one
+///
+/// # Returns
+/// * `Ok(tails)` - The two tail contributions, to be added to the grid MACS
+/// * `Err(msg)` - Error message if inputs are invalid
+pub fn extrapolated_tails(
+    energies: &[f64],
+    cross_sections: &[f64],
+    atomic_mass: f64,
+    temperature_kev: f64,
+    weighting: MaxwellianWeighting,
+    cutoff: Option<f64>,
+    threshold: Option<f64>,
+) -> Result<ExtrapolatedTails, String> {
+    validate_inputs(energies, cross_sections, atomic_mass, temperature_kev)?;
+
+    let a = reduced_mass_factor(atomic_mass, float(NEUTRON_MASS));
+    let kt = thermal_energy(temperature_kev);
+    let weight = |e: f64| maxwellian_weight(e, a, kt, weighting);
+    let norm = normalization(a, kt, weighting);
+
+    // Low-energy 1/v tail, integrated in u = √E where σ(E)·dE = 2σ₀√E₀·du
+    let (e_first, cs_first) = (energies[0], cross_sections[0]);
+    let low = if e_first > 0.0 && cs_first != 0.0 && threshold.is_none() {
+        let scale = 2.0 * cs_first * e_first.sqrt();
+        simpson(
+            &|u: f64| scale * weight(u * u),
+            0.0,
+            e_first.sqrt(),
+            TAIL_INTERVALS,
+        )
+    } else {
+        0.0
+    };
+
+    // High-energy constant tail
+    let last = energies.len() - 1;
+    let (e_last, cs_last) = (energies[last], cross_sections[last]);
+    let underflow_energy = MAX_EXPONENT * kt / a;
+    let (upper_energy, capped) = match cutoff {
+        Some(fraction) => {
+            let peak_energy = match weighting {
+                MaxwellianWeighting::Flux => kt / a,
+                MaxwellianWeighting::NumberDensity => kt / (2.0 * a),
+            };
+            let threshold = fraction * weight(peak_energy);
+            let mut upper = e_last.max(peak_energy);
+            while weight(upper) > threshold && upper < underflow_energy {
+                upper *= 1.1;
+            }
+            if upper > underflow_energy {
+                (underflow_energy, true)
+            } else {
+                (upper, false)
+            }
+        }
+        None => (underflow_energy, false),
+    };
+    let high = if upper_energy > e_last && cs_last != 0.0 {
+        let intervals = ((upper_energy - e_last) * a / kt * TAIL_INTERVALS_PER_KT).ceil() as usize;
+        let intervals = (intervals.max(2) + 1) & !1;
+        cs_last * simpson(&weight, e_last, upper_energy, intervals)
+    } else {
+        0.0
+    };
+
+    Ok(ExtrapolatedTails {
+        low_mb: units::barns_to_millibarns(norm * low),
+        high_mb: units::barns_to_millibarns(norm * high),
+        upper_energy: upper_energy.max(e_last),
+        capped,
+    })
+}
+
+/// Computes the MACS contributions of the integrand ramped down at the grid edges
+///
+/// Without extrapolation the integral stops hard at the first and last grid
+/// points, as if the integrand dropped to zero there. Instead, the integrand
+/// is extended linearly from its edge value down to zero over half of the
+/// adjacent grid interval, adding a triangle f(E)·h/4 at each end, with h the
+/// width of the edge interval. The lower ramp is shortened so as not to reach
+/// below zero energy. This is a milder edge model than [`extrapolated_tails`]:
+/// it assumes only that the data would have continued for about half a step.
+///
+/// # Arguments
+/// * `energies` - Energy points in MeV
+/// * `cross_sections` - Cross section values in barns
+/// * `atomic_mass` - Atomic mass number
+/// * `temperature_kev` - Temperature in keV
+/// * `weighting` - Averaging convention (see [`MaxwellianWeighting`])
+///
+/// # Returns
+/// * `Ok((low_mb, high_mb))` - The contributions below the first and above
+///   the last grid point, to be added to the grid MACS
+/// * `Err(msg)` - Error message if inputs are invalid
+pub fn half_interval_edges(
+    energies: &[f64],
+    cross_sections: &[f64],
+    atomic_mass: f64,
+    temperature_kev: f64,
+    weighting: MaxwellianWeighting,
+) -> Result<(f64, f64), String> {
+    validate_inputs(energies, cross_sections, atomic_mass, temperature_kev)?;
+    if energies.len() < 2 {
+        return Err("Edge ramps need at least two grid points".to_string());
+    }
+
+    let a = reduced_mass_factor(atomic_mass, float(NEUTRON_MASS));
+    let kt = thermal_energy(temperature_kev);
+    let norm = normalization(a, kt, weighting);
+    let last = energies.len() - 1;
+
+    let low_width = ((energies[1] - energies[0]) / 2.0).min(energies[0].max(0.0));
+    let low = integrand(energies[0], cross_sections[0], a, kt, weighting) * low_width / 2.0;
+    let high_width = (energies[last] - energies[last - 1]) / 2.0;
+    let high = integrand(energies[last], cross_sections[last], a, kt, weighting) * high_width / 2.0;
+
+    Ok((
+        units::barns_to_millibarns(norm * low),
+        units::barns_to_millibarns(norm * high),
+    ))
+}
+
+/// Default share of the Maxwellian weight that may lie outside the tabulated
+/// energies before a temperature is considered unsupported by the data
+pub const SUPPORT_THRESHOLD: f64 = 1e-3;
+
+/// Shares of the Maxwellian weight lying below and above the tabulated energies
+///
+/// The weight is the MACS a constant cross section would get, relative to
+/// its analytic value. Whatever lies outside the grid has to come from
+/// outside the data, by extrapolation or not at all, so a large share means
+/// the grid does not bracket the energies that matter at this temperature.
+/// The weight is integrated in u = √E, where it is smooth down to zero.
+///
+/// # Arguments
+/// * `energies` - Energy points in MeV
+/// * `atomic_mass` - Atomic mass number
+/// * `temperature_kev` - Temperature in keV
+/// * `weighting` - Averaging convention (see [`MaxwellianWeighting`])
+///
+/// # Returns
+/// The (below, above) fractions, each between 0 and 1
+pub fn maxwellian_outside(
+    energies: &[f64],
+    atomic_mass: f64,
+    temperature_kev: f64,
+    weighting: MaxwellianWeighting,
+) -> (f64, f64) {
+    let (first, last) = match (energies.first(), energies.last()) {
+        (Some(&first), Some(&last)) => (first.max(0.0), last),
+        _ => (0.0, 0.0),
+    };
+    let a = reduced_mass_factor(atomic_mass, float(NEUTRON_MASS));
+    let kt = thermal_energy(temperature_kev);
+    let underflow_energy = MAX_EXPONENT * kt / a;
+    let total = constant_cross_section_macs(1.0, weighting) / units::barns_to_millibarns(1.0);
+    let share = |low: f64, high: f64| {
+        let high = high.min(underflow_energy);
+        if high <= low {
+            return 0.0;
+        }
+        let weight = simpson(
+            &|u: f64| 2.0 * u * maxwellian_weight(u * u, a, kt, weighting),
+            low.sqrt(),
+            high.sqrt(),
+            TAIL_INTERVALS,
+        );
+        (normalization(a, kt, weighting) * weight / total).clamp(0.0, 1.0)
+    };
+    let below = share(0.0, first);
+    let covered = share(first, last);
+    (below, (1.0 - below - covered).max(0.0))
+}
+
+/// How well an energy grid brackets the Maxwellian weight at a temperature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Coverage {
+    /// At most the allowed share of the weight lies outside the data
+    Full,
+    /// The data starts too high: the weight is missing below the first point
+    LowTruncated,
+    /// The data ends too low: the weight is missing above the last point
+    HighTruncated,
+    /// The weight is missing at both ends
+    Both,
+}
+
+impl Coverage {
+    /// Classifies the shares of the weight outside the data
+    ///
+    /// The temperature is covered when at most `threshold` lies outside in
+    /// total. Otherwise an end is blamed when it misses more than half the
+    /// allowed share, so at least one end always is.
+    pub fn from_outside(below: f64, above: f64, threshold: f64) -> Self {
+        if below + above <= threshold {
+            return Coverage::Full;
+        }
+        match (below > threshold / 2.0, above > threshold / 2.0) {
+            (true, true) => Coverage::Both,
+            (true, false) => Coverage::LowTruncated,
+            _ => Coverage::HighTruncated,
+        }
+    }
+}
+
+impl std::fmt::Display for Coverage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Coverage::Full => "full",
+            Coverage::LowTruncated => "truncated at low energy",
+            Coverage::HighTruncated => "truncated at high energy",
+            Coverage::Both => "truncated at both ends",
+        })
+    }
+}
+
+/// Coverage of a temperature by an energy grid (see [`maxwellian_outside`])
+///
+/// # Arguments
+/// * `energies` - Energy points in MeV
+/// * `atomic_mass` - Atomic mass number
+/// * `temperature_kev` - Temperature in keV
+/// * `weighting` - Averaging convention (see [`MaxwellianWeighting`])
+/// * `threshold` - Largest share of the weight allowed outside the data
+pub fn grid_coverage(
+    energies: &[f64],
+    atomic_mass: f64,
+    temperature_kev: f64,
+    weighting: MaxwellianWeighting,
+    threshold: f64,
+) -> Coverage {
+    let (below, above) = maxwellian_outside(energies, atomic_mass, temperature_kev, weighting);
+    Coverage::from_outside(below, above, threshold)
+}
+
+/// Whether a dataset covers a temperature, with flux weighting and the
+/// default [`SUPPORT_THRESHOLD`]
+///
+/// Lets data be screened before computing anything with it.
+///
+/// # Returns
+/// * `Ok(coverage)` - Coverage of the temperature by the dataset's energies
+/// * `Err(msg)` - If the dataset declares an unrecognized unit
+pub fn dataset_covers(
+    dataset: &CrossSectionDataset,
+    atomic_mass: f64,
+    temperature_kev: f64,
+) -> Result<Coverage, String> {
+    let energy_unit = dataset.column_units()?.0.unwrap_or(EnergyUnit::Ev);
+    let energies: Vec<f64> = dataset
+        .points
+        .iter()
+        .map(|p| energy_unit.to_mev(p.energy))
+        .collect();
+    Ok(grid_coverage(
+        &energies,
+        atomic_mass,
+        temperature_kev,
+        MaxwellianWeighting::Flux,
+        SUPPORT_THRESHOLD,
+    ))
+}
+
+/// Trial temperatures per decade when searching the supported range
+const SUPPORT_SEARCH_PER_DECADE: f64 = 20.0;
+
+/// Bisection steps refining each end of the supported range
+const SUPPORT_BISECTION_STEPS: usize = 40;
+
+/// Range of temperatures at which the grid brackets the Maxwellian weight
+///
+/// This is [`grid_coverage`] in reverse: the temperatures between
+/// [`MIN_TEMPERATURE_KEV`] and [`MAX_TEMPERATURE_KEV`] are scanned on a log
+/// grid, and the ends of the range where at most `threshold` of the weight
+/// lies outside the data are refined by bisection. Too low a temperature puts
+/// the weight below the first grid point, too high a one above the last.
+///
+/// # Arguments
+/// * `energies` - Energy points in MeV
+/// * `atomic_mass` - Atomic mass number
+/// * `weighting` - Averaging convention (see [`MaxwellianWeighting`])
+/// * `threshold` - Largest share of the weight allowed outside the data
+///
+/// # Returns
+/// * `Some((lowest, highest))` - Temperatures in keV
+/// * `None` - If no temperature is supported
+pub fn supported_temperature_range(
+    energies: &[f64],
+    atomic_mass: f64,
+    weighting: MaxwellianWeighting,
+    threshold: f64,
+) -> Option<(f64, f64)> {
+    let supported = |temperature: f64| {
+        grid_coverage(energies, atomic_mass, temperature, weighting, threshold) == Coverage::Full
+    };
+    let (start, end) = (MIN_TEMPERATURE_KEV.log10(), MAX_TEMPERATURE_KEV.log10());
+    let steps = ((end - start) * SUPPORT_SEARCH_PER_DECADE).round() as usize;
+    let trial = |i: usize| 10f64.powf(start + (end - start) * i as f64 / steps as f64);
+    let first = (0..=steps).find(|&i| supported(trial(i)))?;
+    let last = (first..=steps).rev().find(|&i| supported(trial(i)))?;
+
+    // Bisects in log between a supported and an unsupported temperature
+    let refine = |mut inside: f64, mut outside: f64| {
+        for _ in 0..SUPPORT_BISECTION_STEPS {
+            let middle = (inside * outside).sqrt();
+            if supported(middle) {
+                inside = middle;
+            } else {
+                outside = middle;
+            }
+        }
+        inside
+    };
+    let lowest = match first {
+        0 => trial(0),
+        i => refine(trial(i), trial(i - 1)),
+    };
+    let highest = match last {
+        i if i == steps => trial(steps),
+        i => refine(trial(i), trial(i + 1)),
+    };
+    Some((lowest, highest))
+}
+
+/// Threshold energy of a cross section that starts with zeros
+///
+/// Evaluations tabulate a threshold reaction from its threshold with σ = 0, so
+/// the threshold is the last zero point before the first nonzero one.
+///
+/// # Returns
+/// * `Some(energy)` - Threshold in the units of `energies`
+/// * `None` - If the first point is nonzero or all points are zero
+pub fn detect_threshold(energies: &[f64], cross_sections: &[f64]) -> Option<f64> {
+    match cross_sections.iter().position(|&cs| cs != 0.0) {
+        Some(first) if first > 0 => energies.get(first - 1).copied(),
+        _ => None,
+    }
+}
+
+/// Cross sections with the points below a threshold energy set to zero
+pub fn zero_below_threshold(energies: &[f64], cross_sections: &[f64], threshold: f64) -> Vec<f64> {
+    energies
+        .iter()
+        .zip(cross_sections)
+        .map(|(&e, &cs)| if e < threshold { 0.0 } else { cs })
+        .collect()
+}
+
+/// Unit of the energies given to [`calculate_macs_with_options`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EnergyUnit {
+    /// Electronvolts, as tabulated by EXFOR
+    Ev,
+    /// Kiloelectronvolts
+    Kev,
+    /// Megaelectronvolts, the unit of the integral
+    #[default]
+    Mev,
+}
+
+impl EnergyUnit {
+    /// Reads a unit symbol such as "eV" or "MeV", ignoring case
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        match symbol.to_ascii_lowercase().as_str() {
+            "ev" => Some(EnergyUnit::Ev),
+            "kev" => Some(EnergyUnit::Kev),
+            "mev" => Some(EnergyUnit::Mev),
+            _ => None,
+        }
+    }
+
+    /// Converts an energy in this unit to MeV
+    pub fn to_mev(self, energy: f64) -> f64 {
+        match self {
+            EnergyUnit::Ev => units::ev_to_mev(energy),
+            EnergyUnit::Kev => units::kev_to_mev(energy),
+            EnergyUnit::Mev => energy,
+        }
+    }
+}
+
+/// Settings of a MACS calculation
+///
+/// The default is the standard MACS: flux weighting, trapezoidal rule on the
+/// grid, lin-lin interpolation, energies in MeV and no extrapolation beyond
+/// the tabulated range.
+#[derive(Clone, Copy)]
+pub struct MacsOptions<'a> {
+    /// Averaging convention (see [`MaxwellianWeighting`])
+    pub weighting: MaxwellianWeighting,
+    /// Numerical integration scheme
+    pub method: IntegrationMethod,
+    /// Interpolation law of the cross section between grid points
+    pub law: &'a dyn Interpolation,
+    /// Unit of the energies, cross sections being in barns
+    pub energy_unit: EnergyUnit,
+    /// Whether to add the tails of [`extrapolated_tails`] to the grid MACS
+    pub extrapolate: bool,
+    /// Fraction of the peak weight at which the high-energy tail stops, or
+    /// `None` to integrate up to the underflow limit
+    pub weight_cutoff: Option<f64>,
+    /// Threshold energy of the reaction in `energy_unit`, below which the
+    /// cross section is zero; detected from leading zero points if `None`
+    pub threshold: Option<f64>,
+}
+
+impl Default for MacsOptions<'_> {
+    fn default() -> Self {
+        MacsOptions {
+            weighting: MaxwellianWeighting::Flux,
+            method: IntegrationMethod::Trapezoid,
+            law: &LinLin,
+            energy_unit: EnergyUnit::Mev,
+            extrapolate: false,
+            weight_cutoff: Some(WEIGHT_CUTOFF),
+            threshold: None,
+        }
+    }
+}
+
+/// Calculates the MACS of a tabulated cross section with the given settings
+///
+/// This is the entry point for computing a MACS from data already in memory:
+/// it performs no I/O and combines [`calculate_macs`] with the optional
+/// [`extrapolated_tails`]. The command line integrates downloaded data with it.
+///
+/// # Arguments
+/// * `energies` - Energy points, in `options.energy_unit`
+/// * `cross_sections` - Cross section values in barns
+/// * `atomic_mass` - Atomic mass number (e.g., 94 for Mo-94)
+/// * `temperature_kev` - Temperature in keV
+/// * `options` - Weighting, integration, interpolation, units and extrapolation
+///
+/// # Returns
+/// * `Ok(macs)` - MACS value in millibarns
+/// * `Err(msg)` - Error message if inputs are invalid
+///
+/// # Example
+/// ```
+/// # use macs_rs::{EnergyUnit, MacsOptions, calculate_macs_with_options};
+/// let energies = vec![1e3, 1e4, 1e5, 1e6]; // eV
+/// let cross_sections = vec![2.0, 0.8, 0.3, 0.1]; // barns
+/// let options = MacsOptions {
+///     energy_unit: EnergyUnit::Ev,
+///     extrapolate: true,
+///     ..MacsOptions::default()
+/// };
+/// let macs = calculate_macs_with_options(&energies, &cross_sections, 94.0, 30.0, &options)?;
+/// println!("MACS at 30 keV: {} mb", macs);
+/// # Ok::<(), String>(())
+/// ```
+pub fn calculate_macs_with_options(
+    energies: &[f64],
+    cross_sections: &[f64],
+    atomic_mass: f64,
+    temperature_kev: f64,
+    options: &MacsOptions,
+) -> Result<f64, String> {
+    let converted: Vec<f64>;
+    let energies = match options.energy_unit {
+        EnergyUnit::Mev => energies,
+        unit => {
+            converted = energies.iter().map(|&e| unit.to_mev(e)).collect();
+            &converted
+        }
+    };
+    let thresholded: Vec<f64>;
+    let (cross_sections, threshold) = match options.threshold {
+        Some(threshold) => {
+            let threshold = options.energy_unit.to_mev(threshold);
+            thresholded = zero_below_threshold(energies, cross_sections, threshold);
+            (thresholded.as_slice(), Some(threshold))
+        }
+        None => (cross_sections, detect_threshold(energies, cross_sections)),
+    };
+    let mut macs = calculate_macs(
+        energies,
+        cross_sections,
+        atomic_mass,
+        temperature_kev,
+        options.weighting,
+        options.method,
+        options.law,
+    )?;
+    if options.extrapolate {
+        let tails = extrapolated_tails(
+            energies,
+            cross_sections,
+            atomic_mass,
+            temperature_kev,
+            options.weighting,
+            options.weight_cutoff,
+            threshold,
+        )?;
+        macs += tails.low_mb + tails.high_mb;
+    }
+    Ok(macs)
+}
+
+/// Callback told (completed, total) as each temperature of a table is computed
+///
+/// Lets a frontend follow long batch computations without tying the
+/// calculation to a particular display; the CLI's progress bar is one
+/// consumer. It is `Send + Sync` so that the computation may run on another
+/// thread or async task than the display.
+pub type ProgressCallback<'a> = Option<&'a (dyn Fn(usize, usize) + Send + Sync)>;
+
+/// Calculates the MACS at each of several temperatures
+///
+/// The batch form of [`calculate_macs_with_options`], reporting to `progress`
+/// after each temperature.
+///
+/// # Arguments
+/// * `energies` - Energy points, in `options.energy_unit`
+/// * `cross_sections` - Cross section values in barns
+/// * `atomic_mass` - Atomic mass number (e.g., 94 for Mo-94)
+/// * `temperatures` - Temperatures in keV
+/// * `options` - Weighting, integration, interpolation, units and extrapolation
+/// * `progress` - Called with (completed, total) after each temperature
+///
+/// # Returns
+/// * `Ok(results)` - One result per temperature, in order
+/// * `Err(msg)` - Error message if inputs are invalid
+///
+/// # Example
+/// ```
+/// # use macs_rs::{MacsOptions, calculate_macs_table};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// let energies = vec![1e-3, 1e-2, 1e-1, 1.0]; // MeV
+/// let cross_sections = vec![2.0, 0.8, 0.3, 0.1]; // barns
+/// let completed = AtomicUsize::new(0);
+/// let progress = |done: usize, _total: usize| completed.store(done, Ordering::Relaxed);
+/// let table = calculate_macs_table(
+///     &energies,
+///     &cross_sections,
+///     94.0,
+///     &[5.0, 30.0, 90.0],
+///     &MacsOptions::default(),
+///     Some(&progress),
+/// )?;
+/// assert_eq!(table.len(), 3);
+/// assert_eq!(completed.load(Ordering::Relaxed), 3);
+/// # Ok::<(), String>(())
+/// ```
+pub fn calculate_macs_table(
+    energies: &[f64],
+    cross_sections: &[f64],
+    atomic_mass: f64,
+    temperatures: &[f64],
+    options: &MacsOptions,
+    progress: ProgressCallback<'_>,
+) -> Result<Vec<MacsResult>, String> {
+    let mut results = Vec::with_capacity(temperatures.len());
+    for (index, &temperature) in temperatures.iter().enumerate() {
+        let macs = calculate_macs_with_options(
+            energies,
+            cross_sections,
+            atomic_mass,
+            temperature,
+            options,
+        )?;
+        results.push(MacsResult::new(temperature, macs));
+        if let Some(progress) = progress {
+            progress(index + 1, temperatures.len());
+        }
+    }
+    Ok(results)
+}
+
+/// Mean and sample standard deviation of MACS values from several libraries
+///
+/// When no point-wise uncertainties are available, the spread of the MACS
+/// obtained from different evaluations is a pragmatic uncertainty estimate.
+///
+/// # Returns
+/// * `Some((mean, std_dev))` - Mean and standard deviation (0 for a single value)
+/// * `None` - If `values` is empty
+pub fn library_spread(values: &[f64]) -> Option<(f64, f64)> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    if values.len() == 1 {
+        return Some((mean, 0.0));
+    }
+
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    Some((mean, variance.sqrt()))
+}
+
+/// MACS computed at a single temperature
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct MacsResult {
+    /// Temperature (kT) in keV
+    pub temperature_kev: f64,
+    /// MACS in millibarns
+    pub macs_mb: f64,
+    /// Branching ratio applied as a multiplicative factor on the MACS
+    #[serde(default = "unit_factor")]
+    pub branching: f64,
+    /// Renormalization factor applied to the cross section before integrating
+    #[serde(default = "unit_factor")]
+    pub renormalization: f64,
+    /// Doppler-broadening temperature (K) at which the cross section was processed
+    #[serde(default)]
+    pub processing_temperature: f64,
+    /// Estimated numerical integration error in millibarns, if requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub integration_error_mb: Option<f64>,
+    /// MACS with the high-energy tail stopped at the weight cutoff, reported
+    /// alongside the full-range `macs_mb` when the cutoff is disabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cutoff_macs_mb: Option<f64>,
+    /// Energy in MeV up to which the high-energy tail was extrapolated, if it was
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extrapolation_upper_mev: Option<f64>,
+    /// Fraction of the MACS from the cross section extrapolated below the grid
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub low_extrapolation_fraction: Option<f64>,
+    /// Fraction of the MACS from the tabulated energy range
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tabulated_fraction: Option<f64>,
+    /// Fraction of the MACS from the cross section extrapolated above the grid
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub high_extrapolation_fraction: Option<f64>,
+    /// Maxwellian integral over the grid before normalization, in barn·MeV²
+    /// (barn·MeV^(3/2) for number-density weighting), if requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_integral: Option<f64>,
+    /// Stellar MACS in millibarns, the laboratory `macs_mb` times the stellar
+    /// enhancement factor, if an SEF table was given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stellar_macs_mb: Option<f64>,
+    /// Stellar enhancement factor MACS*/MACS_lab applied, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stellar_enhancement: Option<f64>,
+    /// Contribution of the --resonance window to the MACS in millibarns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resonance_mb: Option<f64>,
+    /// Fraction of the MACS over the grid coming from the --resonance window
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resonance_fraction: Option<f64>,
+    /// MACS in millibarns with the --perturb window scaled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub perturbed_macs_mb: Option<f64>,
+    /// Fractional change (perturbed - baseline) / baseline of the MACS
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub perturbation_change: Option<f64>,
+    /// Contribution of the --background cross section to the MACS in millibarns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub background_mb: Option<f64>,
+    /// MACS in millibarns with the other integration method, for --cross-check
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cross_check_macs_mb: Option<f64>,
+    /// Relative difference (other - chosen) / chosen of the two methods
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cross_check_difference: Option<f64>,
+    /// Astrophysical rate N_A⟨σv⟩ in cm³ mol⁻¹ s⁻¹, if requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reaction_rate: Option<f64>,
+    /// Gamow peak energy E₀ in keV (center of mass), for charged projectiles
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gamow_peak_kev: Option<f64>,
+    /// 1/e width ΔE₀ of the Gamow peak in keV, for charged projectiles
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gamow_width_kev: Option<f64>,
+    /// MACS divided by the MACS at the --normalize-to temperature
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalized_macs: Option<f64>,
+}
+
+impl MacsResult {
+    /// Result with only the MACS, unit branching and renormalization and
+    /// none of the optional diagnostics
+    ///
+    /// Fill in the rest with struct update syntax, `..MacsResult::new(kt, macs)`.
+    pub fn new(temperature_kev: f64, macs_mb: f64) -> Self {
+        MacsResult {
+            temperature_kev,
+            macs_mb,
+            branching: 1.0,
+            renormalization: 1.0,
+            processing_temperature: 0.0,
+            integration_error_mb: None,
+            cutoff_macs_mb: None,
+            extrapolation_upper_mev: None,
+            low_extrapolation_fraction: None,
+            tabulated_fraction: None,
+            high_extrapolation_fraction: None,
+            raw_integral: None,
+            stellar_macs_mb: None,
+            stellar_enhancement: None,
+            resonance_mb: None,
+            resonance_fraction: None,
+            perturbed_macs_mb: None,
+            perturbation_change: None,
+            cross_check_macs_mb: None,
+            cross_check_difference: None,
+            background_mb: None,
+            reaction_rate: None,
+            gamow_peak_kev: None,
+            gamow_width_kev: None,
+            normalized_macs: None,
+        }
+    }
+}
+
+/// MACS of one target and library over a set of temperatures
+///
+/// This is what a single-library run writes with `--output-format json`.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct MacsTable {
+    /// Target nucleus in EXFOR notation (e.g., "Mo-94")
+    pub target: String,
+    /// Reaction type (e.g., "n,g")
+    pub reaction: String,
+    /// Nuclear data library the cross section was taken from
+    pub library: String,
+    /// Atomic mass of the target in amu
+    pub atomic_mass: f64,
+    /// MACS at each requested temperature
+    pub results: Vec<MacsResult>,
+    /// Cross section averaged over a user-supplied neutron spectrum in millibarns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spectrum_average_mb: Option<f64>,
+    /// Thermal cross section σ(0.0253 eV) in barns, for `--quantities thermal`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thermal_cross_section_b: Option<f64>,
+    /// Resonance integral above 0.5 eV in barns, for `--quantities ri`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resonance_integral_b: Option<f64>,
+    /// Datasets the results were computed from, embedded on request
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub datasets: Vec<CrossSectionDataset>,
+    /// Energy ranges taken from evaluated and substituted experimental data
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub data_sources: Vec<SourceRange>,
+    /// Analytic fit of the MACS curve, if requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fit: Option<MacsFit>,
+    /// Set when the cross section was angle-integrated from differential data
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub approximate: bool,
+}
+
+impl MacsTable {
+    /// Interpolates the stored MACS curve at a temperature
+    ///
+    /// This is an interpolation of the computed results, not a new integral:
+    /// the MACS varies smoothly with kT, so the two neighbouring temperatures
+    /// are joined log-log (linearly if a value is not positive). Its accuracy
+    /// depends on how densely the curve was sampled.
+    ///
+    /// # Arguments
+    /// * `temperature_kev` - Temperature (kT) in keV
+    ///
+    /// # Returns
+    /// * `Some(macs)` - Interpolated MACS in millibarns
+    /// * `None` - If the temperature lies outside the computed range
+    pub fn interpolate_at(&self, temperature_kev: f64) -> Option<f64> {
+        let mut curve: Vec<(f64, f64)> = self
+            .results
+            .iter()
+            .map(|r| (r.temperature_kev, r.macs_mb))
+            .collect();
+        curve.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let i = curve.partition_point(|&(t, _)| t < temperature_kev);
+        let &(t2, v2) = curve.get(i)?;
+        if t2 == temperature_kev {
+            return Some(v2);
+        }
+        let &(t1, v1) = curve.get(i.checked_sub(1)?)?;
+        let value = if v1 > 0.0 && v2 > 0.0 {
+            LogLog.interpolate(temperature_kev, t1, v1, t2, v2)
+        } else {
+            LinLin.interpolate(temperature_kev, t1, v1, t2, v2)
+        };
+        Some(value)
+    }
+}
+
+/// Default for multiplicative factors missing from saved results
+fn unit_factor() -> f64 {
+    1.0
+}
+
+/// Accumulates the MACS point by point without storing the energy grid
+///
+/// Points must arrive in increasing energy order. Each new point closes a
+/// trapezoid with the previous one, which is added to the running integral of
+/// every requested temperature. Only the trapezoidal rule is supported since
+/// no neighbouring points are kept.
+pub struct StreamingMacs {
+    a: f64,
+    weighting: MaxwellianWeighting,
+    thermal_energies: Vec<f64>,
+    integrals: Vec<f64>,
+    previous: Option<(f64, f64)>,
+    points: usize,
+}
+
+impl StreamingMacs {
+    /// Creates an empty accumulator
+    ///
+    /// # Arguments
+    /// * `atomic_mass` - Atomic mass number (e.g., 94 for Mo-94)
+    /// * `temperatures_kev` - Temperatures in keV
+    /// * `weighting` - Averaging convention
+    pub fn new(
+        atomic_mass: f64,
+        temperatures_kev: &[f64],
+        weighting: MaxwellianWeighting,
+    ) -> Result<Self, String> {
+        for &temperature_kev in temperatures_kev {
+            validate_temperature(temperature_kev)?;
+        }
+        validate_atomic_mass(atomic_mass)?;
+
+        Ok(StreamingMacs {
+            a: reduced_mass_factor(atomic_mass, float(NEUTRON_MASS)),
+            weighting,
+            thermal_energies: temperatures_kev
+                .iter()
+                .map(|&t| thermal_energy(t))
+                .collect(),
+            integrals: vec![0.0; temperatures_kev.len()],
+            previous: None,
+            points: 0,
+        })
+    }
+
+    /// Adds a point (energy in MeV, cross section in barns)
+    pub fn push(&mut self, energy: f64, cross_section: f64) {
+        if let Some((e1, cs1)) = self.previous {
+            for (integral, &kt) in self.integrals.iter_mut().zip(&self.thermal_energies) {
+                let (a, weighting) = (self.a, self.weighting);
+                let f = |e: f64, cs: f64| -> f64 { integrand(e, cs, a, kt, weighting) };
+                *integral += trapezoid_area(&f, e1, energy, cs1, cross_section);
+            }
+        }
+        self.previous = Some((energy, cross_section));
+        self.points += 1;
+    }
+
+    /// Number of points received so far
+    pub fn points(&self) -> usize {
+        self.points
+    }
+
+    /// Returns the MACS in millibarns for each temperature, in input order
+    pub fn finish(&self) -> Result<Vec<f64>, String> {
+        if self.points == 0 {
+            return Err("Input vectors cannot be empty".to_string());
+        }
+
+        Ok(self
+            .integrals
+            .iter()
+            .zip(&self.thermal_energies)
+            .map(|(&integral, &kt)| {
+                units::barns_to_millibarns(normalization(self.a, kt, self.weighting) * integral)
+            })
+            .collect())
+    }
+}
+
+impl crate::exfor_client::PointSink for StreamingMacs {
+    fn push(&mut self, energy: f64, cross_section: f64) {
+        StreamingMacs::push(self, energy, cross_section);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpolation::LogLog;
+
+    /// `n` log-spaced energies in MeV from `low` to `high`
+    fn log_grid(low: f64, high: f64, n: usize) -> Vec<f64> {
+        let (low, high) = (low.ln(), high.ln());
+        (0..n)
+            .map(|i| (low + (high - low) * i as f64 / (n - 1) as f64).exp())
+            .collect()
+    }
+
+    fn macs(energies: &[f64], cross_sections: &[f64], weighting: MaxwellianWeighting) -> f64 {
+        calculate_macs(
+            energies,
+            cross_sections,
+            94.0,
+            30.0,
+            weighting,
+            IntegrationMethod::Trapezoid,
+            &LinLin,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn weightings_differ_for_a_constant_cross_section() {
+        let energies = log_grid(1e-11, 20.0, 20_000);
+        let cross_sections = vec![1.0; energies.len()];
+        let flux = macs(&energies, &cross_sections, MaxwellianWeighting::Flux);
+        let density = macs(
+            &energies,
+            &cross_sections,
+            MaxwellianWeighting::NumberDensity,
+        );
+
+        assert!((flux / (2000.0 / PI.sqrt()) - 1.0).abs() < 1e-4, "{}", flux);
+        assert!((density / 1000.0 - 1.0).abs() < 1e-4, "{}", density);
+        assert!((flux / density - 2.0 / PI.sqrt()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn weightings_ratio_depends_on_the_cross_section_shape() {
+        let energies = log_grid(1e-11, 20.0, 20_000);
+        let one_over_v: Vec<f64> = energies.iter().map(|e| (2.53e-8 / e).sqrt()).collect();
+        let flux = macs(&energies, &one_over_v, MaxwellianWeighting::Flux);
+        let density = macs(&energies, &one_over_v, MaxwellianWeighting::NumberDensity);
+
+        assert!((flux / density - PI.sqrt() / 2.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn adaptive_beats_uniform_refinement_for_the_same_evaluations() {
+        // ∫ E⁻² dE from 1 to 100 = 0.99, steep near the lower bound
+        let evaluations = std::cell::Cell::new(0usize);
+        let f = |_: f64, cs: f64| {
+            evaluations.set(evaluations.get() + 1);
+            cs
+        };
+        let sigma = |e: f64| e.powi(-2);
+        let (x1, x2) = (1.0, 100.0);
+        let exact = 0.99;
+
+        let whole = trapezoid_area(&f, x1, x2, sigma(x1), sigma(x2));
+        let (adaptive, error_estimate) =
+            adaptive_trapezoid(&f, &LogLog, x1, x2, sigma(x1), sigma(x2), whole, 0.0, 0);
+        let adaptive_evaluations = evaluations.replace(0);
+        let adaptive_error = (adaptive - exact).abs();
+
+        let panels = adaptive_evaluations / 2;
+        let width = (x2 - x1) / panels as f64;
+        let uniform: f64 = (0..panels)
+            .map(|i| {
+                let (a, b) = (x1 + i as f64 * width, x1 + (i + 1) as f64 * width);
+                trapezoid_area(&f, a, b, sigma(a), sigma(b))
+            })
+            .sum();
+        assert_eq!(evaluations.get(), adaptive_evaluations);
+        let uniform_error = (uniform - exact).abs();
+
+        assert!(adaptive_error < 1e-5, "{}", adaptive_error);
+        assert!(
+            uniform_error > 10.0 * adaptive_error,
+            "uniform {} vs adaptive {} with {} evaluations",
+            uniform_error,
+            adaptive_error,
+            adaptive_evaluations
+        );
+        assert!((error_estimate / adaptive_error - 1.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn implausible_atomic_mass_is_rejected() {
+        let energies = [0.01, 0.02, 0.03];
+        let cross_sections = [1.0, 1.0, 1.0];
+        for mass in [0.5, 9.4e3, f64::NAN] {
+            let result = calculate_macs(
+                &energies,
+                &cross_sections,
+                mass,
+                30.0,
+                MaxwellianWeighting::Flux,
+                IntegrationMethod::Trapezoid,
+                &LinLin,
+            );
+            assert!(result.is_err(), "mass {} accepted", mass);
+        }
+        assert!(validate_atomic_mass(94.0).is_ok());
+    }
+
+    #[test]
+    fn macs_table_round_trips_through_json() {
+        let mut result: MacsResult =
+            serde_json::from_str(r#"{"temperature_kev": 30.0, "macs_mb": 57.06}"#).unwrap();
+        assert_eq!((result.branching, result.renormalization), (1.0, 1.0));
+        result.integration_error_mb = Some(0.01);
+        result.normalized_macs = Some(1.0);
+        let table = MacsTable {
+            target: "Mo-94".to_string(),
+            reaction: "n,g".to_string(),
+            library: "JEFF-3.1".to_string(),
+            atomic_mass: 94.0,
+            results: vec![
+                MacsResult {
+                    temperature_kev: 5.0,
+                    macs_mb: 160.5,
+                    ..result.clone()
+                },
+                result,
+            ],
+            spectrum_average_mb: None,
+            thermal_cross_section_b: Some(0.02),
+            resonance_integral_b: None,
+            datasets: Vec::new(),
+            data_sources: Vec::new(),
+            fit: None,
+            approximate: true,
+        };
+
+        let json = serde_json::to_value(&table).unwrap();
+        assert!(json.get("resonance_integral_b").is_none());
+        assert!(json["results"][1].get("cutoff_macs_mb").is_none());
+
+        let again: MacsTable = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&again).unwrap(), json);
+        assert_eq!(again.results[0].temperature_kev, 5.0);
+        assert_eq!(again.results[1].integration_error_mb, Some(0.01));
+        assert!(again.approximate);
+    }
+
+    #[test]
+    fn lab_energies_are_unchanged() {
+        for energy in [1e-11, 0.03, 20.0] {
+            assert_eq!(to_lab_energy(energy, 94.0, EnergyFrame::Lab), energy);
+        }
+    }
+
+    #[test]
+    fn cm_energies_convert_to_the_lab_frame() {
+        // E_lab = E_cm (A + 1) / A
+        let lab = to_lab_energy(1.0, 94.0, EnergyFrame::Cm);
+        assert!((lab - 95.0 / 94.0).abs() < 1e-15);
+
+        // A cross section tabulated in CM energies gives the lab MACS once converted
+        let lab_energies = log_grid(1e-5, 1.0, 2000);
+        let cross_sections: Vec<f64> = lab_energies.iter().map(|e| 1.0 + e).collect();
+        let a = reduced_mass_factor(94.0, NEUTRON_MASS);
+        let converted: Vec<f64> = lab_energies
+            .iter()
+            .map(|e| to_lab_energy(e * a, 94.0, EnergyFrame::Cm))
+            .collect();
+        let flux = MaxwellianWeighting::Flux;
+        let expected = macs(&lab_energies, &cross_sections, flux);
+        assert!((macs(&converted, &cross_sections, flux) / expected - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn f32_and_f64_agree_on_the_same_dataset() {
+        let energies = log_grid(1e-8, 5.0, 3000);
+        let cross_sections: Vec<f64> = energies
+            .iter()
+            .map(|e| (2.53e-8 / e).sqrt() + 0.1 / (1.0 + ((e - 1e-3) / 1e-4).powi(2)))
+            .collect();
+        let energies_f32: Vec<f32> = energies.iter().map(|&e| e as f32).collect();
+        let cross_sections_f32: Vec<f32> = cross_sections.iter().map(|&s| s as f32).collect();
+
+        for method in [IntegrationMethod::Trapezoid, IntegrationMethod::Adaptive] {
+            let flux = MaxwellianWeighting::Flux;
+            let double = calculate_macs(
+                &energies,
+                &cross_sections,
+                94.0,
+                30.0,
+                flux,
+                method,
+                &LogLog,
+            )
+            .unwrap();
+            let single = calculate_macs(
+                &energies_f32,
+                &cross_sections_f32,
+                94.0f32,
+                30.0f32,
+                flux,
+                method,
+                &LogLog,
+            )
+            .unwrap();
+            assert!(
+                (f64::from(single) / double - 1.0).abs() < 1e-4,
+                "{:?}: f32 {} vs f64 {}",
+                method,
+                single,
+                double
+            );
+        }
+    }
+
+    /// A table holding the given (kT, MACS) results
+    fn table_of(curve: &[(f64, f64)]) -> MacsTable {
+        let results: Vec<String> = curve
+            .iter()
+            .map(|(t, macs)| format!(r#"{{"temperature_kev": {}, "macs_mb": {}}}"#, t, macs))
+            .collect();
+        serde_json::from_str(&format!(
+            r#"{{"target": "Mo-94", "reaction": "n,g", "library": "TEST", "atomic_mass": 94.0,
+                "results": [{}]}}"#,
+            results.join(", ")
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn interpolated_macs_matches_a_direct_calculation() {
+        let energies = log_grid(1e-8, 5.0, 3000);
+        let cross_sections: Vec<f64> = energies
+            .iter()
+            .map(|e| (2.53e-8 / e).sqrt() + 0.05)
+            .collect();
+        let direct = |kt: f64| {
+            calculate_macs(
+                &energies,
+                &cross_sections,
+                94.0,
+                kt,
+                MaxwellianWeighting::Flux,
+                IntegrationMethod::Trapezoid,
+                &LinLin,
+            )
+            .unwrap()
+        };
+        let table = table_of(&[(35.0, direct(35.0)), (25.0, direct(25.0))]);
+
+        let interpolated = table.interpolate_at(30.0).unwrap();
+        assert!((interpolated / direct(30.0) - 1.0).abs() < 1e-3);
+        assert_eq!(table.interpolate_at(25.0), Some(direct(25.0)));
+    }
+
+    #[test]
+    fn interpolation_stays_within_the_computed_range() {
+        let table = table_of(&[(10.0, 100.0), (40.0, 50.0)]);
+        assert_eq!(table.interpolate_at(5.0), None);
+        assert_eq!(table.interpolate_at(50.0), None);
+        // Log-log: a power law through both points, MACS ∝ kT^-1/2
+        assert!((table.interpolate_at(20.0).unwrap() - 100.0 / 2f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn flat_cross_section_window_follows_the_maxwellian() {
+        // The flux weight E·exp(-aE/kT) is a Gamma(2) distribution:
+        // mean 2kT/a, standard deviation √2·kT/a
+        let energies = log_grid(1e-9, 2.0, 20_000);
+        let cross_sections = vec![1.0; energies.len()];
+        let (centroid, spread) = sensitivity_window(
+            &energies,
+            &cross_sections,
+            94.0,
+            30.0,
+            MaxwellianWeighting::Flux,
+        )
+        .unwrap();
+        let kt_over_a = 0.03 / reduced_mass_factor(94.0, NEUTRON_MASS);
+        assert!((centroid / (2.0 * kt_over_a) - 1.0).abs() < 1e-3);
+        assert!((spread / (2f64.sqrt() * kt_over_a) - 1.0).abs() < 1e-3);
+    }
+
+    /// Peak energy and width in MeV of the resonance of [`narrow_resonance`]
+    const RESONANCE: (f64, f64) = (5e-3, 1e-5);
+
+    /// A 1 kb resonance 10 eV wide at 5 keV on a 1 µb background, sampled
+    /// densely over ±50 widths
+    fn narrow_resonance() -> (Vec<f64>, Vec<f64>) {
+        let (peak, width) = RESONANCE;
+        let mut energies = log_grid(1e-9, 2.0, 5000);
+        energies.extend((-1000..=1000).map(|i| peak + i as f64 * width / 20.0));
+        energies.sort_by(f64::total_cmp);
+        energies.dedup();
+        let cross_sections = energies
+            .iter()
+            .map(|e| 1e-6 + 1e3 / (1.0 + ((e - peak) / (width / 2.0)).powi(2)))
+            .collect();
+        (energies, cross_sections)
+    }
+
+    #[test]
+    fn narrow_resonance_window_is_narrow() {
+        let (energies, cross_sections) = narrow_resonance();
+        let (peak, _) = RESONANCE;
+        let (centroid, spread) = sensitivity_window(
+            &energies,
+            &cross_sections,
+            94.0,
+            30.0,
+            MaxwellianWeighting::Flux,
+        )
+        .unwrap();
+        assert!(
+            (centroid / peak - 1.0).abs() < 0.05,
+            "centroid {}",
+            centroid
+        );
+        // The Lorentzian tails keep it above the resonance width, but far
+        // below the √2·kT/a of a smooth cross section
+        let kt_over_a = 0.03 / reduced_mass_factor(94.0, NEUTRON_MASS);
+        assert!(spread < 0.05 * 2f64.sqrt() * kt_over_a, "spread {}", spread);
+    }
+
+    #[test]
+    fn dominant_resonance_carries_nearly_all_the_macs() {
+        let (energies, cross_sections) = narrow_resonance();
+        let (peak, width) = RESONANCE;
+        let contribution = |window| {
+            resonance_contribution(
+                &energies,
+                &cross_sections,
+                94.0,
+                30.0,
+                MaxwellianWeighting::Flux,
+                IntegrationMethod::Trapezoid,
+                &LinLin,
+                window,
+            )
+            .unwrap()
+        };
+        let resonance = contribution((peak - 50.0 * width, peak + 50.0 * width));
+        assert!(
+            resonance.fraction > 0.98 && resonance.fraction < 1.0,
+            "{}",
+            resonance.fraction
+        );
+        let total = calculate_macs(
+            &energies,
+            &cross_sections,
+            94.0,
+            30.0,
+            MaxwellianWeighting::Flux,
+            IntegrationMethod::Trapezoid,
+            &LinLin,
+        )
+        .unwrap();
+        assert!((resonance.contribution_mb / total - resonance.fraction).abs() < 1e-12);
+
+        let whole = contribution((0.0, 10.0));
+        assert!((whole.fraction - 1.0).abs() < 1e-12);
+        let off_resonance = contribution((1e-2, 2e-2));
+        assert!(off_resonance.fraction < 1e-3);
+    }
+
+    #[test]
+    fn resonance_window_must_overlap_the_grid() {
+        let energies = log_grid(1e-6, 1.0, 100);
+        let cross_sections = vec![1.0; energies.len()];
+        assert!(
+            resonance_contribution(
+                &energies,
+                &cross_sections,
+                94.0,
+                30.0,
+                MaxwellianWeighting::Flux,
+                IntegrationMethod::Trapezoid,
+                &LinLin,
+                (2.0, 3.0),
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn threshold_is_the_last_leading_zero() {
+        let energies = [0.1, 0.5, 1.0, 2.0];
+        assert_eq!(
+            detect_threshold(&energies, &[0.0, 0.0, 0.2, 0.3]),
+            Some(0.5)
+        );
+        assert_eq!(detect_threshold(&energies, &[0.1, 0.0, 0.2, 0.3]), None);
+        assert_eq!(detect_threshold(&energies, &[0.0; 4]), None);
+        assert_eq!(
+            zero_below_threshold(&energies, &[0.1, 0.1, 0.2, 0.3], 0.8),
+            vec![0.0, 0.0, 0.2, 0.3]
+        );
+    }
+
+    #[test]
+    fn threshold_reaction_gets_no_low_energy_tail() {
+        // An (n,p)-like cross section rising from a 0.1 MeV threshold
+        let energies = log_grid(0.1, 20.0, 400);
+        let cross_sections: Vec<f64> = energies.iter().map(|e| 0.05 * (e - 0.1)).collect();
+        let options = |threshold| MacsOptions {
+            extrapolate: true,
+            threshold,
+            ..MacsOptions::default()
+        };
+        let grid_only = calculate_macs(
+            &energies,
+            &cross_sections,
+            94.0,
+            30.0,
+            MaxwellianWeighting::Flux,
+            IntegrationMethod::Trapezoid,
+            &LinLin,
+        )
+        .unwrap();
+        // The leading zero marks the threshold, so only the high tail is added
+        let detected =
+            calculate_macs_with_options(&energies, &cross_sections, 94.0, 30.0, &options(None))
+                .unwrap();
+        let tails = extrapolated_tails(
+            &energies,
+            &cross_sections,
+            94.0,
+            30.0,
+            MaxwellianWeighting::Flux,
+            Some(WEIGHT_CUTOFF),
+            Some(0.1),
+        )
+        .unwrap();
+        assert_eq!(tails.low_mb, 0.0);
+        assert!((detected - grid_only - tails.high_mb).abs() < 1e-12 * detected);
+
+        // Data starting abruptly above the threshold would get a 1/v tail
+        // unless the threshold is given
+        let (abrupt_energies, abrupt) = (&energies[40..], &cross_sections[40..]);
+        let with_threshold =
+            calculate_macs_with_options(abrupt_energies, abrupt, 94.0, 30.0, &options(Some(0.1)))
+                .unwrap();
+        let without =
+            calculate_macs_with_options(abrupt_energies, abrupt, 94.0, 30.0, &options(None))
+                .unwrap();
+        assert!(without > with_threshold);
+    }
+
+    #[test]
+    fn explicit_threshold_zeroes_the_points_below_it() {
+        let energies = log_grid(1e-6, 10.0, 500);
+        let cross_sections = vec![1.0; energies.len()];
+        let threshold = 0.05;
+        let options = MacsOptions {
+            threshold: Some(threshold),
+            ..MacsOptions::default()
+        };
+        let macs =
+            calculate_macs_with_options(&energies, &cross_sections, 94.0, 30.0, &options).unwrap();
+        let zeroed = zero_below_threshold(&energies, &cross_sections, threshold);
+        let expected = calculate_macs(
+            &energies,
+            &zeroed,
+            94.0,
+            30.0,
+            MaxwellianWeighting::Flux,
+            IntegrationMethod::Trapezoid,
+            &LinLin,
+        )
+        .unwrap();
+        assert_eq!(macs, expected);
+        assert!(macs < constant_cross_section_macs(1.0, MaxwellianWeighting::Flux));
+    }
+
+    #[test]
+    fn temperature_bounds_are_inclusive() {
+        assert!(validate_temperature(MIN_TEMPERATURE_KEV).is_ok());
+        assert!(validate_temperature(MAX_TEMPERATURE_KEV).is_ok());
+        assert!(validate_temperature(MIN_TEMPERATURE_KEV * (1.0 - 1e-12)).is_err());
+        assert!(validate_temperature(MAX_TEMPERATURE_KEV * (1.0 + 1e-12)).is_err());
+        assert!(validate_temperature(1e-6f32).is_ok());
+        assert!(validate_temperature(1e5f32).is_err());
+    }
+
+    #[test]
+    fn implausible_temperatures_name_the_converted_kt() {
+        // 1 mK, far too cold, and 300 K mistakenly read as GK
+        let err = validate_temperature(units::kelvin_to_kev(1e-3)).unwrap_err();
+        assert!(err.contains("(1.000e-3 K)"), "{}", err);
+        let err = validate_temperature(units::gk_to_kev(300.0)).unwrap_err();
+        assert!(err.contains("check the temperature unit"), "{}", err);
+
+        assert_eq!(
+            validate_temperature(0.0).unwrap_err(),
+            "Temperature must be positive"
+        );
+        assert!(validate_temperature(-30.0).is_err());
+        assert!(validate_temperature(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn macs_is_finite_at_the_temperature_bounds() {
+        let energies = log_grid(1e-12, 1e3, 3000);
+        let cross_sections: Vec<f64> = energies.iter().map(|e| (2.53e-8 / e).sqrt()).collect();
+        for kt in [MIN_TEMPERATURE_KEV, MAX_TEMPERATURE_KEV] {
+            let macs = calculate_macs(
+                &energies,
+                &cross_sections,
+                94.0,
+                kt,
+                MaxwellianWeighting::Flux,
+                IntegrationMethod::Trapezoid,
+                &LogLog,
+            )
+            .unwrap();
+            assert!(macs.is_finite() && macs > 0.0, "{} mb at {} keV", macs, kt);
+        }
+        assert!(
+            calculate_macs(
+                &energies,
+                &cross_sections,
+                94.0,
+                1e-9,
+                MaxwellianWeighting::Flux,
+                IntegrationMethod::Trapezoid,
+                &LogLog,
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn rate_and_macs_convert_both_ways() {
+        for (macs_mb, kt, mass) in [(612.0, 30.0, 197.0), (1e-3, 5.0, 1.0), (2e4, 100.0, 56.0)] {
+            let mu = neutron_reduced_mass(mass);
+            let round_trip = rate_to_macs(macs_to_rate(macs_mb, kt, mu), kt, mu);
+            assert!((round_trip / macs_mb - 1.0).abs() < 1e-12);
+        }
+        // Au-197 at 30 keV: v_T ≈ 2.40e8 cm/s, N_A⟨σv⟩ ≈ 8.86e7 cm³/mol/s
+        let rate = macs_to_rate(612.0, 30.0, neutron_reduced_mass(197.0));
+        assert!((rate / 8.86e7 - 1.0).abs() < 2e-3, "{}", rate);
+    }
+
+    #[test]
+    fn coverage_blames_the_end_missing_the_weight() {
+        assert_eq!(Coverage::from_outside(0.004, 0.005, 0.01), Coverage::Full);
+        assert_eq!(
+            Coverage::from_outside(0.02, 0.0, 0.01),
+            Coverage::LowTruncated
+        );
+        assert_eq!(
+            Coverage::from_outside(0.004, 0.007, 0.01),
+            Coverage::HighTruncated
+        );
+        assert_eq!(Coverage::from_outside(0.006, 0.006, 0.01), Coverage::Both);
+        assert_eq!(Coverage::Both.to_string(), "truncated at both ends");
+    }
+
+    #[test]
+    fn each_coverage_outcome_of_a_grid() {
+        let coverage = |low, high| {
+            grid_coverage(
+                &log_grid(low, high, 100),
+                94.0,
+                30.0,
+                MaxwellianWeighting::Flux,
+                SUPPORT_THRESHOLD,
+            )
+        };
+        assert_eq!(coverage(1e-9, 2.0), Coverage::Full);
+        assert_eq!(coverage(0.01, 2.0), Coverage::LowTruncated);
+        assert_eq!(coverage(1e-9, 0.1), Coverage::HighTruncated);
+        assert_eq!(coverage(0.01, 0.1), Coverage::Both);
+    }
+
+    #[test]
+    fn dataset_coverage_reads_its_energy_unit() {
+        let dataset = |columns: &str, high: f64| -> CrossSectionDataset {
+            let mut response: crate::exfor_client::CrossSectionResponse =
+                serde_json::from_str(&format!(
+                    r#"{{"datasets": [{{"id": "1", "COLUMNS": {}, "pts": [
+                        {{"E": 1e-3, "Sig": 1}}, {{"E": {}, "Sig": 1}}]}}]}}"#,
+                    columns, high
+                ))
+                .unwrap();
+            response.datasets.remove(0)
+        };
+        // 1e-3 - 2 covers 30 keV in MeV, but ends at 2 eV in eV, the default
+        let covers = |dataset| dataset_covers(&dataset, 94.0, 30.0).unwrap();
+        assert_eq!(
+            covers(dataset(r#"["E(MeV)", "Sig(b)"]"#, 2.0)),
+            Coverage::Full
+        );
+        assert_eq!(
+            covers(dataset(r#"["E(eV)", "Sig(b)"]"#, 2e6)),
+            Coverage::Full
+        );
+        assert_eq!(
+            covers(dataset(r#"["E(eV)", "Sig(b)"]"#, 2.0)),
+            Coverage::HighTruncated
+        );
+        assert_eq!(
+            covers(dataset(r#"["E", "Sig"]"#, 2.0)),
+            Coverage::HighTruncated
+        );
+    }
+
+    #[test]
+    fn loglog_ri_is_exact_for_a_power_law() {
+        // σ = (E_th/E)^1/2: RI = 2σ(E_Cd)·(1 - √(E_Cd/E_max))
+        let energies = log_grid(1e-8, 1.0, 30);
+        let cross_sections: Vec<f64> = energies
+            .iter()
+            .map(|e| (THERMAL_ENERGY_MEV / e).sqrt())
+            .collect();
+        let expected = 2.0
+            * (THERMAL_ENERGY_MEV / CADMIUM_CUTOFF_MEV).sqrt()
+            * (1.0 - CADMIUM_CUTOFF_MEV.sqrt());
+        let ri = |method| {
+            resonance_integral(&energies, &cross_sections, &LogLog, method, LOGLOG_EPSILON).unwrap()
+        };
+        assert!((ri(RiMethod::LogLog) / expected - 1.0).abs() < 1e-12);
+        assert!((ri(RiMethod::Trapezoid) / expected - 1.0).abs() > 1e-3);
+    }
+
+    #[test]
+    fn loglog_ri_survives_equal_and_near_equal_points() {
+        // A flat segment (b = 0) and a repeated point (ln(E₂/E₁) = 0), as at
+        // a tabulated discontinuity, are both 0/0 in the power-law formula
+        assert!(ri_segment(1.0, 2.0, 3.0, 2.0, RiMethod::LogLog, 0.0).is_nan());
+        assert!(ri_segment(1.0, 2.0, 1.0, 2.0, RiMethod::LogLog, 0.0).is_nan());
+        assert_eq!(
+            ri_segment(1.0, 2.0, 3.0, 2.0, RiMethod::LogLog, LOGLOG_EPSILON),
+            2.0 * 3f64.ln()
+        );
+        assert_eq!(
+            ri_segment(1.0, 2.0, 1.0, 2.0, RiMethod::LogLog, LOGLOG_EPSILON),
+            0.0
+        );
+        // Nearly equal cross sections agree with the trapezoid
+        let near = ri_segment(
+            1.0,
+            2.0,
+            3.0,
+            2.0 * (1.0 + 1e-13),
+            RiMethod::LogLog,
+            LOGLOG_EPSILON,
+        );
+        assert!((near / (2.0 * 3f64.ln()) - 1.0).abs() < 1e-12);
+
+        let mut energies = log_grid(1e-7, 1.0, 200);
+        energies.insert(100, energies[100]);
+        let cross_sections = vec![3.0; energies.len()];
+        let ri = resonance_integral(
+            &energies,
+            &cross_sections,
+            &LogLog,
+            RiMethod::LogLog,
+            LOGLOG_EPSILON,
+        )
+        .unwrap();
+        assert!((ri / (3.0 * (1.0 / CADMIUM_CUTOFF_MEV).ln()) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn maxwellian_weight_peaks_at_the_expected_energy() {
+        let a = reduced_mass_factor(94.0, NEUTRON_MASS);
+        for temperature in [5.0, 30.0, 90.0] {
+            let kt = thermal_energy(temperature);
+            for (weighting, peak) in [
+                (MaxwellianWeighting::Flux, kt / a),
+                (MaxwellianWeighting::NumberDensity, kt / (2.0 * a)),
+            ] {
+                let grid = log_grid(peak / 10.0, peak * 10.0, 20001);
+                let found = grid
+                    .iter()
+                    .copied()
+                    .max_by(|x, y| {
+                        maxwellian_weight(*x, a, kt, weighting)
+                            .total_cmp(&maxwellian_weight(*y, a, kt, weighting))
+                    })
+                    .unwrap();
+                assert!(
+                    (found / peak - 1.0).abs() < 2e-4,
+                    "{:?} at {} keV: {} vs {}",
+                    weighting,
+                    temperature,
+                    found,
+                    peak
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn half_interval_edges_matter_on_an_edge_dominated_grid() {
+        let flux = MaxwellianWeighting::Flux;
+        let edges = |energies: &[f64]| {
+            let cross_sections = vec![1.0; energies.len()];
+            let (low, high) =
+                half_interval_edges(energies, &cross_sections, 94.0, 30.0, flux).unwrap();
+            (macs(energies, &cross_sections, flux), low + high)
+        };
+
+        // Three points around the peak: the cut loses a quarter step per edge
+        let (cut, ramps) = edges(&[0.02, 0.03, 0.04]);
+        assert!(ramps / cut > 0.2, "{} of {}", ramps, cut);
+        let window = log_grid(0.015, 0.045, 10_001);
+        let extended = macs(&window, &vec![1.0; window.len()], flux);
+        assert!(cut < cut + ramps && cut + ramps < extended);
+        assert!((cut + ramps - extended).abs() < (cut - extended).abs() / 2.0);
+
+        // A dense grid spanning the Maxwellian leaves nothing at the edges
+        let (cut, ramps) = edges(&log_grid(1e-11, 20.0, 20_000));
+        assert!(ramps / cut < 1e-6, "{} of {}", ramps, cut);
+    }
+
+    #[test]
+    fn lower_edge_ramp_stops_at_zero_energy() {
+        let (low, high) = half_interval_edges(
+            &[0.0, 0.03],
+            &[1.0, 1.0],
+            94.0,
+            30.0,
+            MaxwellianWeighting::Flux,
+        )
+        .unwrap();
+        assert_eq!(low, 0.0);
+        assert!(high > 0.0);
+        assert!(
+            half_interval_edges(&[0.03], &[1.0], 94.0, 30.0, MaxwellianWeighting::Flux).is_err()
+        );
+    }
+
+    #[test]
+    fn trapezoid_error_estimate_matches_the_true_error() {
+        // A constant 1 b integrates to ∫₀ˣ E·exp(-bE) dE = (1 - (1 + bX)·exp(-bX))/b²
+        let flux = MaxwellianWeighting::Flux;
+        let a = reduced_mass_factor(94.0, NEUTRON_MASS);
+        let kt = thermal_energy(30.0);
+        let b = a / kt;
+        let upper = 0.3;
+        let exact = units::barns_to_millibarns(
+            normalization(a, kt, flux) * (1.0 - (1.0 + b * upper) * (-b * upper).exp()) / (b * b),
+        );
+        let errors: Vec<(f64, f64)> = [31, 61, 121]
+            .into_iter()
+            .map(|n| {
+                let energies: Vec<f64> =
+                    (0..n).map(|i| upper * i as f64 / (n - 1) as f64).collect();
+                let cross_sections = vec![1.0; n];
+                let estimate = integration_error_estimate(
+                    &energies,
+                    &cross_sections,
+                    94.0,
+                    30.0,
+                    flux,
+                    IntegrationMethod::Trapezoid,
+                    &LinLin,
+                )
+                .unwrap();
+                let actual = (macs(&energies, &cross_sections, flux) - exact).abs();
+                assert!(
+                    (estimate / actual - 1.0).abs() < 0.1,
+                    "{} points: estimate {} vs error {}",
+                    n,
+                    estimate,
+                    actual
+                );
+                (estimate, actual)
+            })
+            .collect();
+        // Halving the step divides the error by about four
+        for pair in errors.windows(2) {
+            let ratio = pair[0].0 / pair[1].0;
+            assert!((ratio - 4.0).abs() < 0.3, "{}", ratio);
+        }
+    }
+
+    #[test]
+    fn table_reports_each_temperature() {
+        let energies = log_grid(1e-8, 10.0, 2000);
+        let cross_sections: Vec<f64> = energies.iter().map(|e| 1.0 + e).collect();
+        let temperatures = [5.0, 30.0, 90.0];
+        let calls = std::sync::Mutex::new(Vec::new());
+        let progress =
+            |completed: usize, total: usize| calls.lock().unwrap().push((completed, total));
+        let options = MacsOptions::default();
+        let table = calculate_macs_table(
+            &energies,
+            &cross_sections,
+            94.0,
+            &temperatures,
+            &options,
+            Some(&progress),
+        )
+        .unwrap();
+        assert_eq!(calls.into_inner().unwrap(), vec![(1, 3), (2, 3), (3, 3)]);
+        for (result, &temperature) in table.iter().zip(&temperatures) {
+            let single = calculate_macs_with_options(
+                &energies,
+                &cross_sections,
+                94.0,
+                temperature,
+                &options,
+            )
+            .unwrap();
+            assert_eq!(
+                (result.temperature_kev, result.macs_mb),
+                (temperature, single)
+            );
+        }
+        assert!(
+            calculate_macs_table(
+                &energies,
+                &cross_sections,
+                94.0,
+                &[30.0, -1.0],
+                &options,
+                None
+            )
+            .is_err()
+        );
+    }
 }
@@ -7,82 +7,4870 @@
 //! the reaction rate averaged over a Maxwellian neutron energy distribution
 //! at a given temperature.
 
-mod exfor_client;
-mod macs;
+use clap::{Parser, Subcommand, ValueEnum};
+use macs_rs::{
+    angular, charged, compare, downsample, error_weighting, exfor_client, fit, grid_export,
+    input_files, integration_grid, interpolation, library_cache, macs, merge, montecarlo, natural,
+    nuclide, offline_db, overlay, reaclib, report, sef, spectrum, units,
+};
+use std::io::{BufRead, IsTerminal, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
 
-use clap::Parser;
+/// Exit code used when the run is interrupted with Ctrl-C
+const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// Grid density of the flat cross section integrated by --constant-sigma
+const CONSTANT_SIGMA_POINTS_PER_DECADE: f64 = 1000.0;
 
 /// Command-line arguments for MACS calculation
-#[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[derive(Parser, Debug, Clone)]
+#[command(author, version, about, long_about = None, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Target nucleus (e.g., Mo-94, Zr-92)
-    #[arg(short, long)]
-    target: String,
+    #[arg(
+        short,
+        long,
+        required_unless_present_all = ["z", "a"],
+        required_unless_present_any = ["element", "constant_sigma", "from_rate", "list_libraries"]
+    )]
+    target: Option<String>,
 
-    /// Nuclear data library (e.g., JEFF-3.1, JEFF-4.0, ENDF-B-VIII.1, JENDL-5)
-    #[arg(short, long)]
-    library: String,
+    /// Atomic number of the target, used with --a instead of --target
+    #[arg(long, requires = "a")]
+    z: Option<u32>,
+
+    /// Mass number of the target, used with --z instead of --target
+    #[arg(long, requires = "z")]
+    a: Option<u32>,
+
+    /// Compute the MACS of every isotope of this element found in the data
+    /// (e.g., Mo), using each mass number as the atomic mass
+    #[arg(
+        long,
+        conflicts_with_all = ["target", "z", "a", "mass", "eval_id", "single_temperature"]
+    )]
+    element: Option<String>,
+
+    /// With --element, compute the stable isotopes and also report their
+    /// natural-abundance weighted MACS
+    #[arg(long, requires = "element")]
+    natural: bool,
+
+    /// Nuclear data library (e.g., JEFF-3.1, JEFF-4.0, ENDF-B-VIII.1, JENDL-5);
+    /// several comma-separated libraries produce a comparison table
+    #[arg(
+        short,
+        long,
+        required_unless_present_any = ["constant_sigma", "from_rate", "list_libraries", "input", "url"],
+        value_delimiter = ','
+    )]
+    library: Vec<String>,
+
+    /// Print the library names in the library cache and exit; with a target,
+    /// list the libraries tabulating its reaction instead
+    #[arg(long)]
+    list_libraries: bool,
+
+    /// Forget the cached library names and keep only those seen in this run
+    #[arg(long)]
+    refresh_library_cache: bool,
+
+    /// Use this evaluation ID of the library instead of its newest evaluation
+    /// (requires a single --library)
+    #[arg(long)]
+    eval_id: Option<u32>,
 
     /// Reaction type (default: n,g for neutron capture)
     #[arg(short, long, default_value = "n,g")]
     reaction: String,
 
-    /// Atomic mass number (e.g., 94 for Mo-94)
+    /// EXFOR quantity to fetch (e.g., SIG, MACS, RI)
+    #[arg(long, default_value = exfor_client::DEFAULT_QUANTITY, value_parser = parse_quantity)]
+    quantity: String,
+
+    /// Quantities derived from the fetched cross section, computed in one run
+    /// (comma-separated): macs, ri (resonance integral) and thermal (σ at 0.0253 eV)
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "macs", conflicts_with_all = ["stream", "constant_sigma", "from_rate", "ratio", "single_temperature"])]
+    quantities: Vec<Quantity>,
+
+    /// Integration of the resonance integral between grid points
+    #[arg(long, value_enum, default_value_t = macs::RiMethod::Trapezoid)]
+    ri_method: macs::RiMethod,
+
+    /// Smallest power-law exponent |b| (and log energy step) integrated
+    /// analytically by --ri-method log-log; below it the trapezoid is used
+    /// (default 1e-6)
+    #[arg(
+        long,
+        value_parser = parse_loglog_epsilon,
+        requires_all = ["ri_method", "quantities"]
+    )]
+    loglog_epsilon: Option<f64>,
+
+    /// Compute the MACS of a cross section constant in energy, in barns,
+    /// instead of fetching data (no --target or --library needed)
+    #[arg(
+        long,
+        value_name = "BARNS",
+        value_parser = parse_constant_sigma,
+        conflicts_with_all = ["element", "stream", "describe", "ratio", "experimental", "offline_db"]
+    )]
+    constant_sigma: Option<f64>,
+
+    /// Convert a published reaction rate N_A⟨σv⟩ in cm³ mol⁻¹ s⁻¹ to the MACS
+    /// it implies at each temperature, instead of fetching data (needs --mass
+    /// or --target)
+    #[arg(
+        long,
+        value_name = "RATE",
+        value_parser = parse_rate,
+        conflicts_with_all = ["constant_sigma", "element", "stream", "describe", "ratio", "experimental", "offline_db"]
+    )]
+    from_rate: Option<f64>,
+
+    /// Also report the astrophysical reaction rate N_A⟨σv⟩ = N_A·MACS·v_T
+    /// in cm³ mol⁻¹ s⁻¹
+    #[arg(long)]
+    rate: bool,
+
+    /// Also report the Gamow peak energy E0 and its 1/e width of a
+    /// charged-particle reaction at each temperature
+    #[arg(long)]
+    gamow_peak: bool,
+
+    /// Also report each MACS divided by the MACS at this temperature (e.g.
+    /// 30), which is computed too if it is not among --temperatures
+    #[arg(long, conflicts_with_all = ["single_temperature", "interactive", "stream", "from_rate"])]
+    normalize_to: Option<units::Temperature>,
+
+    /// Atomic mass number (e.g., 94 for Mo-94); defaults to the standard
+    /// atomic weight for natural-element targets (e.g., Mo-nat)
     #[arg(short, long)]
-    mass: f64,
+    mass: Option<f64>,
 
-    /// Temperatures in keV (comma-separated, e.g., 8,25,30,90)
+    /// Temperatures (comma-separated, e.g., 8,25,30,90); each may carry its
+    /// own unit suffix keV, eV, K or GK (e.g., 30keV,0.35GK)
     #[arg(
         short = 'T',
         long,
         value_delimiter = ',',
-        default_value = "8.0,25.0,30.0,90.0"
+        default_value = "8keV,25keV,30keV,90keV"
+    )]
+    temperatures: Vec<units::Temperature>,
+
+    /// Unit of --temperatures values given without a suffix
+    #[arg(long, value_enum, default_value_t = units::TemperatureUnit::Kev)]
+    temperature_unit: units::TemperatureUnit,
+
+    /// Compute the MACS at this single temperature and print it as a bare
+    /// number; a unit suffix is read as in --temperatures
+    #[arg(long, conflicts_with = "temperatures")]
+    single_temperature: Option<units::Temperature>,
+
+    /// Compute the MACS exactly as tabulated by KADoNiS (kT = 30 keV, flux
+    /// weighting, lab frame, extrapolated tails, no rescaling), overriding
+    /// the options that would change it
+    #[arg(
+        long,
+        conflicts_with_all = ["stream", "experimental", "max_points", "half_extend_edges", "background", "threshold", "integration_grid", "perturb", "error_weighted"]
+    )]
+    kadonis_convention: bool,
+
+    /// Compute repeated --temperatures again instead of dropping them
+    #[arg(long)]
+    keep_duplicate_temperatures: bool,
+
+    /// Also print the MACS at these temperatures, interpolated log-log from
+    /// the computed curve instead of integrated (table output only); unit
+    /// suffixes are read as in --temperatures
+    #[arg(long, value_delimiter = ',', conflicts_with = "single_temperature")]
+    interpolate_at: Vec<units::Temperature>,
+
+    /// Fit ln(MACS) with a polynomial of this degree in ln(kT/30 keV) and
+    /// print its coefficients and residuals
+    #[arg(long, value_parser = parse_fit_degree, conflicts_with = "single_temperature")]
+    fit: Option<usize>,
+
+    /// Maxwellian weighting used to average the cross section
+    #[arg(long, value_enum, default_value_t = macs::MaxwellianWeighting::Flux)]
+    weighting: macs::MaxwellianWeighting,
+
+    /// Frame of the tabulated energies; center-of-mass energies are converted
+    /// to the laboratory frame using --mass
+    #[arg(long, value_enum, default_value_t = macs::EnergyFrame::Lab, conflicts_with = "stream")]
+    frame: macs::EnergyFrame,
+
+    /// Numerical integration method
+    #[arg(long, value_enum, default_value_t = macs::IntegrationMethod::Trapezoid)]
+    integration: macs::IntegrationMethod,
+
+    /// Also compute the MACS with the other integration method and report
+    /// both with their relative difference, warning above 1%
+    #[arg(long, conflicts_with = "stream")]
+    cross_check: bool,
+
+    /// Extend the cross section beyond its grid: as 1/v down to zero energy
+    /// and constant above the last point, until the Maxwellian weight decays
+    #[arg(long, conflicts_with = "stream")]
+    extrapolate: bool,
+
+    /// Without --extrapolate, ramp the integrand linearly down to zero over
+    /// half an interval beyond the first and last grid points instead of
+    /// cutting it off there
+    #[arg(long, conflicts_with_all = ["extrapolate", "kadonis_convention", "stream"])]
+    half_extend_edges: bool,
+
+    /// Threshold energy of the reaction in MeV (lab frame): points below it
+    /// are set to zero and --extrapolate adds no 1/v tail; detected from
+    /// leading zero cross sections when not given
+    #[arg(long, value_name = "ENERGY_MEV", value_parser = parse_threshold, conflicts_with = "stream")]
+    threshold: Option<f64>,
+
+    /// Integrate the high-energy extrapolation over its full range instead of
+    /// stopping at the weight cutoff, reporting the cutoff MACS alongside
+    #[arg(long, requires = "extrapolate")]
+    no_cutoff: bool,
+
+    /// Fraction of its peak below which the Maxwellian weight ends the
+    /// high-energy extrapolation; smaller values reach higher energies
+    #[arg(long, default_value_t = macs::WEIGHT_CUTOFF, value_parser = parse_weight_cutoff, requires = "extrapolate")]
+    weight_cutoff: f64,
+
+    /// Omit temperatures whose Maxwellian weight the tabulated energies do not
+    /// bracket, instead of printing a MACS the data does not support
+    #[arg(long, conflicts_with = "stream")]
+    skip_unsupported: bool,
+
+    /// Largest share of the Maxwellian weight that may lie outside the
+    /// tabulated energies for a temperature to count as supported, used by
+    /// --skip-unsupported and --suggest-temperatures
+    #[arg(long, value_name = "FRACTION", default_value_t = macs::SUPPORT_THRESHOLD, value_parser = parse_support_threshold)]
+    support_threshold: f64,
+
+    /// Estimate the numerical error of the integral by Richardson extrapolation,
+    /// from the half-density grid (trapezoid) or the accepted subintervals (adaptive)
+    #[arg(long, conflicts_with = "stream")]
+    error_estimate: bool,
+
+    /// Also report the Maxwellian integral ∫σ(E)·E·exp(-aE/kT)dE over the grid
+    /// before normalization, in barn·MeV²
+    #[arg(long, conflicts_with = "stream")]
+    raw_integral: bool,
+
+    /// Report the contribution to the MACS of a resonance at ENERGY (MeV),
+    /// integrated over a window of WIDTH (MeV) centred on it
+    #[arg(long, value_name = "ENERGY:WIDTH", value_parser = parse_resonance, conflicts_with = "stream")]
+    resonance: Option<(f64, f64)>,
+
+    /// Cross-check each MACS against a Monte Carlo average of the cross
+    /// section over this many energies sampled from the Maxwellian
+    #[arg(long, value_name = "N_SAMPLES", value_parser = parse_mc_samples, conflicts_with = "stream")]
+    validate_mc: Option<usize>,
+
+    /// Also compute the MACS with the cross section between START and END
+    /// (MeV) scaled by FACTOR, reporting it and its relative change
+    #[arg(long, value_name = "START:END:FACTOR", value_parser = parse_perturbation, conflicts_with = "stream")]
+    perturb: Option<macs::Perturbation>,
+
+    /// Add a smooth background cross section to the data before integrating:
+    /// a constant VALUE or 1/v:VALUE (barns at 0.0253 eV); its contribution
+    /// to the MACS is reported separately
+    #[arg(long, value_name = "SPEC", value_parser = parse_background, conflicts_with_all = ["stream", "constant_sigma", "from_rate"])]
+    background: Option<macs::Background>,
+
+    /// Branching ratio towards the channel of interest, multiplied onto the MACS
+    #[arg(long, default_value_t = 1.0, value_parser = parse_branching)]
+    branching: f64,
+
+    /// Also average the cross section over the neutron spectrum tabulated in
+    /// this two-column (energy in MeV, flux) file
+    #[arg(long, value_parser = parse_spectrum, conflicts_with = "stream")]
+    spectrum: Option<spectrum::Spectrum>,
+
+    /// Also report the stellar MACS and its ratio to the laboratory MACS, using
+    /// the enhancement factors of this two-column (kT in keV, SEF) file
+    #[arg(long, value_parser = parse_sef)]
+    sef: Option<sef::SefTable>,
+
+    /// Multiply all cross sections by this factor before integrating, e.g. to
+    /// apply a recalibrated standard
+    #[arg(long, default_value_t = 1.0, value_parser = parse_renorm)]
+    renorm: f64,
+
+    /// Measured cross sections (energy in MeV, σ in barns per line) to compare
+    /// with the evaluated data, or to substitute with --experimental-range
+    #[arg(long, value_parser = parse_experimental, conflicts_with_all = ["stream", "merge_datasets", "describe"])]
+    experimental: Option<overlay::ExperimentalData>,
+
+    /// Use the --experimental points instead of the evaluated cross section
+    /// between these energies in MeV (e.g. 0.01:0.1, repeatable)
+    #[arg(long, value_parser = parse_energy_window, value_delimiter = ',', requires = "experimental")]
+    experimental_range: Vec<(f64, f64)>,
+
+    /// Compute each temperature from the dataset Doppler broadened closest to
+    /// it (in practice the hottest, since processing temperatures lie far
+    /// below any stellar kT) when the response holds datasets processed at
+    /// several temperatures
+    #[arg(
+        long,
+        conflicts_with_all = ["stream", "merge_datasets", "dataset_index", "experimental", "spectrum", "export_grid", "dump_intervals", "cumulative"]
+    )]
+    match_broadening: bool,
+
+    /// Stitch all datasets of the response (e.g. resolved and unresolved
+    /// resonance regions) into one grid, keeping each dataset's interpolation
+    /// law over its own energy range (honored by --integration adaptive)
+    #[arg(long, conflicts_with = "stream")]
+    merge_datasets: bool,
+
+    /// Combine each point with the value interpolated from its neighbours,
+    /// weighting both by their inverse variance, so that uncertain points
+    /// count less
+    #[arg(long, conflicts_with_all = ["stream", "merge_datasets"])]
+    error_weighted: bool,
+
+    /// Use this dataset of the response (1 for the first, as numbered by
+    /// --describe) when it holds several
+    #[arg(long, value_parser = parse_dataset_index, conflicts_with_all = ["merge_datasets", "stream"])]
+    dataset_index: Option<usize>,
+
+    /// Downsample the cross section to at most this many points before
+    /// integrating, keeping resonance peaks and valleys first
+    #[arg(long, value_parser = parse_max_points, conflicts_with = "stream")]
+    max_points: Option<usize>,
+
+    /// Fail unless the MACS of the --max-points grid agrees with the full grid
+    /// to this relative difference at every temperature
+    #[arg(long, value_name = "REL", value_parser = parse_max_points_tolerance, requires = "max_points")]
+    max_points_tolerance: Option<f64>,
+
+    /// Resample the cross section onto the points of this one-column file and
+    /// integrate over them instead of the data grid
+    #[arg(long, value_parser = parse_integration_grid, conflicts_with_all = ["stream", "max_points"])]
+    integration_grid: Option<integration_grid::IntegrationGrid>,
+
+    /// Variable the --integration-grid points are given in
+    #[arg(long, value_enum, default_value_t = integration_grid::GridVariable::Energy, requires = "integration_grid")]
+    grid_variable: integration_grid::GridVariable,
+
+    /// Parse the dataset while downloading it, keeping memory bounded for huge
+    /// datasets (trapezoid integration only, no verbose diagnostics)
+    #[arg(long, conflicts_with_all = ["z", "a"])]
+    stream: bool,
+
+    /// E-mail address sent with every request to the IAEA, so that its
+    /// operators can reach you about heavy usage
+    #[arg(long, value_name = "EMAIL", value_parser = parse_contact)]
+    contact: Option<String>,
+
+    /// Read the cross section from these files instead of the EXFOR API
+    /// (comma-separated or repeated): saved JSON responses, or two columns of
+    /// energy in MeV and σ in barns. Their datasets are stitched as with
+    /// --merge-datasets
+    #[arg(
+        long,
+        value_name = "FILE",
+        value_delimiter = ',',
+        conflicts_with_all = ["stream", "offline_db", "element", "dataset_index", "error_weighted", "ratio", "describe", "list_libraries", "constant_sigma", "from_rate"]
+    )]
+    input: Vec<std::path::PathBuf>,
+
+    /// Download the cross section from this e4sig URL, e.g. one found through
+    /// the IAEA web interface, instead of looking up the library's section
+    #[arg(
+        long,
+        conflicts_with_all = ["input", "stream", "offline_db", "element", "ratio", "list_libraries", "constant_sigma", "from_rate"]
     )]
-    temperatures: Vec<f64>,
+    url: Option<String>,
+
+    /// Accept a --url on any host and path, not only the IAEA e4sig endpoint
+    #[arg(long, requires = "url")]
+    allow_any_host: bool,
+
+    /// Read datasets from this offline snapshot instead of the EXFOR API
+    #[arg(long, conflicts_with = "stream")]
+    offline_db: Option<std::path::PathBuf>,
+
+    /// Download the requested datasets and store them in the --offline-db snapshot
+    #[arg(long, requires = "offline_db")]
+    refresh_offline_db: bool,
+
+    /// Output format of the results
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+    output_format: OutputFormat,
+
+    /// Number notation of the MACS in the table output; `auto` switches to
+    /// scientific when the values span a range fixed decimals cannot show
+    #[arg(long, value_enum, default_value_t = Notation::Auto)]
+    notation: Notation,
+
+    /// Include the datasets (metadata and all points) the results were
+    /// computed from in the JSON output
+    #[arg(long, conflicts_with = "stream")]
+    embed_data: bool,
+
+    /// Write the cross sections of all libraries, resampled onto the union
+    /// of their energy grids, to this CSV file
+    #[arg(long, conflicts_with_all = ["element", "stream", "describe"])]
+    export_grid: Option<std::path::PathBuf>,
+
+    /// Write every grid interval of the Maxwellian integral (energies, cross
+    /// sections, integrand, area and running sum) to this CSV file
+    #[arg(long, conflicts_with_all = ["element", "stream", "describe", "ratio"])]
+    dump_intervals: Option<std::path::PathBuf>,
+
+    /// Write the MACS accumulated up to each grid energy, and its fraction of
+    /// the total, to this CSV file
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["element", "stream", "describe", "ratio"])]
+    cumulative: Option<std::path::PathBuf>,
+
+    /// Write the results to this file instead of stdout
+    #[arg(short, long)]
+    output: Option<std::path::PathBuf>,
+
+    /// Compute the MACS of two reactions of the target (e.g. n,g:n,p) and
+    /// print their ratio at each temperature
+    #[arg(long, value_parser = parse_ratio, conflicts_with_all = ["element", "describe", "export_grid", "single_temperature"])]
+    ratio: Option<ReactionRatio>,
+
+    /// Fetch the data once, then read temperatures from stdin and print the
+    /// MACS of each, until `quit` or end of input
+    #[arg(long, conflicts_with_all = ["element", "stream", "describe", "ratio", "constant_sigma", "from_rate", "suggest_temperatures"])]
+    interactive: bool,
+
+    /// Print the range of temperatures the fetched data supports, and a
+    /// --temperatures list within it, instead of computing the MACS
+    #[arg(long, conflicts_with_all = ["element", "stream", "describe", "ratio", "constant_sigma", "from_rate"])]
+    suggest_temperatures: bool,
+
+    /// Print the metadata of the fetched datasets instead of computing the MACS
+    #[arg(long, conflicts_with_all = ["element", "stream"])]
+    describe: bool,
+
+    /// Print additional diagnostics about the downloaded data
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// Hide status messages and the progress bar (warnings are still shown)
+    #[arg(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Treat every warning as an error: nothing is written and the program
+    /// exits with an error if any warning was raised
+    #[arg(long)]
+    strict: bool,
+
+    /// Print tables without ANSI colors (they are also disabled when stdout
+    /// is not a terminal or NO_COLOR is set)
+    #[arg(long)]
+    no_color: bool,
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = Args::parse();
+/// Subcommands that do not compute a MACS
+#[derive(Subcommand, Debug, Clone)]
+enum Command {
+    /// Compare two result files saved with --output-format json
+    Compare {
+        /// Reference results file
+        baseline: std::path::PathBuf,
 
-    // Fetch cross section data from EXFOR database
-    println!(
-        "Downloading {} data for {}({})...",
-        args.library, args.target, args.reaction
-    );
-    let cross_section_data =
-        exfor_client::fetch_cross_section(&args.target, &args.reaction, &args.library).await?;
+        /// Results file to check against the reference
+        candidate: std::path::PathBuf,
 
-    // Extract energy and cross section vectors
-    let (energies, cross_sections) = if let Some(dataset) = cross_section_data.datasets.first() {
-        // Convert energy from eV to MeV
-        let energies: Vec<f64> = dataset.points.iter().map(|p| p.energy * 1e-6).collect();
-        let cross_sections: Vec<f64> = dataset.points.iter().map(|p| p.cross_section).collect();
+        /// Relative difference above which a temperature is flagged
+        #[arg(long, default_value_t = 1e-6)]
+        tolerance: f64,
+    },
+}
 
-        println!("Downloaded {} data points from API", energies.len());
-        println!(
-            "Energy range: {:.2e} - {:.2e} MeV",
-            energies.first().unwrap_or(&0.0),
-            energies.last().unwrap_or(&0.0)
-        );
-        (energies, cross_sections)
+/// Format used to print the MACS results
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Human-readable table
+    Table,
+    /// Comma-separated values with a header row
+    Csv,
+    /// JSON object with the target metadata and per-temperature results
+    Json,
+    /// One JSON object per line for each result, written as each library is
+    /// computed
+    Ndjson,
+    /// REACLIB 2 rate entry fitted over a fixed T9 grid, replacing --temperatures
+    Reaclib,
+}
+
+/// Quantity derived from a cross section by --quantities
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Quantity {
+    /// MACS at each temperature
+    Macs,
+    /// Resonance integral above the 0.5 eV cadmium cutoff
+    Ri,
+    /// Thermal cross section at 0.0253 eV
+    Thermal,
+}
+
+/// Number notation of the MACS values in the table output
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Notation {
+    /// Scientific when a value is below 1e-3 or at least 1e5, fixed otherwise
+    Auto,
+    /// Six decimals
+    Fixed,
+    /// Aligned scientific notation, e.g. 5.70610e+01
+    Scientific,
+}
+
+/// Smallest magnitude shown with fixed decimals by `--notation auto`; below
+/// it six decimals keep fewer than four significant digits
+const FIXED_NOTATION_MIN: f64 = 1e-3;
+
+/// Magnitude from which `--notation auto` switches to scientific, as fixed
+/// decimals would overflow the column
+const FIXED_NOTATION_MAX: f64 = 1e5;
+
+/// Whether a table of values is printed in scientific notation
+fn use_scientific(notation: Notation, values: impl IntoIterator<Item = f64>) -> bool {
+    match notation {
+        Notation::Fixed => false,
+        Notation::Scientific => true,
+        Notation::Auto => values
+            .into_iter()
+            .map(f64::abs)
+            .filter(|v| v.is_finite() && *v > 0.0)
+            .any(|v| !(FIXED_NOTATION_MIN..FIXED_NOTATION_MAX).contains(&v)),
+    }
+}
+
+/// Deviation, in standard errors, above which --validate-mc flags a MACS
+const MC_TOLERANCE_SIGMAS: f64 = 3.0;
+
+/// Relative difference between the integration methods above which
+/// --cross-check warns
+const CROSS_CHECK_TOLERANCE: f64 = 0.01;
+
+/// Share of the natural abundance, beyond the rounding of the abundance
+/// table, whose absence --natural reports
+const NATURAL_COVERAGE_TOLERANCE: f64 = 1e-3;
+
+/// Number of worst interpolation offenders shown in verbose mode
+const WORST_OFFENDERS: usize = 5;
+
+/// Returns the interpolation law declared by a dataset, defaulting to lin-lin
+fn dataset_law(law_name: &str) -> Box<dyn interpolation::Interpolation> {
+    interpolation::from_name(law_name).unwrap_or_else(|| {
+        report::warning(format!(
+            "Unknown interpolation law '{}', assuming lin-lin",
+            law_name
+        ));
+        Box::new(interpolation::LinLin)
+    })
+}
+
+/// Prints the grid points worst represented by the dataset's interpolation law
+fn print_interpolation_diagnostic(
+    energies: &[f64],
+    cross_sections: &[f64],
+    law: &dyn interpolation::Interpolation,
+    law_name: &str,
+) {
+    let deviations = interpolation::interpolation_deviations(energies, cross_sections, law);
+    let flagged = deviations
+        .iter()
+        .filter(|d| d.relative_deviation > interpolation::DEVIATION_THRESHOLD)
+        .count();
+
+    report::status(format!(
+        "\nInterpolation check ({}): {} of {} interior points deviate by more than {:.0}%",
+        law_name,
+        flagged,
+        deviations.len(),
+        interpolation::DEVIATION_THRESHOLD * 100.0
+    ));
+    if flagged == 0 {
+        return;
+    }
+    report::status("  E(MeV)        Sig(b)        Interp(b)     Deviation");
+    for d in deviations.iter().take(WORST_OFFENDERS.min(flagged)) {
+        report::status(format!(
+            "  {:.6e}  {:.6e}  {:.6e}  {:8.1}%",
+            d.energy,
+            d.actual,
+            d.interpolated,
+            d.relative_deviation * 100.0
+        ));
+    }
+}
+
+/// Parses an EXFOR quantity code, which must be one of the known codes
+fn parse_quantity(value: &str) -> Result<String, String> {
+    let quantity = value.trim().to_ascii_uppercase();
+    if exfor_client::KNOWN_QUANTITIES.contains(&quantity.as_str()) {
+        Ok(quantity)
     } else {
-        return Err("No dataset found in API response".into());
+        Err(format!(
+            "unknown quantity '{}', expected one of {}",
+            value,
+            exfor_client::KNOWN_QUANTITIES.join(", ")
+        ))
+    }
+}
+
+/// Parses a branching ratio, which must lie in (0, 1]
+fn parse_branching(value: &str) -> Result<f64, String> {
+    let branching: f64 = value
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", value))?;
+    if branching > 0.0 && branching <= 1.0 {
+        Ok(branching)
+    } else {
+        Err(format!("branching must be in (0, 1], got {}", branching))
+    }
+}
+
+/// Parses a --constant-sigma cross section, which must be positive and finite
+fn parse_constant_sigma(value: &str) -> Result<f64, String> {
+    let cross_section: f64 = value
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", value))?;
+    if cross_section > 0.0 && cross_section.is_finite() {
+        Ok(cross_section)
+    } else {
+        Err(format!(
+            "the cross section must be positive, got {}",
+            cross_section
+        ))
+    }
+}
+
+/// Parses a --from-rate reaction rate, which must be positive and finite
+fn parse_rate(value: &str) -> Result<f64, String> {
+    let rate: f64 = value
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", value))?;
+    if rate > 0.0 && rate.is_finite() {
+        Ok(rate)
+    } else {
+        Err(format!("the reaction rate must be positive, got {}", rate))
+    }
+}
+
+/// Parses a --threshold energy, which must be positive and finite
+fn parse_threshold(value: &str) -> Result<f64, String> {
+    let threshold: f64 = value
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", value))?;
+    if threshold > 0.0 && threshold.is_finite() {
+        Ok(threshold)
+    } else {
+        Err(format!("the threshold must be positive, got {}", threshold))
+    }
+}
+
+/// Reads the --experimental file
+fn parse_experimental(value: &str) -> Result<overlay::ExperimentalData, String> {
+    overlay::ExperimentalData::load(std::path::Path::new(value)).map_err(|e| e.to_string())
+}
+
+/// Parses an energy window "<start>:<end>" in MeV
+fn parse_energy_window(value: &str) -> Result<(f64, f64), String> {
+    let parse = |field: &str| {
+        field
+            .trim()
+            .parse::<f64>()
+            .map_err(|_| format!("'{}' is not a number", field))
     };
+    let (start, end) = value
+        .split_once(':')
+        .ok_or_else(|| format!("expected <start>:<end> in MeV, got '{}'", value))?;
+    let (start, end) = (parse(start)?, parse(end)?);
+    if start < end {
+        Ok((start, end))
+    } else {
+        Err(format!(
+            "window start {} must be below its end {}",
+            start, end
+        ))
+    }
+}
 
-    // Calculate MACS at specified temperatures
-    println!(
-        "\n=== MACS Calculation for {} {}({}) ===",
-        args.library, args.target, args.reaction
-    );
-    println!("\nT(keV)    MACS(mb)");
-    println!("--------------------");
+/// Parses a --resonance "<energy>:<width>" in MeV into its energy window
+fn parse_resonance(value: &str) -> Result<(f64, f64), String> {
+    let parse = |field: &str| {
+        field
+            .trim()
+            .parse::<f64>()
+            .map_err(|_| format!("'{}' is not a number", field))
+    };
+    let (energy, width) = value
+        .split_once(':')
+        .ok_or_else(|| format!("expected <energy>:<width> in MeV, got '{}'", value))?;
+    let (energy, width) = (parse(energy)?, parse(width)?);
+    if energy > 0.0 && width > 0.0 {
+        Ok(((energy - width / 2.0).max(0.0), energy + width / 2.0))
+    } else {
+        Err(format!(
+            "resonance energy and width must be positive, got {} and {}",
+            energy, width
+        ))
+    }
+}
+
+/// Parses a --perturb value START:END:FACTOR
+fn parse_perturbation(value: &str) -> Result<macs::Perturbation, String> {
+    let (window, factor) = value
+        .rsplit_once(':')
+        .ok_or_else(|| format!("expected <start>:<end>:<factor>, got '{}'", value))?;
+    let (start, end) = parse_energy_window(window)?;
+    let factor: f64 = factor
+        .trim()
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", factor))?;
+    if factor >= 0.0 && factor.is_finite() {
+        Ok(macs::Perturbation { start, end, factor })
+    } else {
+        Err(format!(
+            "the factor must be zero or positive, got {}",
+            factor
+        ))
+    }
+}
+
+/// Parses a --background value, VALUE or 1/v:VALUE in barns
+fn parse_background(value: &str) -> Result<macs::Background, String> {
+    let (inverse_velocity, number) = match value.split_once(':') {
+        Some((form, number)) if form.trim().eq_ignore_ascii_case("1/v") => (true, number),
+        Some((form, _)) => {
+            return Err(format!(
+                "unknown background form '{}', expected a constant or 1/v:<value>",
+                form
+            ));
+        }
+        None => (false, value),
+    };
+    let cross_section: f64 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", number))?;
+    if !cross_section.is_finite() || cross_section < 0.0 {
+        return Err(format!(
+            "the background must be zero or positive, got {}",
+            cross_section
+        ));
+    }
+    Ok(if inverse_velocity {
+        macs::Background::InverseVelocity(cross_section)
+    } else {
+        macs::Background::Constant(cross_section)
+    })
+}
+
+/// Parses a --contact address, which must look like an e-mail address
+fn parse_contact(value: &str) -> Result<String, String> {
+    let value = value.trim();
+    match value.split_once('@') {
+        Some((user, domain)) if !user.is_empty() && !domain.is_empty() => Ok(value.to_string()),
+        _ => Err(format!("'{}' is not an e-mail address", value)),
+    }
+}
+
+/// Parses the --loglog-epsilon switchover, a small positive number
+fn parse_loglog_epsilon(value: &str) -> Result<f64, String> {
+    let epsilon: f64 = value
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", value))?;
+    if epsilon > 0.0 && epsilon < 1.0 {
+        Ok(epsilon)
+    } else {
+        Err(format!(
+            "loglog-epsilon must be between 0 and 1, got {}",
+            epsilon
+        ))
+    }
+}
+
+/// Reads the --integration-grid file
+fn parse_integration_grid(value: &str) -> Result<integration_grid::IntegrationGrid, String> {
+    integration_grid::IntegrationGrid::load(std::path::Path::new(value)).map_err(|e| e.to_string())
+}
+
+/// Reads the --spectrum file
+fn parse_spectrum(value: &str) -> Result<spectrum::Spectrum, String> {
+    spectrum::Spectrum::load(std::path::Path::new(value)).map_err(|e| e.to_string())
+}
+
+/// Parses a --fit polynomial degree
+fn parse_fit_degree(value: &str) -> Result<usize, String> {
+    let degree: usize = value
+        .parse()
+        .map_err(|_| format!("'{}' is not a non-negative integer", value))?;
+    if degree <= fit::MAX_FIT_DEGREE {
+        Ok(degree)
+    } else {
+        Err(format!(
+            "fit degree must be at most {}, got {}",
+            fit::MAX_FIT_DEGREE,
+            degree
+        ))
+    }
+}
+
+/// Reads the --sef file
+fn parse_sef(value: &str) -> Result<sef::SefTable, String> {
+    sef::SefTable::load(std::path::Path::new(value)).map_err(|e| e.to_string())
+}
+
+/// Removes repeated temperatures, keeping the first occurrence of each
+fn unique_temperatures(temperatures: &[f64]) -> Vec<f64> {
+    let mut unique: Vec<f64> = Vec::with_capacity(temperatures.len());
+    for &temp in temperatures {
+        if !unique.contains(&temp) {
+            unique.push(temp);
+        }
+    }
+    unique
+}
+
+/// Pair of reactions whose MACS are divided by --ratio
+#[derive(Debug, Clone)]
+struct ReactionRatio {
+    /// Reaction in the numerator (e.g., "n,g")
+    numerator: String,
+    /// Reaction in the denominator (e.g., "n,p")
+    denominator: String,
+}
+
+/// Parses a --ratio value of the form "<reaction>:<reaction>"
+fn parse_ratio(value: &str) -> Result<ReactionRatio, String> {
+    match value.split_once(':') {
+        Some((numerator, denominator))
+            if !numerator.trim().is_empty() && !denominator.trim().is_empty() =>
+        {
+            Ok(ReactionRatio {
+                numerator: numerator.trim().to_string(),
+                denominator: denominator.trim().to_string(),
+            })
+        }
+        _ => Err(format!(
+            "expected two reactions separated by ':' (e.g. n,g:n,p), got '{}'",
+            value
+        )),
+    }
+}
+
+/// Parses a --support-threshold fraction, which must lie strictly between 0 and 1
+fn parse_support_threshold(value: &str) -> Result<f64, String> {
+    let fraction: f64 = value
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", value))?;
+    if fraction > 0.0 && fraction < 1.0 {
+        Ok(fraction)
+    } else {
+        Err(format!(
+            "support threshold must be between 0 and 1, got {}",
+            fraction
+        ))
+    }
+}
+
+/// Parses a --weight-cutoff fraction, which must lie strictly between 0 and 1
+fn parse_weight_cutoff(value: &str) -> Result<f64, String> {
+    let fraction: f64 = value
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", value))?;
+    if fraction > 0.0 && fraction < 1.0 {
+        Ok(fraction)
+    } else {
+        Err(format!(
+            "weight cutoff must be between 0 and 1, got {}",
+            fraction
+        ))
+    }
+}
+
+/// Parses a renormalization factor, which must be positive and finite
+fn parse_renorm(value: &str) -> Result<f64, String> {
+    let factor: f64 = value
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", value))?;
+    if factor > 0.0 && factor.is_finite() {
+        Ok(factor)
+    } else {
+        Err(format!(
+            "renormalization factor must be positive, got {}",
+            factor
+        ))
+    }
+}
+
+/// Parses a --dataset-index value, counted from 1
+fn parse_dataset_index(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(index) if index >= 1 => Ok(index),
+        _ => Err(format!(
+            "dataset index must be a positive integer, got '{}'",
+            value
+        )),
+    }
+}
+
+/// Parses a --validate-mc sample count, which must allow a standard error
+fn parse_mc_samples(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(samples) if samples >= 2 => Ok(samples),
+        _ => Err(format!(
+            "the number of samples must be an integer of at least 2, got '{}'",
+            value
+        )),
+    }
+}
+
+/// Parses a --max-points-tolerance, which must be a positive relative difference
+fn parse_max_points_tolerance(value: &str) -> Result<f64, String> {
+    let tolerance: f64 = value
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", value))?;
+    if tolerance > 0.0 {
+        Ok(tolerance)
+    } else {
+        Err(format!(
+            "max-points tolerance must be positive, got {}",
+            tolerance
+        ))
+    }
+}
+
+/// Parses a --max-points value, which must keep at least the two end points
+fn parse_max_points(value: &str) -> Result<usize, String> {
+    let max_points: usize = value
+        .parse()
+        .map_err(|_| format!("'{}' is not a positive integer", value))?;
+    if max_points >= 2 {
+        Ok(max_points)
+    } else {
+        Err(format!("max-points must be at least 2, got {}", max_points))
+    }
+}
+
+/// Marker appended to the table row at the reference temperature
+const REFERENCE_MARKER: &str = "  <- reference";
 
-    for &temp in &args.temperatures {
-        let macs_value = macs::calculate_macs(&energies, &cross_sections, args.mass, temp)?;
-        println!("{:6.1}    {:12.6}", temp, macs_value);
+/// ANSI style of the table row at the reference temperature (bold green)
+const REFERENCE_STYLE: &str = "\x1b[1;32m";
+
+/// ANSI style of table rows with a missing or non-finite value (red)
+const ERROR_STYLE: &str = "\x1b[31m";
+
+/// ANSI sequence restoring the default style
+const RESET_STYLE: &str = "\x1b[0m";
+
+/// Whether table rows are colored
+///
+/// Only tables printed to an interactive terminal are colored, so piped or
+/// saved output stays plain.
+fn use_color(args: &Args) -> bool {
+    !args.no_color
+        && args.output.is_none()
+        && std::env::var_os("NO_COLOR").is_none()
+        && std::io::stdout().is_terminal()
+}
+
+/// Wraps a table row in an ANSI style when coloring is enabled
+fn styled(line: String, style: Option<&str>, color: bool) -> String {
+    match style {
+        Some(style) if color => format!("{}{}{}", style, line, RESET_STYLE),
+        _ => line,
+    }
+}
+
+/// Picks the style of a table row from its values and temperature
+fn row_style(complete: bool, temperature_kev: f64) -> Option<&'static str> {
+    if !complete {
+        Some(ERROR_STYLE)
+    } else if macs::is_reference_temperature(temperature_kev) {
+        Some(REFERENCE_STYLE)
+    } else {
+        None
+    }
+}
+
+/// Writes the results in the selected output format
+fn write_results(
+    out: &mut dyn Write,
+    args: &Args,
+    table: &macs::MacsTable,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let results = &table.results;
+    if args.single_temperature.is_some() {
+        if let Some(result) = results.first() {
+            writeln!(out, "{:.6}", result.macs_mb)?;
+        }
+        return Ok(());
     }
 
+    match args.output_format {
+        OutputFormat::Table => {
+            writeln!(
+                out,
+                "\n=== MACS Calculation for {} {}({}) ===",
+                table.library, table.target, table.reaction
+            )?;
+            if args.kadonis_convention {
+                writeln!(
+                    out,
+                    "KADoNiS convention: flux-weighted, lab frame, extrapolated tails"
+                )?;
+            }
+            if args.branching != 1.0 {
+                writeln!(out, "Branching ratio applied: {}", args.branching)?;
+            }
+            if args.renorm != 1.0 {
+                writeln!(out, "Cross sections renormalized by: {}", args.renorm)?;
+            }
+            if table.approximate {
+                writeln!(
+                    out,
+                    "Approximate: cross section angle-integrated from differential (DA) data"
+                )?;
+            }
+            if !args.quantities.contains(&Quantity::Macs) {
+                write_derived_quantities(out, table)?;
+                return Ok(());
+            }
+            let columns = optional_columns(args);
+            let mut header = String::from("T(keV)    MACS(mb)");
+            if !columns.is_empty() {
+                header.push_str("    ");
+            }
+            for column in &columns {
+                header.push_str(&format!("{:>14}", column.header));
+            }
+            writeln!(out, "\n{}", header)?;
+            writeln!(out, "{}", "-".repeat(header.len().max(20)))?;
+            let color = use_color(args);
+            let scientific = use_scientific(args.notation, results.iter().map(|r| r.macs_mb));
+            for result in results {
+                let macs = if scientific {
+                    format!("{:>12}", units::format_exponent(result.macs_mb, 5))
+                } else {
+                    format!("{:12.6}", result.macs_mb)
+                };
+                let mut line = format!("{:6.1}    {}", result.temperature_kev, macs);
+                for column in &columns {
+                    line.push_str(&match (column.value)(result) {
+                        Some(v) if column.scientific || scientific => {
+                            format!("{:>14}", units::format_exponent(v, 3))
+                        }
+                        Some(v) => format!("{:>14.6}", v),
+                        None => format!("{:>14}", "-"),
+                    });
+                }
+                if macs::is_reference_temperature(result.temperature_kev) {
+                    line.push_str(REFERENCE_MARKER);
+                }
+                let style = row_style(result.macs_mb.is_finite(), result.temperature_kev);
+                writeln!(out, "{}", styled(line, style, color))?;
+            }
+            if let Some(reference) = results
+                .iter()
+                .find(|r| macs::is_reference_temperature(r.temperature_kev))
+            {
+                writeln!(
+                    out,
+                    "\nReference MACS (kT = {} keV): {:.6} mb",
+                    macs::REFERENCE_TEMPERATURE_KEV,
+                    reference.macs_mb
+                )?;
+            }
+            for temperature in &args.interpolate_at {
+                let temperature = temperature.to_kev(args.temperature_unit);
+                match table.interpolate_at(temperature) {
+                    Some(value) => writeln!(
+                        out,
+                        "Interpolated MACS (kT = {} keV): {:.6} mb",
+                        temperature, value
+                    )?,
+                    None => report::warning(format!(
+                        "{} keV is outside the computed temperatures, not interpolated",
+                        temperature
+                    )),
+                }
+            }
+            if let Some(average) = table.spectrum_average_mb {
+                writeln!(out, "Spectrum-averaged cross section: {:.6} mb", average)?;
+            }
+            write_derived_quantities(out, table)?;
+            if let Some(fit) = &table.fit {
+                write_fit(out, fit)?;
+            }
+        }
+        OutputFormat::Csv => {
+            let columns = optional_columns(args);
+            let mut header = String::from("temperature_kev,macs_mb");
+            for column in &columns {
+                header.push(',');
+                header.push_str(column.csv_name);
+            }
+            writeln!(out, "{}", header)?;
+            for result in results {
+                let mut line = format!("{},{}", result.temperature_kev, result.macs_mb);
+                for column in &columns {
+                    line.push(',');
+                    if let Some(v) = (column.value)(result) {
+                        line.push_str(&v.to_string());
+                    }
+                }
+                writeln!(out, "{}", line)?;
+            }
+        }
+        OutputFormat::Json => writeln!(out, "{}", serde_json::to_string_pretty(table)?)?,
+        OutputFormat::Ndjson => write_ndjson(out, table)?,
+        OutputFormat::Reaclib => write_reaclib(out, table)?,
+    }
     Ok(())
 }
+
+/// Writes the --quantities other than the MACS, one line each
+fn write_derived_quantities(out: &mut dyn Write, table: &macs::MacsTable) -> std::io::Result<()> {
+    if let Some(thermal) = table.thermal_cross_section_b {
+        writeln!(out, "Thermal cross section (0.0253 eV): {:.6} b", thermal)?;
+    }
+    if let Some(integral) = table.resonance_integral_b {
+        writeln!(out, "Resonance integral (above 0.5 eV): {:.6} b", integral)?;
+    }
+    Ok(())
+}
+
+/// Writes the per-interval terms of the Maxwellian integral at each temperature as CSV
+fn write_intervals(
+    out: &mut dyn Write,
+    breakdowns: &[(f64, macs::IntegralBreakdown)],
+) -> std::io::Result<()> {
+    writeln!(
+        out,
+        "temperature_kev,e1_mev,e2_mev,sigma1_b,sigma2_b,integrand1,integrand2,area,cumulative,cumulative_macs_mb"
+    )?;
+    for (temperature, breakdown) in breakdowns {
+        for interval in &breakdown.intervals {
+            writeln!(
+                out,
+                "{},{:e},{:e},{:e},{:e},{:e},{:e},{:e},{:e},{:e}",
+                temperature,
+                interval.energies.0,
+                interval.energies.1,
+                interval.cross_sections.0,
+                interval.cross_sections.1,
+                interval.integrand.0,
+                interval.integrand.1,
+                interval.area,
+                interval.cumulative,
+                units::barns_to_millibarns(breakdown.normalization * interval.cumulative)
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the MACS accumulated up to each grid energy for --cumulative
+fn write_cumulative(
+    out: &mut dyn Write,
+    breakdowns: &[(f64, macs::IntegralBreakdown)],
+) -> std::io::Result<()> {
+    writeln!(
+        out,
+        "temperature_kev,energy_mev,cumulative_macs_mb,fraction"
+    )?;
+    for (temperature, breakdown) in breakdowns {
+        let curve = breakdown.cumulative_macs();
+        let total = curve.last().map_or(0.0, |&(_, macs)| macs);
+        for (energy, macs) in curve {
+            writeln!(
+                out,
+                "{},{:e},{:e},{:e}",
+                temperature,
+                energy,
+                macs,
+                macs / total
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Energy in MeV below which `fraction` of the grid MACS has accumulated
+fn accumulation_energy(curve: &[(f64, f64)], fraction: f64) -> Option<f64> {
+    let total = curve.last()?.1;
+    curve
+        .iter()
+        .find(|&&(_, macs)| macs >= fraction * total)
+        .map(|&(energy, _)| energy)
+}
+
+/// Fits the rate of a table and writes it as a REACLIB entry
+///
+/// The fit quality is reported on stderr, since REACLIB files have no
+/// comments; a fit deviating from the rate by more than
+/// [`reaclib::MAX_FIT_DEVIATION`] is warned about.
+fn write_reaclib(
+    out: &mut dyn Write,
+    table: &macs::MacsTable,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (chapter, nuclides) = reaclib::reaction_nuclides(&table.target, &table.reaction)?;
+    let fit = reaclib::fit_rates(&table.results, table.atomic_mass)?;
+    report::status(format!(
+        "REACLIB fit of {} {}({}): largest deviation {:.2}% over T9 = 0.01 - 10",
+        table.library,
+        table.target,
+        table.reaction,
+        fit.max_deviation * 100.0
+    ));
+    if fit.max_deviation > reaclib::MAX_FIT_DEVIATION {
+        report::warning(format!(
+            "the REACLIB fit of {} deviates from the rate by up to {:.1}%",
+            table.library,
+            fit.max_deviation * 100.0
+        ));
+    }
+    reaclib::write_entry(out, chapter, &nuclides, &fit)?;
+    Ok(())
+}
+
+/// Writes the coefficients and residuals of a MACS fit
+fn write_fit(out: &mut dyn Write, fit: &fit::MacsFit) -> std::io::Result<()> {
+    let terms: Vec<String> = (0..fit.coefficients.len())
+        .map(|k| match k {
+            0 => "c0".to_string(),
+            1 => "c1*x".to_string(),
+            _ => format!("c{}*x^{}", k, k),
+        })
+        .collect();
+    writeln!(
+        out,
+        "\nFit: ln(MACS/mb) = {}, x = ln(kT/{} keV)",
+        terms.join(" + "),
+        macs::REFERENCE_TEMPERATURE_KEV
+    )?;
+    for (k, c) in fit.coefficients.iter().enumerate() {
+        writeln!(out, "  c{} = {:.6e}", k, c)?;
+    }
+    writeln!(out, "T(keV)    Residual")?;
+    for &(temperature, residual) in &fit.residuals {
+        writeln!(out, "{:6.1}    {:8.3}%", temperature, residual * 100.0)?;
+    }
+    writeln!(
+        out,
+        "RMS relative residual: {:.3}%",
+        fit.rms_residual * 100.0
+    )
+}
+
+/// Optional per-temperature column of the results
+struct ResultColumn {
+    /// Header in the table output
+    header: &'static str,
+    /// Header in the CSV output
+    csv_name: &'static str,
+    /// Value of the column for a result
+    value: fn(&macs::MacsResult) -> Option<f64>,
+    /// Whether the table shows the value in scientific notation
+    scientific: bool,
+}
+
+/// Lists the optional result columns enabled on the command line
+fn optional_columns(args: &Args) -> Vec<ResultColumn> {
+    let mut columns = Vec::new();
+    if args.error_estimate {
+        columns.push(ResultColumn {
+            header: "NumErr(mb)",
+            csv_name: "integration_error_mb",
+            value: |r| r.integration_error_mb,
+            scientific: true,
+        });
+    }
+    if args.raw_integral {
+        columns.push(ResultColumn {
+            header: "RawIntegral",
+            csv_name: "raw_integral",
+            value: |r| r.raw_integral,
+            scientific: true,
+        });
+    }
+    if args.sef.is_some() {
+        columns.push(ResultColumn {
+            header: "Stellar(mb)",
+            csv_name: "stellar_macs_mb",
+            value: |r| r.stellar_macs_mb,
+            scientific: false,
+        });
+        columns.push(ResultColumn {
+            header: "SEF",
+            csv_name: "stellar_enhancement",
+            value: |r| r.stellar_enhancement,
+            scientific: false,
+        });
+    }
+    if args.resonance.is_some() {
+        columns.push(ResultColumn {
+            header: "Resonance(mb)",
+            csv_name: "resonance_mb",
+            value: |r| r.resonance_mb,
+            scientific: false,
+        });
+        columns.push(ResultColumn {
+            header: "ResFraction",
+            csv_name: "resonance_fraction",
+            value: |r| r.resonance_fraction,
+            scientific: false,
+        });
+    }
+    if args.perturb.is_some() {
+        columns.push(ResultColumn {
+            header: "Perturbed(mb)",
+            csv_name: "perturbed_macs_mb",
+            value: |r| r.perturbed_macs_mb,
+            scientific: false,
+        });
+        columns.push(ResultColumn {
+            header: "Change",
+            csv_name: "perturbation_change",
+            value: |r| r.perturbation_change,
+            scientific: true,
+        });
+    }
+    if args.match_broadening {
+        columns.push(ResultColumn {
+            header: "ProcTemp(K)",
+            csv_name: "processing_temperature",
+            value: |r| Some(r.processing_temperature),
+            scientific: true,
+        });
+    }
+    if args.rate {
+        columns.push(ResultColumn {
+            header: "Rate",
+            csv_name: "reaction_rate",
+            value: |r| r.reaction_rate,
+            scientific: true,
+        });
+    }
+    if args.gamow_peak {
+        columns.push(ResultColumn {
+            header: "E0(keV)",
+            csv_name: "gamow_peak_kev",
+            value: |r| r.gamow_peak_kev,
+            scientific: false,
+        });
+        columns.push(ResultColumn {
+            header: "dE0(keV)",
+            csv_name: "gamow_width_kev",
+            value: |r| r.gamow_width_kev,
+            scientific: false,
+        });
+    }
+    if args.cross_check {
+        columns.push(ResultColumn {
+            header: "Check(mb)",
+            csv_name: "cross_check_macs_mb",
+            value: |r| r.cross_check_macs_mb,
+            scientific: false,
+        });
+        columns.push(ResultColumn {
+            header: "CheckDiff",
+            csv_name: "cross_check_difference",
+            value: |r| r.cross_check_difference,
+            scientific: false,
+        });
+    }
+    if args.background.is_some() {
+        columns.push(ResultColumn {
+            header: "Bkg(mb)",
+            csv_name: "background_mb",
+            value: |r| r.background_mb,
+            scientific: false,
+        });
+    }
+    if args.normalize_to.is_some() {
+        columns.push(ResultColumn {
+            header: "MACS/Ref",
+            csv_name: "normalized_macs",
+            value: |r| r.normalized_macs,
+            scientific: false,
+        });
+    }
+    if args.no_cutoff {
+        columns.push(ResultColumn {
+            header: "Cutoff(mb)",
+            csv_name: "cutoff_macs_mb",
+            value: |r| r.cutoff_macs_mb,
+            scientific: false,
+        });
+    }
+    columns
+}
+
+/// Advances the CLI progress bar by one step per computed temperature, as
+/// the [`macs::ProgressCallback`] of the command line
+fn advance_progress_bar(_completed: usize, _total: usize) {
+    report::advance(1);
+}
+
+/// Computes the MACS while streaming the dataset, without storing its points
+async fn compute_streaming(
+    args: &Args,
+    source: &dyn exfor_client::CrossSectionSource,
+    library: &str,
+    target: &str,
+    mass: f64,
+    temperatures: &[f64],
+    progress: macs::ProgressCallback<'_>,
+) -> Result<Vec<macs::MacsResult>, Box<dyn std::error::Error>> {
+    if args.integration != macs::IntegrationMethod::Trapezoid {
+        return Err("--stream only supports trapezoid integration".into());
+    }
+
+    let mut accumulator = macs::StreamingMacs::new(mass, temperatures, args.weighting)?;
+    let request = exfor_client::FetchRequest {
+        target,
+        za: args.z.zip(args.a),
+        reaction: &args.reaction,
+        quantity: &args.quantity,
+        lib_name: library,
+        eval_id: args.eval_id,
+    };
+    let processing_temperature = source.stream(request, &mut accumulator).await?;
+    report::status(format!(
+        "Streamed {} data points from API",
+        accumulator.points()
+    ));
+    // All temperatures are accumulated together and finish at once
+    if let Some(progress) = progress {
+        for completed in 1..=temperatures.len() {
+            progress(completed, temperatures.len());
+        }
+    }
+
+    let results: Vec<macs::MacsResult> = temperatures
+        .iter()
+        .zip(accumulator.finish()?)
+        .map(|(&temp, macs_value)| macs::MacsResult {
+            branching: args.branching,
+            renormalization: args.renorm,
+            processing_temperature,
+            ..macs::MacsResult::new(temp, macs_value * args.renorm * args.branching)
+        })
+        .collect();
+    Ok(results)
+}
+
+/// Computes the MACS table of one library
+///
+/// Bundles the results of [`compute_library`] with the target metadata, and
+/// returns the integrated cross section as well when --export-grid is set.
+#[allow(clippy::too_many_arguments)]
+async fn compute_table(
+    args: &Args,
+    source: &dyn exfor_client::CrossSectionSource,
+    library: &str,
+    target: &str,
+    mass: f64,
+    temperatures: &[f64],
+    interrupted: &AtomicBool,
+    offline_db: Option<&mut offline_db::OfflineDb>,
+    progress: macs::ProgressCallback<'_>,
+) -> Result<(macs::MacsTable, Option<grid_export::Curve>), Box<dyn std::error::Error>> {
+    let mut library_results = compute_library(
+        args,
+        source,
+        library,
+        target,
+        mass,
+        temperatures,
+        interrupted,
+        offline_db,
+        progress,
+    )
+    .await?;
+    if let Some(sef) = &args.sef {
+        sef.apply(&mut library_results.macs)?;
+    }
+    if args.rate {
+        add_reaction_rates(&mut library_results.macs, mass);
+    }
+    if let Some(reference) = args.normalize_to {
+        normalize_results(
+            &mut library_results.macs,
+            reference.to_kev(args.temperature_unit),
+        );
+    }
+    let fit = match args.fit {
+        Some(degree) => Some(fit::fit_macs_curve(&library_results.macs, degree)?),
+        None => None,
+    };
+    let table = macs::MacsTable {
+        target: target.to_string(),
+        reaction: args.reaction.clone(),
+        library: library_results
+            .library
+            .unwrap_or_else(|| library.to_string()),
+        atomic_mass: mass,
+        results: library_results.macs,
+        spectrum_average_mb: library_results.spectrum_average_mb,
+        thermal_cross_section_b: library_results.thermal_cross_section_b,
+        resonance_integral_b: library_results.resonance_integral_b,
+        datasets: library_results.datasets,
+        data_sources: library_results.data_sources,
+        fit,
+        approximate: library_results.approximate,
+    };
+    Ok((table, library_results.curve))
+}
+
+/// Divides each MACS by the MACS at the reference temperature for --normalize-to
+///
+/// The reference row itself gets exactly 1. If the reference was not
+/// computed (skipped or interrupted) or is zero, no ratio is given.
+fn normalize_results(results: &mut [macs::MacsResult], reference_kev: f64) {
+    let reference = results
+        .iter()
+        .find(|r| r.temperature_kev == reference_kev)
+        .map(|r| r.macs_mb);
+    match reference {
+        Some(reference) if reference != 0.0 && reference.is_finite() => {
+            for result in results {
+                result.normalized_macs = Some(result.macs_mb / reference);
+            }
+        }
+        _ => report::warning(format!(
+            "no usable MACS at the {} keV reference, the results are not normalized",
+            reference_kev
+        )),
+    }
+}
+
+/// Fills in the reaction rate of each result for --rate
+fn add_reaction_rates(results: &mut [macs::MacsResult], mass: f64) {
+    let reduced_mass = macs::neutron_reduced_mass(mass);
+    for result in results {
+        result.reaction_rate = Some(macs::macs_to_rate(
+            result.macs_mb,
+            result.temperature_kev,
+            reduced_mass,
+        ));
+    }
+}
+
+/// Converts a published reaction rate to the MACS it implies, for --from-rate
+///
+/// The same rate is taken at every temperature; the MACS follows from
+/// [`macs::rate_to_macs`] with the neutron-target reduced mass. Without a
+/// target the MACS is labelled X(reaction) and --mass is required.
+fn from_rate_table(
+    args: &Args,
+    rate: f64,
+    temperatures: &[f64],
+) -> Result<macs::MacsTable, Box<dyn std::error::Error>> {
+    let (target, mass) = if args.target.is_some() || args.z.is_some() {
+        let target = resolve_target(args.target.as_deref(), args.z, args.a)?;
+        let mass = target_mass(args, &target)?;
+        (target, mass)
+    } else {
+        let mass = args
+            .mass
+            .ok_or("--from-rate needs the target mass, give --mass or --target")?;
+        ("X".to_string(), mass)
+    };
+    macs::validate_atomic_mass(mass)?;
+    for &temp in temperatures {
+        macs::validate_temperature(temp)?;
+    }
+    let reduced_mass = macs::neutron_reduced_mass(mass);
+    let results = temperatures
+        .iter()
+        .map(|&temp| macs::MacsResult {
+            reaction_rate: Some(rate),
+            ..macs::MacsResult::new(temp, macs::rate_to_macs(rate, temp, reduced_mass))
+        })
+        .collect();
+    Ok(macs::MacsTable {
+        target,
+        reaction: args.reaction.clone(),
+        library: format!("N_A<σv> = {} cm³/mol/s", rate),
+        atomic_mass: mass,
+        results,
+        spectrum_average_mb: None,
+        thermal_cross_section_b: None,
+        resonance_integral_b: None,
+        datasets: Vec::new(),
+        data_sources: Vec::new(),
+        fit: None,
+        approximate: false,
+    })
+}
+
+/// Computes the MACS of a constant cross section, without any data source
+///
+/// The cross section is tabulated flat on a log-spaced grid reaching far into
+/// both tails of every Maxwellian and integrated like fetched data, so that
+/// the result can be checked against [`macs::constant_cross_section_macs`].
+/// Without a target the MACS is labelled X(reaction) and, unless --mass is
+/// given, computed for a single nucleon; it does not depend on the mass.
+fn constant_sigma_table(
+    args: &Args,
+    cross_section: f64,
+    temperatures: &[f64],
+    interrupted: &AtomicBool,
+) -> Result<macs::MacsTable, Box<dyn std::error::Error>> {
+    let (target, mass) = if args.target.is_some() || args.z.is_some() {
+        let target = resolve_target(args.target.as_deref(), args.z, args.a)?;
+        let mass = target_mass(args, &target)?;
+        (target, mass)
+    } else {
+        ("X".to_string(), args.mass.unwrap_or(1.0))
+    };
+
+    let lowest = temperatures.iter().copied().fold(f64::INFINITY, f64::min);
+    let highest = temperatures.iter().copied().fold(0.0, f64::max);
+    let (start, end) = (
+        (1e-4 * macs::thermal_energy(lowest)).log10(),
+        (100.0 * macs::thermal_energy(highest)).log10(),
+    );
+    let points = ((end - start) * CONSTANT_SIGMA_POINTS_PER_DECADE).ceil() as usize + 1;
+    let energies: Vec<f64> = (0..points)
+        .map(|i| 10f64.powf(start + (end - start) * i as f64 / (points - 1) as f64))
+        .collect();
+    let cross_sections = vec![cross_section; points];
+
+    let mut library_results = macs_at_temperatures(
+        args,
+        &energies,
+        &cross_sections,
+        Box::new(interpolation::LogLog),
+        &target,
+        mass,
+        temperatures,
+        0.0,
+        interrupted,
+        Some(&advance_progress_bar),
+    )?;
+    let expected = macs::constant_cross_section_macs(cross_section, args.weighting)
+        * args.renorm
+        * args.branching;
+    report::status(format!(
+        "Analytic MACS of a constant {} b: {:.6} mb",
+        cross_section, expected
+    ));
+    if let Some(sef) = &args.sef {
+        sef.apply(&mut library_results.macs)?;
+    }
+    if args.rate {
+        add_reaction_rates(&mut library_results.macs, mass);
+    }
+    if let Some(reference) = args.normalize_to {
+        normalize_results(
+            &mut library_results.macs,
+            reference.to_kev(args.temperature_unit),
+        );
+    }
+    let fit = match args.fit {
+        Some(degree) => Some(fit::fit_macs_curve(&library_results.macs, degree)?),
+        None => None,
+    };
+    Ok(macs::MacsTable {
+        target,
+        reaction: args.reaction.clone(),
+        library: format!("constant σ = {} b", cross_section),
+        atomic_mass: mass,
+        results: library_results.macs,
+        spectrum_average_mb: library_results.spectrum_average_mb,
+        thermal_cross_section_b: None,
+        resonance_integral_b: None,
+        datasets: Vec::new(),
+        data_sources: Vec::new(),
+        fit,
+        approximate: false,
+    })
+}
+
+/// Downloads the data of one library and computes the MACS at each temperature
+///
+/// Returns early with the results computed so far if `interrupted` is set.
+#[allow(clippy::too_many_arguments)]
+async fn compute_library(
+    args: &Args,
+    source: &dyn exfor_client::CrossSectionSource,
+    library: &str,
+    target: &str,
+    mass: f64,
+    temperatures: &[f64],
+    interrupted: &AtomicBool,
+    offline_db: Option<&mut offline_db::OfflineDb>,
+    progress: macs::ProgressCallback<'_>,
+) -> Result<LibraryResults, Box<dyn std::error::Error>> {
+    if args.stream {
+        report::status(format!(
+            "Downloading {} data for {}({})...",
+            library, target, args.reaction
+        ));
+        let macs =
+            compute_streaming(args, source, library, target, mass, temperatures, progress).await?;
+        return Ok(LibraryResults {
+            macs,
+            ..Default::default()
+        });
+    }
+
+    match load_response(args, source, library, target, interrupted, offline_db).await? {
+        Some(cross_section_data) => {
+            let mut results = compute_from_response(
+                args,
+                &cross_section_data,
+                target,
+                mass,
+                temperatures,
+                interrupted,
+                progress,
+            )?;
+            if args.embed_data {
+                results.datasets = cross_section_data.datasets;
+            }
+            results.library = cross_section_data.library;
+            results.approximate = cross_section_data.angle_integrated;
+            Ok(results)
+        }
+        None => Ok(LibraryResults::default()),
+    }
+}
+
+/// Reads the cross section data of one library
+///
+/// The data is read from the offline snapshot when one is given, unless it is
+/// being refreshed, in which case the downloaded data is stored into it.
+/// Returns `None` if the download is interrupted.
+async fn load_response(
+    args: &Args,
+    source: &dyn exfor_client::CrossSectionSource,
+    library: &str,
+    target: &str,
+    interrupted: &AtomicBool,
+    offline_db: Option<&mut offline_db::OfflineDb>,
+) -> Result<Option<exfor_client::CrossSectionResponse>, Box<dyn std::error::Error>> {
+    if let Some(db) = offline_db.as_deref()
+        && !args.refresh_offline_db
+    {
+        report::status(format!(
+            "Reading {} data for {}({}) from the offline database...",
+            library, target, args.reaction
+        ));
+        let cross_section_data = db.get(target, &args.reaction, library).ok_or_else(|| {
+            format!(
+                "No {} data for {}({}) in the offline database",
+                library, target, args.reaction
+            )
+        })?;
+        return Ok(Some(cross_section_data.clone()));
+    }
+
+    // Fetch cross section data from EXFOR database
+    report::status(format!(
+        "Downloading {} data for {}({})...",
+        library, target, args.reaction
+    ));
+    // Timing is only measured in verbose mode
+    let fetch_start = args.verbose.then(Instant::now);
+    let request = exfor_client::FetchRequest {
+        target,
+        za: args.z.zip(args.a),
+        reaction: &args.reaction,
+        quantity: &args.quantity,
+        lib_name: library,
+        eval_id: args.eval_id,
+    };
+    let fetch = async {
+        match source.fetch(request).await {
+            // Only a listing without the cross section, not e.g. a network error
+            Err(e) if e.is::<exfor_client::NoSectionError>() => {
+                fetch_angle_integrated(source, request).await.ok_or(e)
+            }
+            result => result,
+        }
+    };
+    let cross_section_data = tokio::select! {
+        data = fetch => data?,
+        _ = tokio::signal::ctrl_c() => {
+            interrupted.store(true, Ordering::SeqCst);
+            return Ok(None);
+        }
+    };
+    if let Some(start) = fetch_start {
+        report::status(format!(
+            "Fetch time: {:.3} s",
+            start.elapsed().as_secs_f64()
+        ));
+    }
+
+    if let Some(db) = offline_db {
+        db.insert(target, &args.reaction, library, cross_section_data.clone());
+    }
+    Ok(Some(cross_section_data))
+}
+
+/// Falls back to differential (`DA`) data when no cross section is tabulated
+///
+/// The angular distributions are integrated over the solid angle, see
+/// [`angular`]. Returns `None` if there is no usable differential data either,
+/// so that the original error is reported.
+async fn fetch_angle_integrated(
+    source: &dyn exfor_client::CrossSectionSource,
+    request: exfor_client::FetchRequest<'_>,
+) -> Option<exfor_client::CrossSectionResponse> {
+    report::status(format!(
+        "No {} cross section for {}({}), trying differential (DA) data...",
+        request.lib_name, request.target, request.reaction
+    ));
+    let differential = source
+        .fetch(exfor_client::FetchRequest {
+            quantity: "DA",
+            ..request
+        })
+        .await
+        .ok()?;
+    match angular::angle_integrated_response(&differential) {
+        Ok(response) => {
+            report::warning(format!(
+                "{} cross section for {}({}) angle-integrated from differential data, the MACS is approximate",
+                request.lib_name, request.target, request.reaction
+            ));
+            Some(response)
+        }
+        Err(e) => {
+            report::status(format!("Cannot use the differential data: {}", e));
+            None
+        }
+    }
+}
+
+/// Computes the MACS at each temperature from the dataset broadened closest
+/// to it, for --match-broadening
+///
+/// Temperatures sharing a dataset are computed together, and the results are
+/// returned in the order of `temperatures`. Each records the processing
+/// temperature of its dataset.
+fn compute_matching_broadening(
+    args: &Args,
+    cross_section_data: &exfor_client::CrossSectionResponse,
+    target: &str,
+    mass: f64,
+    temperatures: &[f64],
+    interrupted: &AtomicBool,
+    progress: macs::ProgressCallback<'_>,
+) -> Result<LibraryResults, Box<dyn std::error::Error>> {
+    let mut groups: Vec<(usize, Vec<f64>)> = Vec::new();
+    for &temp in temperatures {
+        let index = cross_section_data
+            .dataset_broadened_for(temp)
+            .ok_or("No dataset found in API response")?;
+        report::status(format!(
+            "kT = {} keV: using dataset {} broadened at {} K",
+            temp, cross_section_data.datasets[index].id, cross_section_data.datasets[index].temp
+        ));
+        match groups.iter_mut().find(|(i, _)| *i == index) {
+            Some((_, group)) => group.push(temp),
+            None => groups.push((index, vec![temp])),
+        }
+    }
+
+    let mut results = LibraryResults::default();
+    for (index, group) in groups {
+        let single = exfor_client::CrossSectionResponse {
+            datasets: vec![cross_section_data.datasets[index].clone()],
+            ..cross_section_data.clone()
+        };
+        let group_results =
+            compute_from_response(args, &single, target, mass, &group, interrupted, progress)?;
+        results.macs.extend(group_results.macs);
+    }
+    results.macs.sort_by_key(|result| {
+        temperatures
+            .iter()
+            .position(|&temp| temp == result.temperature_kev)
+    });
+    Ok(results)
+}
+
+/// Computes the MACS at each temperature from one dataset of a response
+///
+/// The dataset is the one chosen with --dataset-index, by default the first,
+/// or all of them stitched together with --merge-datasets.
+fn compute_from_response(
+    args: &Args,
+    cross_section_data: &exfor_client::CrossSectionResponse,
+    target: &str,
+    mass: f64,
+    temperatures: &[f64],
+    interrupted: &AtomicBool,
+    progress: macs::ProgressCallback<'_>,
+) -> Result<LibraryResults, Box<dyn std::error::Error>> {
+    if args.match_broadening && cross_section_data.datasets.len() > 1 {
+        return compute_matching_broadening(
+            args,
+            cross_section_data,
+            target,
+            mass,
+            temperatures,
+            interrupted,
+            progress,
+        );
+    }
+    if args.merge_datasets && cross_section_data.datasets.len() > 1 {
+        let merged = merge::merge_datasets(&cross_section_data.datasets)?;
+        for range in &merged.ranges {
+            let dataset = &cross_section_data.datasets[range.source];
+            report::status(format!(
+                "Merged dataset {} ({}): {:.2e} - {:.2e} MeV",
+                dataset.id, range.interpolation, range.start, range.end
+            ));
+        }
+        report::status(format!("Loaded {} data points", merged.energies.len()));
+        let processing_temperature = cross_section_data.datasets[0].temp;
+        return macs_at_temperatures(
+            args,
+            &merged.energies,
+            &merged.cross_sections,
+            Box::new(merged.law),
+            target,
+            mass,
+            temperatures,
+            processing_temperature,
+            interrupted,
+            progress,
+        );
+    }
+
+    let datasets = &cross_section_data.datasets;
+    let index = args.dataset_index.map_or(0, |i| i - 1);
+    if let Some(i) = args.dataset_index
+        && i > datasets.len()
+    {
+        return Err(format!(
+            "--dataset-index {} is out of range, the response holds {} datasets",
+            i,
+            datasets.len()
+        )
+        .into());
+    }
+    if datasets.len() > 1 {
+        match args.dataset_index {
+            Some(_) => report::status(format!("Using dataset {} of {}", index + 1, datasets.len())),
+            None => report::warning(format!(
+                "the response holds {} datasets, using the first (id {}); pick one with --dataset-index or join them with --merge-datasets",
+                datasets.len(),
+                datasets[0].id
+            )),
+        }
+    }
+
+    // Extract energy and cross section vectors
+    let (energies, cross_sections, law, law_name, processing_temperature) = if let Some(dataset) =
+        datasets.get(index)
+    {
+        let (energies, cross_sections) = dataset.points_in_mev_barns()?;
+
+        report::status(format!("Loaded {} data points", energies.len()));
+        report::status(format!(
+            "Energy range: {:.2e} - {:.2e} MeV",
+            energies.first().unwrap_or(&0.0),
+            energies.last().unwrap_or(&0.0)
+        ));
+        report::status(format!("Processing temperature: {} K", dataset.temp));
+        if dataset.temp != 0.0 {
+            report::warning(format!(
+                "the cross section is Doppler broadened at {} K, resonance shapes differ from 0 K data",
+                dataset.temp
+            ));
+        }
+        let law = dataset_law(&dataset.default_interpolation);
+        let cross_sections = if args.error_weighted {
+            let uncertainties = dataset.uncertainties_in_barns()?;
+            let (weighted, changed) =
+                error_weighting::error_weighted(&energies, &cross_sections, &uncertainties, &*law);
+            if uncertainties.iter().all(Option::is_none) {
+                report::warning(
+                    "the dataset has no point uncertainties, --error-weighted leaves it unchanged",
+                );
+            } else {
+                report::status(format!(
+                    "Error-weighted {} of {} points",
+                    changed,
+                    energies.len()
+                ));
+            }
+            weighted
+        } else {
+            cross_sections
+        };
+        if args.verbose {
+            let columns: Vec<String> = dataset
+                .columns_data()
+                .iter()
+                .map(|(name, values)| {
+                    let count = values.iter().filter(|v| v.is_some()).count();
+                    format!("{} ({} values)", name, count)
+                })
+                .collect();
+            report::status(format!("Columns: {}", columns.join(", ")));
+            print_interpolation_diagnostic(
+                &energies,
+                &cross_sections,
+                &*law,
+                &dataset.default_interpolation,
+            );
+        }
+        (
+            energies,
+            cross_sections,
+            law,
+            &dataset.default_interpolation,
+            dataset.temp,
+        )
+    } else {
+        return Err("No dataset found in API response".into());
+    };
+
+    if let Some(experimental) = &args.experimental {
+        match experimental.compare(&energies, &cross_sections, &*law) {
+            Some((ratio, points)) => report::status(format!(
+                "Experimental/evaluated cross section: mean ratio {:.4} over {} points",
+                ratio, points
+            )),
+            None => report::warning("no experimental point lies within the evaluated energy range"),
+        }
+        if !args.experimental_range.is_empty() {
+            let overlay = overlay::overlay(
+                &energies,
+                &cross_sections,
+                law_name,
+                experimental,
+                &args.experimental_range,
+            )?;
+            for range in &overlay.ranges {
+                report::status(format!(
+                    "{:?} data: {:.3e} - {:.3e} MeV ({} points)",
+                    range.source, range.start_mev, range.end_mev, range.points
+                ));
+            }
+            let mut results = macs_at_temperatures(
+                args,
+                &overlay.energies,
+                &overlay.cross_sections,
+                Box::new(overlay.law),
+                target,
+                mass,
+                temperatures,
+                processing_temperature,
+                interrupted,
+                progress,
+            )?;
+            results.data_sources = overlay.ranges;
+            return Ok(results);
+        }
+    }
+
+    macs_at_temperatures(
+        args,
+        &energies,
+        &cross_sections,
+        law,
+        target,
+        mass,
+        temperatures,
+        processing_temperature,
+        interrupted,
+        progress,
+    )
+}
+
+/// Results computed from the cross section of one library
+#[derive(Default)]
+struct LibraryResults {
+    /// MACS at each temperature
+    macs: Vec<macs::MacsResult>,
+    /// Cross section averaged over the --spectrum in millibarns
+    spectrum_average_mb: Option<f64>,
+    /// Thermal cross section in barns, for --quantities thermal
+    thermal_cross_section_b: Option<f64>,
+    /// Resonance integral in barns, for --quantities ri
+    resonance_integral_b: Option<f64>,
+    /// Datasets used, kept for --embed-data
+    datasets: Vec<exfor_client::CrossSectionDataset>,
+    /// Library name as spelled by the API, when it differs from --library
+    library: Option<String>,
+    /// Cross section as integrated, kept for --export-grid
+    curve: Option<grid_export::Curve>,
+    /// Energy ranges taken from evaluated and experimental data
+    data_sources: Vec<overlay::SourceRange>,
+    /// Cross section angle-integrated from differential data
+    approximate: bool,
+}
+
+/// Returns the projectile of a reaction if it is charged
+fn charged_projectile(reaction: &str) -> Option<charged::Projectile> {
+    charged::Projectile::from_reaction(reaction).filter(charged::Projectile::is_charged)
+}
+
+/// Rejects options that only apply to neutron-induced reactions, and
+/// --gamow-peak for them
+///
+/// The MACS helpers behind these options (tails, edge ramps, coverage, error
+/// estimates, breakdowns, streaming, Monte Carlo) use the neutron reduced
+/// mass, so a charged-particle result combining them would mix two.
+/// A projectile that is not recognized at all (e.g. a photon) is computed
+/// with the neutron reduced mass A/(1+A), which is warned about.
+fn check_charged_options(args: &Args) -> Result<(), String> {
+    if args.gamow_peak && charged_projectile(&args.reaction).is_none() {
+        return Err(format!(
+            "--gamow-peak is only available for charged-particle reactions, not ({})",
+            args.reaction
+        ));
+    }
+    if charged::Projectile::from_reaction(&args.reaction).is_none() {
+        report::warning(format!(
+            "the projectile of ({}) is not recognized, the reduced mass assumes a neutron",
+            args.reaction
+        ));
+    }
+    if charged_projectile(&args.reaction).is_none() {
+        return Ok(());
+    }
+    let unsupported = [
+        ("--kadonis-convention", args.kadonis_convention),
+        ("--stream", args.stream),
+        ("--extrapolate", args.extrapolate),
+        ("--half-extend-edges", args.half_extend_edges),
+        ("--error-estimate", args.error_estimate),
+        ("--raw-integral", args.raw_integral),
+        ("--resonance", args.resonance.is_some()),
+        ("--validate-mc", args.validate_mc.is_some()),
+        ("--cross-check", args.cross_check),
+        ("--perturb", args.perturb.is_some()),
+        ("--background", args.background.is_some()),
+        ("--dump-intervals", args.dump_intervals.is_some()),
+        ("--cumulative", args.cumulative.is_some()),
+        ("--skip-unsupported", args.skip_unsupported),
+        ("--constant-sigma", args.constant_sigma.is_some()),
+        ("--from-rate", args.from_rate.is_some()),
+        ("--rate", args.rate),
+        ("--spectrum", args.spectrum.is_some()),
+        (
+            "--max-points-tolerance",
+            args.max_points_tolerance.is_some(),
+        ),
+        (
+            "--weighting number-density",
+            args.weighting != macs::MaxwellianWeighting::Flux,
+        ),
+        ("--frame cm", args.frame != macs::EnergyFrame::Lab),
+    ];
+    match unsupported.iter().find(|(_, used)| *used) {
+        Some((option, _)) => Err(format!(
+            "{} is only supported for neutron-induced reactions, not ({})",
+            option, args.reaction
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Fixes the options that define the KADoNiS MACS convention
+///
+/// KADoNiS quotes the laboratory MACS ⟨σv⟩/v_T at kT = 30 keV, averaged over
+/// the Maxwellian flux of the laboratory-frame cross section over all
+/// energies. To reproduce it the temperatures become 30 keV only, the
+/// weighting `flux`, the frame `lab`, the tails are extrapolated up to the
+/// default weight cutoff, and branching and renormalization are reset to 1.
+/// Other values given for these options are reported and overridden; the
+/// requested temperatures are replaced silently since the output shows it.
+fn apply_kadonis_convention(args: &mut Args) {
+    let mut overridden = Vec::new();
+    if args.weighting != macs::MaxwellianWeighting::Flux {
+        overridden.push("--weighting");
+    }
+    if args.frame != macs::EnergyFrame::Lab {
+        overridden.push("--frame");
+    }
+    if args.no_cutoff || args.weight_cutoff != macs::WEIGHT_CUTOFF {
+        overridden.push("--no-cutoff/--weight-cutoff");
+    }
+    if args.branching != 1.0 {
+        overridden.push("--branching");
+    }
+    if args.renorm != 1.0 {
+        overridden.push("--renorm");
+    }
+    if !overridden.is_empty() {
+        report::warning(format!(
+            "--kadonis-convention overrides {}",
+            overridden.join(", ")
+        ));
+    }
+
+    args.temperatures = vec![units::Temperature {
+        value: macs::REFERENCE_TEMPERATURE_KEV,
+        unit: Some(units::TemperatureUnit::Kev),
+    }];
+    args.single_temperature = args.single_temperature.map(|_| units::Temperature {
+        value: macs::REFERENCE_TEMPERATURE_KEV,
+        unit: Some(units::TemperatureUnit::Kev),
+    });
+    args.weighting = macs::MaxwellianWeighting::Flux;
+    args.frame = macs::EnergyFrame::Lab;
+    args.extrapolate = true;
+    args.no_cutoff = false;
+    args.weight_cutoff = macs::WEIGHT_CUTOFF;
+    args.branching = 1.0;
+    args.renorm = 1.0;
+}
+
+/// Checks the MACS of a downsampled grid against the full grid, for
+/// --max-points-tolerance
+///
+/// Both grids are integrated without tails at each temperature, and the
+/// relative difference (reduced - full)/full is reported.
+///
+/// # Returns
+/// * `Ok(())` - Every temperature agrees within `tolerance`
+/// * `Err(msg)` - The temperatures that do not, with their differences
+fn check_downsampling(
+    args: &Args,
+    full: (&[f64], &[f64]),
+    reduced: (&[f64], &[f64]),
+    law: &dyn interpolation::Interpolation,
+    mass: f64,
+    temperatures: &[f64],
+    tolerance: f64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let options = macs::MacsOptions {
+        weighting: args.weighting,
+        method: args.integration,
+        law,
+        ..macs::MacsOptions::default()
+    };
+    let full_table =
+        macs::calculate_macs_table(full.0, full.1, mass, temperatures, &options, None)?;
+    let reduced_table =
+        macs::calculate_macs_table(reduced.0, reduced.1, mass, temperatures, &options, None)?;
+    let mut failures = Vec::new();
+    for (full, reduced) in full_table.iter().zip(&reduced_table) {
+        let (temp, full_mb, reduced_mb) = (full.temperature_kev, full.macs_mb, reduced.macs_mb);
+        let difference = (reduced_mb - full_mb) / full_mb;
+        report::status(format!(
+            "Downsampled MACS at {} keV: {:.6} mb vs {:.6} mb on the full grid ({:+.3e})",
+            temp, reduced_mb, full_mb, difference
+        ));
+        // A NaN difference (vanishing MACS) fails as well
+        if difference.is_nan() || difference.abs() > tolerance {
+            failures.push(format!("{} keV ({:+.3e})", temp, difference));
+        }
+    }
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "The downsampled MACS differs from the full grid by more than {:e} at {}; raise --max-points",
+            tolerance,
+            failures.join(", ")
+        )
+        .into())
+    }
+}
+
+/// Calculates the MACS of a cross section at each temperature, and its
+/// average over the --spectrum if one is given
+#[allow(clippy::too_many_arguments)]
+fn macs_at_temperatures(
+    args: &Args,
+    energies: &[f64],
+    cross_sections: &[f64],
+    law: Box<dyn interpolation::Interpolation>,
+    target: &str,
+    mass: f64,
+    temperatures: &[f64],
+    processing_temperature: f64,
+    interrupted: &AtomicBool,
+    progress: macs::ProgressCallback<'_>,
+) -> Result<LibraryResults, Box<dyn std::error::Error>> {
+    let renormalized: Vec<f64>;
+    let cross_sections = if args.renorm != 1.0 {
+        renormalized = cross_sections.iter().map(|cs| cs * args.renorm).collect();
+        &renormalized
+    } else {
+        cross_sections
+    };
+
+    let lab_energies: Vec<f64>;
+    let energies = match args.frame {
+        macs::EnergyFrame::Lab => energies,
+        frame => {
+            lab_energies = energies
+                .iter()
+                .map(|&e| macs::to_lab_energy(e, mass, frame))
+                .collect();
+            &lab_energies
+        }
+    };
+
+    let reduced;
+    let (energies, cross_sections) = match args.max_points {
+        Some(max_points) if energies.len() > max_points => {
+            reduced = downsample::downsample(energies, cross_sections, &*law, max_points);
+            report::status(format!(
+                "Downsampled {} to {} points ({} dropped)",
+                energies.len(),
+                reduced.0.len(),
+                energies.len() - reduced.0.len()
+            ));
+            if let Some(tolerance) = args.max_points_tolerance {
+                check_downsampling(
+                    args,
+                    (energies, cross_sections),
+                    (&reduced.0, &reduced.1),
+                    &*law,
+                    mass,
+                    temperatures,
+                    tolerance,
+                )?;
+            }
+            (reduced.0.as_slice(), reduced.1.as_slice())
+        }
+        _ => (energies, cross_sections),
+    };
+
+    let resampled;
+    let (energies, cross_sections) = match &args.integration_grid {
+        Some(grid) => {
+            resampled = grid.resample(energies, cross_sections, &*law, args.grid_variable)?;
+            report::status(format!(
+                "Resampled {} points onto {} integration grid points",
+                energies.len(),
+                resampled.0.len()
+            ));
+            if resampled.2 > 0 {
+                report::warning(format!(
+                    "{} integration grid point(s) outside the cross section's energy range were dropped",
+                    resampled.2
+                ));
+            }
+            (resampled.0.as_slice(), resampled.1.as_slice())
+        }
+        None => (energies, cross_sections),
+    };
+
+    let thresholded: Vec<f64>;
+    let (cross_sections, threshold) = match args.threshold {
+        Some(threshold) => {
+            thresholded = macs::zero_below_threshold(energies, cross_sections, threshold);
+            let zeroed = cross_sections
+                .iter()
+                .zip(&thresholded)
+                .filter(|(cs, zero)| cs != zero)
+                .count();
+            if zeroed > 0 {
+                report::status(format!(
+                    "Set {} point(s) below the {:.3e} MeV threshold to zero",
+                    zeroed, threshold
+                ));
+            }
+            (thresholded.as_slice(), Some(threshold))
+        }
+        None => (
+            cross_sections,
+            macs::detect_threshold(energies, cross_sections),
+        ),
+    };
+    if let Some(threshold) = threshold
+        && args.extrapolate
+    {
+        report::status(format!(
+            "Reaction threshold at {:.3e} MeV, the cross section is not extrapolated below the grid",
+            threshold
+        ));
+    }
+
+    let with_background: Vec<f64>;
+    let (cross_sections, data_cross_sections) = match args.background {
+        Some(background) => {
+            report::status(format!("Adding a background cross section: {}", background));
+            with_background = background.apply(energies, cross_sections);
+            (with_background.as_slice(), cross_sections)
+        }
+        None => (cross_sections, cross_sections),
+    };
+
+    if let Some(perturbation) = args.perturb
+        && !energies
+            .iter()
+            .any(|e| (perturbation.start..=perturbation.end).contains(e))
+    {
+        report::warning(format!(
+            "no grid point lies in the --perturb window {} - {} MeV, the MACS is unchanged",
+            perturbation.start, perturbation.end
+        ));
+    }
+
+    let charged_projectile = charged_projectile(&args.reaction);
+    let target_z = match charged_projectile {
+        Some(_) => Some(
+            nuclide::parse_target(target)
+                .ok_or_else(|| format!("Cannot read the atomic number of target '{}'", target))?
+                .0,
+        ),
+        None => None,
+    };
+
+    // Calculate MACS at specified temperatures
+    let mut results = Vec::with_capacity(temperatures.len());
+    let mut breakdowns = Vec::new();
+    for (index, &temp) in temperatures.iter().enumerate() {
+        if interrupted.load(Ordering::SeqCst) {
+            break;
+        }
+        // The weight is that of a neutron; charged projectiles are left unchecked
+        let check_coverage = !args.extrapolate && charged_projectile.is_none();
+        if args.skip_unsupported || check_coverage {
+            let (below, above) = macs::maxwellian_outside(energies, mass, temp, args.weighting);
+            let coverage = macs::Coverage::from_outside(below, above, args.support_threshold);
+            if coverage != macs::Coverage::Full {
+                let skipped = if args.skip_unsupported {
+                    ", the temperature is skipped"
+                } else {
+                    ""
+                };
+                report::warning(format!(
+                    "{:.2}% of the Maxwellian weight at {} keV lies outside the data ({}){}",
+                    (below + above) * 100.0,
+                    temp,
+                    coverage,
+                    skipped
+                ));
+                if args.skip_unsupported {
+                    if let Some(progress) = progress {
+                        progress(index + 1, temperatures.len());
+                    }
+                    continue;
+                }
+            }
+        }
+        let calculation_start = args.verbose.then(Instant::now);
+        // Tails are added below, where they are also reported
+        let options = macs::MacsOptions {
+            weighting: args.weighting,
+            method: args.integration,
+            law: &*law,
+            ..macs::MacsOptions::default()
+        };
+        let mut gamow_window = None;
+        let mut macs_value = match charged_projectile.zip(target_z) {
+            Some((projectile, z)) => {
+                let (peak, width) =
+                    charged::CoulombSystem::new(projectile, z, mass).gamow_window(temp);
+                if args.verbose {
+                    report::status(format!(
+                        "Gamow window at {} keV: {:.3} ± {:.3} keV (center of mass)",
+                        temp,
+                        peak,
+                        width / 2.0
+                    ));
+                }
+                if args.gamow_peak {
+                    gamow_window = Some((peak, width));
+                }
+                charged::calculate_charged_macs(
+                    energies,
+                    cross_sections,
+                    projectile,
+                    z,
+                    mass,
+                    temp,
+                )?
+            }
+            None => {
+                if args.verbose {
+                    match macs::sensitivity_window(
+                        energies,
+                        cross_sections,
+                        mass,
+                        temp,
+                        args.weighting,
+                    ) {
+                        Ok((centroid, spread)) => report::status(format!(
+                            "Sensitivity at {} keV: centroid {:.3e} ± {:.3e} MeV",
+                            temp, centroid, spread
+                        )),
+                        Err(e) => {
+                            report::warning(format!("no sensitivity window at {} keV: {}", temp, e))
+                        }
+                    }
+                }
+                macs::calculate_macs_with_options(energies, cross_sections, mass, temp, &options)?
+            }
+        };
+        if let Some(samples) = args.validate_mc {
+            let estimate = montecarlo::monte_carlo_macs(
+                energies,
+                cross_sections,
+                &*law,
+                mass,
+                temp,
+                args.weighting,
+                samples,
+                montecarlo::DEFAULT_SEED,
+            )?;
+            let deviation = (estimate.macs_mb - macs_value) / estimate.std_error_mb;
+            report::status(format!(
+                "Monte Carlo at {} keV: {:.6} ± {:.6} mb ({} samples) vs {:.6} mb, {:+.2} standard errors",
+                temp, estimate.macs_mb, estimate.std_error_mb, samples, macs_value, deviation
+            ));
+            if deviation.abs() > MC_TOLERANCE_SIGMAS {
+                report::warning(format!(
+                    "the Monte Carlo MACS at {} keV differs from the integral by more than {} standard errors",
+                    temp, MC_TOLERANCE_SIGMAS
+                ));
+            }
+        }
+        if args.dump_intervals.is_some() || args.cumulative.is_some() {
+            let breakdown = macs::integral_breakdown(
+                energies,
+                cross_sections,
+                mass,
+                temp,
+                args.weighting,
+                args.integration,
+                &*law,
+            )?;
+            breakdowns.push((temp, breakdown));
+        }
+        // The tails and edge ramps do not depend on the method, so the grid
+        // difference is the whole change
+        let cross_check_delta = if args.cross_check {
+            let method = match args.integration {
+                macs::IntegrationMethod::Trapezoid => macs::IntegrationMethod::Adaptive,
+                macs::IntegrationMethod::Adaptive => macs::IntegrationMethod::Trapezoid,
+            };
+            let other_options = macs::MacsOptions { method, ..options };
+            let value = macs::calculate_macs_with_options(
+                energies,
+                cross_sections,
+                mass,
+                temp,
+                &other_options,
+            )?;
+            if args.verbose {
+                report::status(format!(
+                    "{:?} integration at {} keV: {:.6} mb",
+                    method, temp, value
+                ));
+            }
+            Some(value - macs_value)
+        } else {
+            None
+        };
+        // A window reaching the grid edges also changes the tails and edge
+        // ramps, whose differences are added below
+        let perturbed = args
+            .perturb
+            .map(|perturbation| perturbation.apply(energies, cross_sections));
+        let mut perturbation_delta = match &perturbed {
+            Some(perturbed) => Some(
+                macs::calculate_macs_with_options(energies, perturbed, mass, temp, &options)?
+                    - macs_value,
+            ),
+            None => None,
+        };
+        // The difference from the data alone, tails included below
+        let mut background_delta = match args.background {
+            Some(_) => Some(
+                macs_value
+                    - macs::calculate_macs_with_options(
+                        energies,
+                        data_cross_sections,
+                        mass,
+                        temp,
+                        &options,
+                    )?,
+            ),
+            None => None,
+        };
+        let mut cutoff_macs_mb = None;
+        let mut extrapolation_upper_mev = None;
+        let mut extrapolation_fractions = None;
+        if args.extrapolate {
+            let weight_cutoff = (!args.no_cutoff).then_some(args.weight_cutoff);
+            let tails = macs::extrapolated_tails(
+                energies,
+                cross_sections,
+                mass,
+                temp,
+                args.weighting,
+                weight_cutoff,
+                threshold,
+            )?;
+            if tails.capped {
+                report::warning(format!(
+                    "--weight-cutoff {:e} lies beyond where the Maxwellian underflows at {} keV, the tail stops at {:.3e} MeV",
+                    args.weight_cutoff, temp, tails.upper_energy
+                ));
+            }
+            extrapolation_upper_mev = Some(tails.upper_energy);
+            extrapolation_fractions = tails.fractions(macs_value);
+            if args.verbose {
+                report::status(format!(
+                    "Extrapolation at {} keV: {:.6} mb below and {:.6} mb above the grid, up to {:.3e} MeV",
+                    temp, tails.low_mb, tails.high_mb, tails.upper_energy
+                ));
+                if let Some((low, tabulated, high)) = extrapolation_fractions {
+                    report::status(format!(
+                        "MACS shares at {} keV: {:.2}% extrapolated below, {:.2}% tabulated, {:.2}% extrapolated above",
+                        temp,
+                        low * 100.0,
+                        tabulated * 100.0,
+                        high * 100.0
+                    ));
+                }
+            }
+            if args.no_cutoff {
+                let cut = macs::extrapolated_tails(
+                    energies,
+                    cross_sections,
+                    mass,
+                    temp,
+                    args.weighting,
+                    Some(args.weight_cutoff),
+                    threshold,
+                )?;
+                cutoff_macs_mb = Some((macs_value + cut.low_mb + cut.high_mb) * args.branching);
+            }
+            if let Some(delta) = &mut background_delta {
+                let data_tails = macs::extrapolated_tails(
+                    energies,
+                    data_cross_sections,
+                    mass,
+                    temp,
+                    args.weighting,
+                    weight_cutoff,
+                    threshold,
+                )?;
+                *delta += tails.low_mb + tails.high_mb - data_tails.low_mb - data_tails.high_mb;
+            }
+            if let (Some(delta), Some(perturbed)) = (&mut perturbation_delta, &perturbed) {
+                let perturbed_tails = macs::extrapolated_tails(
+                    energies,
+                    perturbed,
+                    mass,
+                    temp,
+                    args.weighting,
+                    weight_cutoff,
+                    threshold,
+                )?;
+                *delta +=
+                    perturbed_tails.low_mb + perturbed_tails.high_mb - tails.low_mb - tails.high_mb;
+            }
+            macs_value += tails.low_mb + tails.high_mb;
+        }
+        if args.half_extend_edges {
+            let (low, high) =
+                macs::half_interval_edges(energies, cross_sections, mass, temp, args.weighting)?;
+            if args.verbose {
+                report::status(format!(
+                    "Edge ramps at {} keV: {:.6} mb below and {:.6} mb above the grid",
+                    temp, low, high
+                ));
+            }
+            if let Some(delta) = &mut background_delta {
+                let (data_low, data_high) = macs::half_interval_edges(
+                    energies,
+                    data_cross_sections,
+                    mass,
+                    temp,
+                    args.weighting,
+                )?;
+                *delta += low + high - data_low - data_high;
+            }
+            if let (Some(delta), Some(perturbed)) = (&mut perturbation_delta, &perturbed) {
+                let (perturbed_low, perturbed_high) =
+                    macs::half_interval_edges(energies, perturbed, mass, temp, args.weighting)?;
+                *delta += perturbed_low + perturbed_high - low - high;
+            }
+            macs_value += low + high;
+        }
+        let background_mb = background_delta.map(|delta| delta * args.branching);
+        let perturbed_macs_mb =
+            perturbation_delta.map(|delta| (macs_value + delta) * args.branching);
+        let perturbation_change = perturbation_delta.map(|delta| delta / macs_value);
+        let cross_check_macs_mb =
+            cross_check_delta.map(|delta| (macs_value + delta) * args.branching);
+        let cross_check_difference = cross_check_delta.map(|delta| delta / macs_value);
+        if let Some(difference) = cross_check_difference
+            && difference.abs() > CROSS_CHECK_TOLERANCE
+        {
+            report::warning(format!(
+                "the integration methods differ by {:+.2}% at {} keV, the grid may be too coarse for the data",
+                difference * 100.0,
+                temp
+            ));
+        }
+        let raw_integral = if args.raw_integral {
+            Some(macs::raw_integral(
+                energies,
+                cross_sections,
+                mass,
+                temp,
+                args.weighting,
+                args.integration,
+                &*law,
+            )?)
+        } else {
+            None
+        };
+        let (resonance_mb, resonance_fraction) = match args.resonance {
+            Some(window) => {
+                let resonance = macs::resonance_contribution(
+                    energies,
+                    cross_sections,
+                    mass,
+                    temp,
+                    args.weighting,
+                    args.integration,
+                    &*law,
+                    window,
+                )?;
+                (
+                    Some(resonance.contribution_mb * args.branching),
+                    Some(resonance.fraction),
+                )
+            }
+            None => (None, None),
+        };
+        let integration_error_mb = if args.error_estimate {
+            let error = macs::integration_error_estimate(
+                energies,
+                cross_sections,
+                mass,
+                temp,
+                args.weighting,
+                args.integration,
+                &*law,
+            )?;
+            Some(error * args.branching)
+        } else {
+            None
+        };
+        results.push(macs::MacsResult {
+            branching: args.branching,
+            renormalization: args.renorm,
+            processing_temperature,
+            integration_error_mb,
+            cutoff_macs_mb,
+            extrapolation_upper_mev,
+            low_extrapolation_fraction: extrapolation_fractions.map(|(low, _, _)| low),
+            tabulated_fraction: extrapolation_fractions.map(|(_, tabulated, _)| tabulated),
+            high_extrapolation_fraction: extrapolation_fractions.map(|(_, _, high)| high),
+            raw_integral,
+            resonance_mb,
+            resonance_fraction,
+            perturbed_macs_mb,
+            perturbation_change,
+            cross_check_macs_mb,
+            cross_check_difference,
+            background_mb,
+            gamow_peak_kev: gamow_window.map(|(peak, _)| peak),
+            gamow_width_kev: gamow_window.map(|(_, width)| width),
+            ..macs::MacsResult::new(temp, macs_value * args.branching)
+        });
+        if let Some(start) = calculation_start {
+            report::status(format!(
+                "Calculation time at {} keV: {:.3} ms",
+                temp,
+                start.elapsed().as_secs_f64() * 1000.0
+            ));
+        }
+        if let Some(progress) = progress {
+            progress(index + 1, temperatures.len());
+        }
+    }
+    if let Some(path) = &args.dump_intervals {
+        let mut file = std::io::BufWriter::new(create_output_file(path)?);
+        write_intervals(&mut file, &breakdowns)?;
+        file.flush()?;
+        report::status(format!("Integral intervals written to {}", path.display()));
+    }
+    if let Some(path) = &args.cumulative {
+        for (temperature, breakdown) in &breakdowns {
+            let curve = breakdown.cumulative_macs();
+            if let (Some(half), Some(most)) = (
+                accumulation_energy(&curve, 0.5),
+                accumulation_energy(&curve, 0.9),
+            ) {
+                report::status(format!(
+                    "At {} keV, 50% of the MACS accumulates below {:.3e} MeV and 90% below {:.3e} MeV",
+                    temperature, half, most
+                ));
+            }
+        }
+        let mut file = std::io::BufWriter::new(create_output_file(path)?);
+        write_cumulative(&mut file, &breakdowns)?;
+        file.flush()?;
+        report::status(format!("Cumulative MACS written to {}", path.display()));
+    }
+    let spectrum_average_mb = match &args.spectrum {
+        Some(spectrum) => {
+            let (average, coverage) =
+                spectrum.average_cross_section(energies, cross_sections, &*law)?;
+            if coverage < 1.0 {
+                report::warning(format!(
+                    "only {:.1}% of the spectrum's flux lies within the cross section's energy range",
+                    coverage * 100.0
+                ));
+            }
+            report::status(format!(
+                "Spectrum-averaged cross section: {:.6} mb",
+                average
+            ));
+            Some(average)
+        }
+        None => None,
+    };
+    let derived = |quantity, name, value: Option<f64>| {
+        if !args.quantities.contains(&quantity) {
+            return None;
+        }
+        if value.is_none() {
+            report::warning(format!("the cross section does not cover the {}", name));
+        }
+        value.map(|v| v * args.branching)
+    };
+    let thermal_cross_section_b = derived(
+        Quantity::Thermal,
+        "thermal energy (0.0253 eV)",
+        macs::thermal_cross_section(energies, cross_sections, &*law),
+    );
+    let resonance_integral_b = derived(
+        Quantity::Ri,
+        "cadmium cutoff (0.5 eV) of the resonance integral",
+        macs::resonance_integral(
+            energies,
+            cross_sections,
+            &*law,
+            args.ri_method,
+            args.loglog_epsilon.unwrap_or(macs::LOGLOG_EPSILON),
+        ),
+    );
+    Ok(LibraryResults {
+        macs: results,
+        spectrum_average_mb,
+        thermal_cross_section_b,
+        resonance_integral_b,
+        curve: args.export_grid.is_some().then(|| grid_export::Curve {
+            energies: energies.to_vec(),
+            cross_sections: cross_sections.to_vec(),
+            law,
+        }),
+        ..Default::default()
+    })
+}
+
+/// MACS of several libraries at one temperature, with their spread
+#[derive(Debug, serde::Serialize)]
+struct ComparisonRow {
+    /// Temperature in keV
+    temperature_kev: f64,
+    /// MACS in millibarns per library, in the order of --library
+    macs_mb: Vec<Option<f64>>,
+    /// Mean MACS over the libraries in millibarns
+    mean_mb: Option<f64>,
+    /// Inter-library standard deviation in millibarns
+    spread_mb: Option<f64>,
+}
+
+/// Writes the MACS of several libraries side by side with the inter-library spread
+fn write_comparison(
+    out: &mut dyn Write,
+    args: &Args,
+    target: &str,
+    temperatures: &[f64],
+    library_results: &[macs::MacsTable],
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Libraries may lack different temperatures (e.g. with --skip-unsupported),
+    // so rows are matched by temperature; those no library has are left out
+    let temperatures: Vec<f64> = temperatures
+        .iter()
+        .copied()
+        .filter(|&temperature| {
+            library_results
+                .iter()
+                .flat_map(|table| &table.results)
+                .any(|r| r.temperature_kev == temperature)
+        })
+        .collect();
+
+    let rows: Vec<ComparisonRow> = temperatures
+        .iter()
+        .map(|&temperature_kev| {
+            let macs_mb: Vec<Option<f64>> = library_results
+                .iter()
+                .map(|table| {
+                    table
+                        .results
+                        .iter()
+                        .find(|r| r.temperature_kev == temperature_kev)
+                        .map(|r| r.macs_mb)
+                })
+                .collect();
+            let available: Vec<f64> = macs_mb.iter().flatten().copied().collect();
+            let spread = macs::library_spread(&available);
+            ComparisonRow {
+                temperature_kev,
+                macs_mb,
+                mean_mb: spread.map(|(mean, _)| mean),
+                spread_mb: spread.map(|(_, std_dev)| std_dev),
+            }
+        })
+        .collect();
+
+    let format_mb = |value: Option<f64>| match value {
+        Some(v) => format!("{:>14.6}", v),
+        None => format!("{:>14}", "-"),
+    };
+
+    match args.output_format {
+        OutputFormat::Table => {
+            writeln!(
+                out,
+                "\n=== MACS Comparison for {}({}) ===",
+                target, args.reaction
+            )?;
+            if args.branching != 1.0 {
+                writeln!(out, "Branching ratio applied: {}", args.branching)?;
+            }
+            if args.renorm != 1.0 {
+                writeln!(out, "Cross sections renormalized by: {}", args.renorm)?;
+            }
+            let mut header = String::from("\nT(keV)");
+            for table in library_results {
+                header.push_str(&format!("{:>14}", table.library));
+            }
+            header.push_str(&format!("{:>14}{:>14}", "Mean(mb)", "Spread(mb)"));
+            writeln!(out, "{}", header)?;
+            writeln!(out, "{}", "-".repeat(header.len() - 1))?;
+            let color = use_color(args);
+            for row in &rows {
+                let mut line = format!("{:6.1}", row.temperature_kev);
+                for &value in &row.macs_mb {
+                    line.push_str(&format_mb(value));
+                }
+                line.push_str(&format_mb(row.mean_mb));
+                line.push_str(&format_mb(row.spread_mb));
+                if macs::is_reference_temperature(row.temperature_kev) {
+                    line.push_str(REFERENCE_MARKER);
+                }
+                let complete = row.macs_mb.iter().all(|v| v.is_some_and(f64::is_finite));
+                let style = row_style(complete, row.temperature_kev);
+                writeln!(out, "{}", styled(line, style, color))?;
+            }
+            if args.spectrum.is_some() {
+                let mut line = String::from("\nSpectrum");
+                for table in library_results {
+                    line.push_str(&format_mb(table.spectrum_average_mb));
+                }
+                writeln!(out, "{}", line)?;
+            }
+            if args.quantities.contains(&Quantity::Thermal) {
+                let mut line = String::from("\nThermal(b)");
+                for table in library_results {
+                    line.push_str(&format_mb(table.thermal_cross_section_b));
+                }
+                writeln!(out, "{}", line)?;
+            }
+            if args.quantities.contains(&Quantity::Ri) {
+                let mut line = String::from("\nRI(b)");
+                for table in library_results {
+                    line.push_str(&format_mb(table.resonance_integral_b));
+                }
+                writeln!(out, "{}", line)?;
+            }
+        }
+        OutputFormat::Csv => {
+            let libraries: Vec<&str> = library_results.iter().map(|t| t.library.as_str()).collect();
+            writeln!(
+                out,
+                "temperature_kev,{},mean_mb,spread_mb",
+                libraries.join(",")
+            )?;
+            let format_csv = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or_default();
+            for row in &rows {
+                let mut line = row.temperature_kev.to_string();
+                for &value in row.macs_mb.iter().chain([&row.mean_mb, &row.spread_mb]) {
+                    line.push(',');
+                    line.push_str(&format_csv(value));
+                }
+                writeln!(out, "{}", line)?;
+            }
+        }
+        OutputFormat::Json => {
+            let libraries: Vec<&str> = library_results.iter().map(|t| t.library.as_str()).collect();
+            let mut comparison = serde_json::json!({ "libraries": libraries, "rows": rows });
+            if args.spectrum.is_some() {
+                let averages: Vec<Option<f64>> = library_results
+                    .iter()
+                    .map(|t| t.spectrum_average_mb)
+                    .collect();
+                comparison["spectrum_average_mb"] = serde_json::json!(averages);
+            }
+            if args.quantities.contains(&Quantity::Thermal) {
+                let values: Vec<Option<f64>> = library_results
+                    .iter()
+                    .map(|t| t.thermal_cross_section_b)
+                    .collect();
+                comparison["thermal_cross_section_b"] = serde_json::json!(values);
+            }
+            if args.quantities.contains(&Quantity::Ri) {
+                let values: Vec<Option<f64>> = library_results
+                    .iter()
+                    .map(|t| t.resonance_integral_b)
+                    .collect();
+                comparison["resonance_integral_b"] = serde_json::json!(values);
+            }
+            writeln!(out, "{}", serde_json::to_string_pretty(&comparison)?)?;
+        }
+        OutputFormat::Ndjson => {
+            for table in library_results {
+                write_ndjson(out, table)?;
+            }
+        }
+        OutputFormat::Reaclib => {
+            for table in library_results {
+                write_reaclib(out, table)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes the metadata of every dataset in a response
+///
+/// With an atomic mass, the coverage of each temperature by each dataset is
+/// listed too (see [`macs::dataset_covers`]).
+fn write_description(
+    out: &mut dyn Write,
+    library: &str,
+    target: &str,
+    response: &exfor_client::CrossSectionResponse,
+    coverage: Option<(f64, &[f64])>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    writeln!(out, "\n=== Datasets of {} {} ===", library, target)?;
+    writeln!(out, "Produced by {} on {}", response.program, response.now)?;
+    let count = response.datasets.len();
+    for (i, dataset) in response.datasets.iter().enumerate() {
+        writeln!(out, "\nDataset {} of {} (id {})", i + 1, count, dataset.id)?;
+        writeln!(out, "  File:          {}", dataset.file)?;
+        writeln!(out, "  Library:       {}", dataset.library)?;
+        writeln!(out, "  Target:        {}", dataset.target)?;
+        writeln!(
+            out,
+            "  Reaction:      {} (MAT {}, MF {}, MT {}, NSUB {})",
+            dataset.reaction, dataset.mat, dataset.mf, dataset.mt, dataset.nsub
+        )?;
+        writeln!(out, "  Data type:     {}", dataset.data_type)?;
+        writeln!(out, "  Temperature:   {} K", dataset.temp)?;
+        writeln!(out, "  Interpolation: {}", dataset.default_interpolation)?;
+        writeln!(
+            out,
+            "  Points:        {} (declared {})",
+            dataset.points.len(),
+            dataset.n_pts
+        )?;
+        if let (Some(first), Some(last)) = (dataset.points.first(), dataset.points.last()) {
+            writeln!(
+                out,
+                "  Energy range:  {:.4e} - {:.4e} eV",
+                first.energy, last.energy
+            )?;
+            let (min, max) = dataset
+                .points
+                .iter()
+                .map(|p| p.cross_section)
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), cs| {
+                    (min.min(cs), max.max(cs))
+                });
+            writeln!(out, "  Cross section: {:.4e} - {:.4e} b", min, max)?;
+        }
+        let columns: Vec<String> = dataset
+            .columns_data()
+            .iter()
+            .map(|(name, values)| {
+                let present = values.iter().filter(|v| v.is_some()).count();
+                format!("{} ({} values)", name, present)
+            })
+            .collect();
+        writeln!(out, "  Columns:       {}", columns.join(", "))?;
+        if let Some((mass, temperatures)) = coverage {
+            let mut lines = Vec::with_capacity(temperatures.len());
+            for &temperature in temperatures {
+                let covers = macs::dataset_covers(dataset, mass, temperature)?;
+                lines.push(format!("{} keV {}", temperature, covers));
+            }
+            writeln!(out, "  Coverage:      {}", lines.join(", "))?;
+        }
+    }
+    Ok(())
+}
+
+/// Prints the metadata of the datasets of each library without computing the MACS
+async fn describe(
+    args: &Args,
+    source: &dyn exfor_client::CrossSectionSource,
+    target: &str,
+    interrupted: &AtomicBool,
+    mut offline_db: Option<&mut offline_db::OfflineDb>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut responses = Vec::with_capacity(args.library.len());
+    for library in &args.library {
+        match load_response(
+            args,
+            source,
+            library,
+            target,
+            interrupted,
+            offline_db.as_deref_mut(),
+        )
+        .await?
+        {
+            Some(response) => responses.push((library, response)),
+            None => break,
+        }
+    }
+
+    if let (Some(path), Some(db)) = (&args.offline_db, &offline_db)
+        && args.refresh_offline_db
+    {
+        db.save(path)?;
+        report::status(format!("Offline database written to {}", path.display()));
+    }
+
+    report::check_strict()?;
+
+    let mut out = open_output(args)?;
+    let temperatures: Vec<f64> = args
+        .temperatures
+        .iter()
+        .map(|t| t.to_kev(args.temperature_unit))
+        .collect();
+    let coverage = args.mass.map(|mass| (mass, temperatures.as_slice()));
+    for (library, response) in &responses {
+        write_description(&mut out, library, target, response, coverage)?;
+    }
+    out.flush()?;
+
+    if interrupted.load(Ordering::SeqCst) {
+        std::process::exit(INTERRUPTED_EXIT_CODE);
+    }
+    Ok(())
+}
+
+/// Lines of stdin, read on a thread of their own
+///
+/// A blocking read cannot be cancelled, so it is kept off the runtime: Ctrl-C
+/// at the prompt then stops the loop at once, and the thread left waiting on
+/// stdin does not keep the process from exiting.
+fn stdin_lines() -> tokio::sync::mpsc::Receiver<std::io::Result<String>> {
+    let (sender, receiver) = tokio::sync::mpsc::channel(1);
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            if sender.blocking_send(line).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+/// Reads temperatures from stdin and prints the MACS of each, for --interactive
+///
+/// The data is loaded once and kept in memory; each line holds one
+/// temperature, with an optional unit suffix as in --temperatures. Status
+/// lines are shown while loading only. Errors in a line are reported and the
+/// loop goes on; `quit`, `exit` or the end of input stop it.
+async fn interactive(
+    args: &Args,
+    source: &dyn exfor_client::CrossSectionSource,
+    (target, mass): (&str, f64),
+    interrupted: &AtomicBool,
+    mut offline_db: Option<&mut offline_db::OfflineDb>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let library = &args.library[0];
+    let Some(response) = load_response(
+        args,
+        source,
+        library,
+        target,
+        interrupted,
+        offline_db.as_deref_mut(),
+    )
+    .await?
+    else {
+        return Ok(());
+    };
+    if let (Some(path), Some(db)) = (&args.offline_db, &offline_db)
+        && args.refresh_offline_db
+    {
+        db.save(path)?;
+        report::status(format!("Offline database written to {}", path.display()));
+    }
+    report::status(format!(
+        "{} {}({}) loaded, enter one temperature per line as in --temperatures, or quit",
+        library, target, args.reaction
+    ));
+
+    let prompt = std::io::stdin().is_terminal();
+    let mut out = open_output(args)?;
+    let mut lines = stdin_lines();
+    loop {
+        if prompt {
+            eprint!("kT> ");
+            std::io::stderr().flush()?;
+        }
+        let line = tokio::select! {
+            line = lines.recv() => line,
+            _ = tokio::signal::ctrl_c() => {
+                interrupted.store(true, Ordering::SeqCst);
+                None
+            }
+        };
+        let Some(line) = line.transpose()? else {
+            break;
+        };
+        let line = line.trim();
+        match line {
+            "" => continue,
+            "quit" | "exit" => break,
+            _ => {}
+        }
+        let temperature = match line.parse::<units::Temperature>() {
+            Ok(temperature) => temperature.to_kev(args.temperature_unit),
+            Err(e) => {
+                report::error(e);
+                continue;
+            }
+        };
+        report::set_quiet(true);
+        let results = compute_from_response(
+            args,
+            &response,
+            target,
+            mass,
+            &[temperature],
+            interrupted,
+            None,
+        );
+        report::set_quiet(args.quiet);
+        match results.map(|r| r.macs.into_iter().next()) {
+            Ok(Some(result)) => {
+                writeln!(
+                    out,
+                    "{} keV: {:.6} mb",
+                    result.temperature_kev, result.macs_mb
+                )?;
+                out.flush()?;
+            }
+            Ok(None) if interrupted.load(Ordering::SeqCst) => break,
+            // Only --skip-unsupported leaves a temperature without a result
+            Ok(None) => report::error(format!(
+                "no MACS at {} keV, the data do not cover its Maxwellian (--skip-unsupported)",
+                temperature
+            )),
+            Err(e) => report::error(e),
+        }
+    }
+    Ok(())
+}
+
+/// Round temperatures between two bounds, as a --temperatures list
+///
+/// Values of the 1-2-5 series are used, only powers of ten over more than
+/// four decades. If none falls in between, the bounds themselves are rounded
+/// inwards to three significant digits.
+fn round_temperatures(lowest: f64, highest: f64) -> Vec<String> {
+    let steps: &[u32] = if highest / lowest > 1e4 {
+        &[1]
+    } else {
+        &[1, 2, 5]
+    };
+    let mut temperatures = Vec::new();
+    for exponent in lowest.log10().floor() as i32..=highest.log10().floor() as i32 {
+        for &step in steps {
+            let temperature = f64::from(step) * 10f64.powi(exponent);
+            if (lowest..=highest).contains(&temperature) {
+                temperatures.push(match exponent {
+                    0.. => temperature.to_string(),
+                    _ => format!("{}e{}", step, exponent),
+                });
+            }
+        }
+    }
+    if temperatures.is_empty() {
+        let round = |value: f64, up: bool| {
+            let scale = 10f64.powi(value.log10().floor() as i32 - 2);
+            let digits = value / scale;
+            (if up { digits.ceil() } else { digits.floor() }) * scale
+        };
+        let (low, high) = (round(lowest, true), round(highest, false));
+        temperatures.push(format!("{:.3e}", low));
+        if high > low {
+            temperatures.push(format!("{:.3e}", high));
+        }
+    }
+    temperatures
+}
+
+/// Prints the temperatures each library's data supports
+///
+/// The energy grid is read as for the MACS (the --dataset-index dataset, or
+/// all of them with --merge-datasets) and [`macs::supported_temperature_range`]
+/// finds where at most --support-threshold of the Maxwellian weight lies
+/// outside it.
+async fn suggest_temperatures(
+    args: &Args,
+    source: &dyn exfor_client::CrossSectionSource,
+    (target, mass): (&str, f64),
+    interrupted: &AtomicBool,
+    mut offline_db: Option<&mut offline_db::OfflineDb>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut out = open_output(args)?;
+    for library in &args.library {
+        let Some(response) = load_response(
+            args,
+            source,
+            library,
+            target,
+            interrupted,
+            offline_db.as_deref_mut(),
+        )
+        .await?
+        else {
+            break;
+        };
+        let energies = if args.merge_datasets && response.datasets.len() > 1 {
+            merge::merge_datasets(&response.datasets)?.energies
+        } else {
+            let index = args.dataset_index.map_or(0, |i| i - 1);
+            response
+                .datasets
+                .get(index)
+                .ok_or_else(|| {
+                    format!("No dataset {} for {}({})", index + 1, target, args.reaction)
+                })?
+                .points_in_mev_barns()?
+                .0
+        };
+        let energies: Vec<f64> = energies
+            .iter()
+            .map(|&e| macs::to_lab_energy(e, mass, args.frame))
+            .collect();
+
+        writeln!(out, "{} {}({}):", library, target, args.reaction)?;
+        writeln!(
+            out,
+            "  Energy range: {:.3e} - {:.3e} MeV",
+            energies.first().unwrap_or(&0.0),
+            energies.last().unwrap_or(&0.0)
+        )?;
+        match macs::supported_temperature_range(
+            &energies,
+            mass,
+            args.weighting,
+            args.support_threshold,
+        ) {
+            Some((lowest, highest)) => {
+                writeln!(
+                    out,
+                    "  Supported kT: {:.3e} - {:.3e} keV (at most {}% of the Maxwellian weight outside the data)",
+                    lowest,
+                    highest,
+                    args.support_threshold * 100.0
+                )?;
+                writeln!(
+                    out,
+                    "  Suggested: --temperatures {}",
+                    round_temperatures(lowest, highest).join(",")
+                )?;
+            }
+            None => writeln!(
+                out,
+                "  No temperature is supported: the data is too narrow for the Maxwellian at any kT"
+            )?,
+        }
+    }
+    out.flush()?;
+
+    if let (Some(path), Some(db)) = (&args.offline_db, &offline_db)
+        && args.refresh_offline_db
+    {
+        db.save(path)?;
+        report::status(format!("Offline database written to {}", path.display()));
+    }
+    if interrupted.load(Ordering::SeqCst) {
+        std::process::exit(INTERRUPTED_EXIT_CODE);
+    }
+    Ok(())
+}
+
+/// MACS of two reactions and their ratio at one temperature
+#[derive(Debug, serde::Serialize)]
+struct RatioRow {
+    /// Temperature in keV
+    temperature_kev: f64,
+    /// MACS of the numerator reaction in millibarns, if available
+    numerator_mb: Option<f64>,
+    /// MACS of the denominator reaction in millibarns, if available
+    denominator_mb: Option<f64>,
+    /// Ratio of the two, if both are available and the denominator is not zero
+    ratio: Option<f64>,
+}
+
+/// Ratio of the MACS of two reactions in one library
+#[derive(Debug, serde::Serialize)]
+struct RatioTable {
+    /// Target nucleus in EXFOR notation
+    target: String,
+    /// Nuclear data library
+    library: String,
+    /// Reaction in the numerator
+    numerator: String,
+    /// Reaction in the denominator
+    denominator: String,
+    /// Values at each temperature
+    rows: Vec<RatioRow>,
+}
+
+/// Computes the MACS of both reactions of a --ratio in each library
+///
+/// A reaction missing from a library is reported and its ratio left
+/// unavailable instead of failing the run.
+async fn reaction_ratio(
+    args: &Args,
+    source: &dyn exfor_client::CrossSectionSource,
+    ratio: &ReactionRatio,
+    (target, mass): (&str, f64),
+    temperatures: &[f64],
+    interrupted: &AtomicBool,
+    mut offline_db: Option<&mut offline_db::OfflineDb>,
+) -> Result<Vec<RatioTable>, Box<dyn std::error::Error>> {
+    let reactions = [&ratio.numerator, &ratio.denominator];
+    let mut reaction_args = Vec::with_capacity(reactions.len());
+    for reaction in reactions {
+        let mut args = args.clone();
+        args.reaction = reaction.clone();
+        check_charged_options(&args)?;
+        reaction_args.push(args);
+    }
+
+    report::init(
+        args.quiet,
+        (reactions.len() * args.library.len() * temperatures.len()) as u64,
+    );
+    let mut tables = Vec::with_capacity(args.library.len());
+    for library in &args.library {
+        let mut macs: Vec<Option<macs::MacsTable>> = Vec::with_capacity(reactions.len());
+        for args in &reaction_args {
+            if interrupted.load(Ordering::SeqCst) {
+                break;
+            }
+            report::set_step(format!("{} ({})", library, args.reaction));
+            let table = compute_table(
+                args,
+                source,
+                library,
+                target,
+                mass,
+                temperatures,
+                interrupted,
+                offline_db.as_deref_mut(),
+                Some(&advance_progress_bar),
+            )
+            .await;
+            match table {
+                Ok((table, _)) => macs.push(Some(table)),
+                Err(e) => {
+                    report::warning(format!(
+                        "no ({}) MACS from {}: {}",
+                        args.reaction, library, e
+                    ));
+                    report::advance(temperatures.len() as u64);
+                    macs.push(None);
+                }
+            }
+        }
+        if interrupted.load(Ordering::SeqCst) && macs.len() < reactions.len() {
+            break;
+        }
+
+        let value_at = |table: &Option<macs::MacsTable>, temperature_kev: f64| {
+            table.as_ref().and_then(|table| {
+                table
+                    .results
+                    .iter()
+                    .find(|r| r.temperature_kev == temperature_kev)
+                    .map(|r| r.macs_mb)
+            })
+        };
+        let rows = temperatures
+            .iter()
+            .map(|&temperature_kev| {
+                let numerator_mb = value_at(&macs[0], temperature_kev);
+                let denominator_mb = value_at(&macs[1], temperature_kev);
+                RatioRow {
+                    temperature_kev,
+                    numerator_mb,
+                    denominator_mb,
+                    ratio: numerator_mb
+                        .zip(denominator_mb)
+                        .filter(|&(_, d)| d != 0.0)
+                        .map(|(n, d)| n / d),
+                }
+            })
+            .collect();
+        let library = macs
+            .iter()
+            .flatten()
+            .next()
+            .map_or_else(|| library.clone(), |table| table.library.clone());
+        tables.push(RatioTable {
+            target: target.to_string(),
+            library,
+            numerator: ratio.numerator.clone(),
+            denominator: ratio.denominator.clone(),
+            rows,
+        });
+    }
+    Ok(tables)
+}
+
+/// Writes the MACS ratios of each library in the selected output format
+fn write_ratio(
+    out: &mut dyn Write,
+    args: &Args,
+    tables: &[RatioTable],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let format_value = |value: Option<f64>| match value {
+        Some(v) => format!("{:>14.6}", v),
+        None => format!("{:>14}", "-"),
+    };
+    let csv_value = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or_default();
+
+    match args.output_format {
+        OutputFormat::Table => {
+            let color = use_color(args);
+            for table in tables {
+                writeln!(
+                    out,
+                    "\n=== MACS Ratio ({})/({}) for {} {} ===",
+                    table.numerator, table.denominator, table.library, table.target
+                )?;
+                let header = format!(
+                    "\nT(keV){:>14}{:>14}{:>14}",
+                    format!("({})", table.numerator),
+                    format!("({})", table.denominator),
+                    "Ratio"
+                );
+                writeln!(out, "{}", header)?;
+                writeln!(out, "{}", "-".repeat(header.len() - 1))?;
+                for row in &table.rows {
+                    let mut line = format!("{:6.1}", row.temperature_kev);
+                    line.push_str(&format_value(row.numerator_mb));
+                    line.push_str(&format_value(row.denominator_mb));
+                    line.push_str(&format_value(row.ratio));
+                    if macs::is_reference_temperature(row.temperature_kev) {
+                        line.push_str(REFERENCE_MARKER);
+                    }
+                    let style = row_style(row.ratio.is_some(), row.temperature_kev);
+                    writeln!(out, "{}", styled(line, style, color))?;
+                }
+            }
+        }
+        OutputFormat::Csv => {
+            writeln!(
+                out,
+                "library,temperature_kev,numerator_mb,denominator_mb,ratio"
+            )?;
+            for table in tables {
+                for row in &table.rows {
+                    writeln!(
+                        out,
+                        "{},{},{},{},{}",
+                        table.library,
+                        row.temperature_kev,
+                        csv_value(row.numerator_mb),
+                        csv_value(row.denominator_mb),
+                        csv_value(row.ratio)
+                    )?;
+                }
+            }
+        }
+        OutputFormat::Json => writeln!(out, "{}", serde_json::to_string_pretty(tables)?)?,
+        OutputFormat::Ndjson => {
+            for table in tables {
+                for row in &table.rows {
+                    let mut record = serde_json::to_value(row)?;
+                    record["target"] = serde_json::json!(table.target);
+                    record["library"] = serde_json::json!(table.library);
+                    record["numerator"] = serde_json::json!(table.numerator);
+                    record["denominator"] = serde_json::json!(table.denominator);
+                    writeln!(out, "{}", serde_json::to_string(&record)?)?;
+                }
+            }
+        }
+        OutputFormat::Reaclib => {
+            return Err("--output-format reaclib is not available with --ratio".into());
+        }
+    }
+    Ok(())
+}
+
+/// Opens the --output file, or standard output
+fn open_output(args: &Args) -> Result<Box<dyn Write>, String> {
+    Ok(match &args.output {
+        Some(path) => Box::new(std::io::BufWriter::new(create_output_file(path)?)),
+        None => Box::new(std::io::stdout().lock()),
+    })
+}
+
+/// One line of --output-format ndjson: a result with the table it belongs to
+#[derive(serde::Serialize)]
+struct NdjsonRecord<'a> {
+    target: &'a str,
+    reaction: &'a str,
+    library: &'a str,
+    #[serde(flatten)]
+    result: &'a macs::MacsResult,
+}
+
+/// Writes each result of a table as one JSON line and flushes them, so that
+/// consumers can read them before the run ends
+fn write_ndjson(
+    out: &mut dyn Write,
+    table: &macs::MacsTable,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for result in &table.results {
+        let record = NdjsonRecord {
+            target: &table.target,
+            reaction: &table.reaction,
+            library: &table.library,
+            result,
+        };
+        writeln!(out, "{}", serde_json::to_string(&record)?)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Creates the output file, including any missing parent directories
+fn create_output_file(path: &std::path::Path) -> Result<std::fs::File, String> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Cannot create directory {}: {}", parent.display(), e))?;
+    }
+    std::fs::File::create(path).map_err(|e| format!("Cannot write {}: {}", path.display(), e))
+}
+
+/// Lists the isotopes of an element to scan, with their mass numbers as atomic masses
+///
+/// Isotopes are taken from the offline snapshot when one is read, otherwise
+/// from the listing of `source`.
+async fn element_targets(
+    args: &Args,
+    source: &dyn exfor_client::CrossSectionSource,
+    symbol: &str,
+    offline_db: Option<&offline_db::OfflineDb>,
+) -> Result<Vec<(String, f64)>, Box<dyn std::error::Error>> {
+    let z = nuclide::z_for_symbol(symbol).ok_or_else(|| format!("Unknown element {}", symbol))?;
+    let symbol = nuclide::symbol_for_z(z).ok_or("Unknown element")?;
+
+    let mut isotopes = match offline_db.filter(|_| !args.refresh_offline_db) {
+        Some(db) => db
+            .targets(&args.reaction)
+            .into_iter()
+            .filter_map(|target| match nuclide::parse_target(&target)? {
+                (target_z, a) if target_z == z && a != 0 => Some((target, a)),
+                _ => None,
+            })
+            .collect(),
+        None => {
+            source
+                .isotopes(symbol, &args.reaction, &args.quantity)
+                .await?
+        }
+    };
+    isotopes.sort_by_key(|&(_, a)| a);
+
+    if isotopes.is_empty() {
+        return Err(format!("No isotope of {} found for ({})", symbol, args.reaction).into());
+    }
+    Ok(isotopes
+        .into_iter()
+        .map(|(target, a)| (target, a as f64))
+        .collect())
+}
+
+/// Lists the naturally occurring isotopes of an element for --natural, with
+/// their mass numbers as atomic masses
+fn natural_targets(symbol: &str) -> Result<Vec<(String, f64)>, String> {
+    let z = nuclide::z_for_symbol(symbol).ok_or_else(|| format!("Unknown element {}", symbol))?;
+    let abundances = nuclide::natural_abundances(z);
+    if abundances.is_empty() {
+        return Err(format!("{} has no natural isotopic composition", symbol));
+    }
+    abundances
+        .into_iter()
+        .map(|(a, _)| Ok((nuclide::format_target(z, a)?, a as f64)))
+        .collect()
+}
+
+/// Combines the isotope tables of each library into natural-element tables
+///
+/// Libraries lacking some isotopes are combined over the others with a
+/// warning giving the abundance left out.
+fn natural_tables(
+    symbol: &str,
+    target_results: &[(String, Vec<macs::MacsTable>)],
+) -> Result<Vec<macs::MacsTable>, String> {
+    let z = nuclide::z_for_symbol(symbol).ok_or_else(|| format!("Unknown element {}", symbol))?;
+    let mut libraries: Vec<&str> = Vec::new();
+    for table in target_results.iter().flat_map(|(_, tables)| tables) {
+        if !libraries.contains(&table.library.as_str()) {
+            libraries.push(&table.library);
+        }
+    }
+    let mut tables = Vec::with_capacity(libraries.len());
+    for library in libraries {
+        let isotopes: Vec<(u32, &macs::MacsTable)> = target_results
+            .iter()
+            .flat_map(|(_, tables)| tables)
+            .filter(|table| table.library == library)
+            .filter_map(|table| Some((nuclide::parse_target(&table.target)?.1, table)))
+            .collect();
+        let (table, covered) = natural::combine(z, &isotopes)?;
+        if covered < 1.0 - NATURAL_COVERAGE_TOLERANCE {
+            report::warning(format!(
+                "{} lacks isotopes making up {:.3}% of natural {}, the natural MACS averages the others",
+                library,
+                (1.0 - covered) * 100.0,
+                symbol
+            ));
+        }
+        tables.push(table);
+    }
+    Ok(tables)
+}
+
+/// Determines the EXFOR target string from either --target or --z/--a
+///
+/// When both forms are given they must describe the same nuclide. Natural
+/// elements are spelled "Sym-0" as EXFOR expects (e.g., "Mo-nat" gives "Mo-0").
+fn resolve_target(target: Option<&str>, z: Option<u32>, a: Option<u32>) -> Result<String, String> {
+    match (target, z.zip(a)) {
+        (Some(target), Some((z, a))) => match nuclide::parse_target(target) {
+            Some(parsed) if parsed == (z, a) => Ok(nuclide::exfor_target(target)),
+            _ => Err(format!(
+                "--target {} does not match --z {} --a {}",
+                target, z, a
+            )),
+        },
+        (Some(target), None) => Ok(nuclide::exfor_target(target)),
+        (None, Some((z, a))) => nuclide::format_target(z, a),
+        (None, None) => Err("Either --target or both --z and --a must be given".to_string()),
+    }
+}
+
+/// Atomic mass of the target: --mass, or the standard atomic weight of a
+/// natural element (see [`nuclide::natural_atomic_weight`])
+fn target_mass(args: &Args, target: &str) -> Result<f64, String> {
+    match (args.mass, nuclide::natural_atomic_weight(target)) {
+        (Some(mass), _) => Ok(mass),
+        (None, Some(weight)) => {
+            report::status(format!(
+                "Using the standard atomic weight {} of natural {} as the atomic mass",
+                weight, target
+            ));
+            Ok(weight)
+        }
+        (None, None) => Err("--mass is required".to_string()),
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = Args::parse();
+    exfor_client::init_client(args.contact.as_deref())?;
+    let input_files = if args.input.is_empty() {
+        None
+    } else {
+        // The files are pieces of one cross section
+        args.merge_datasets = true;
+        if args.library.is_empty() {
+            args.library = vec![input_files::INPUT_LIBRARY.to_string()];
+        }
+        Some(input_files::InputFiles::load(&args.input)?)
+    };
+
+    let cache_path = library_cache::default_path();
+    let mut libraries = match &cache_path {
+        Some(path) if !args.refresh_library_cache => library_cache::LibraryCache::load(path)
+            .unwrap_or_else(|e| {
+                report::warning(format!("{}, starting with an empty library cache", e));
+                library_cache::LibraryCache::default()
+            }),
+        _ => library_cache::LibraryCache::default(),
+    };
+    exfor_client::remember_libraries(libraries.names().map(str::to_string));
+    let refresh = args.refresh_library_cache;
+
+    let url_source = match &args.url {
+        Some(url) => {
+            if args.library.len() > 1 {
+                return Err(
+                    "--url downloads one dataset and conflicts with several --library values"
+                        .into(),
+                );
+            }
+            if args.library.is_empty() {
+                args.library = vec![exfor_client::URL_LIBRARY.to_string()];
+            }
+            Some(exfor_client::UrlSource {
+                url: exfor_client::dataset_url(url, args.allow_any_host)?,
+            })
+        }
+        None => None,
+    };
+    let result = match (&input_files, &url_source) {
+        (Some(files), _) => run(args, files).await,
+        (None, Some(source)) => run(args, source).await,
+        (None, None) => run(args, &exfor_client::ExforSource).await,
+    };
+    if let Some(path) = &cache_path
+        && (libraries.extend(exfor_client::known_libraries()) || refresh)
+        && let Err(e) = libraries.save(path)
+    {
+        report::status(format!("Library cache not updated: {}", e));
+    }
+    report::finish();
+    result
+}
+
+/// Runs the command selected on the command line
+///
+/// Cross sections missing from the offline database are fetched from `source`,
+/// through an in-memory cache so each dataset is downloaded once per run.
+async fn run(
+    mut args: Args,
+    source: &dyn exfor_client::CrossSectionSource,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(Command::Compare {
+        baseline,
+        candidate,
+        tolerance,
+    }) = &args.command
+    {
+        return compare::run(baseline, candidate, *tolerance);
+    }
+
+    if args.list_libraries {
+        let names = if args.target.is_some() || args.z.is_some() {
+            let target = resolve_target(args.target.as_deref(), args.z, args.a)?;
+            let names =
+                exfor_client::list_libraries(&target, &args.reaction, &args.quantity).await?;
+            report::status(format!(
+                "{} libraries tabulate {}({})",
+                names.len(),
+                target,
+                args.reaction
+            ));
+            names
+        } else {
+            let names = exfor_client::known_libraries();
+            if names.is_empty() {
+                report::status("The library cache is empty, give a --target to query the listing");
+            }
+            names
+        };
+        let mut out = open_output(&args)?;
+        for name in names {
+            writeln!(out, "{}", name)?;
+        }
+        out.flush()?;
+        return Ok(());
+    }
+
+    report::set_strict(args.strict);
+    if args.embed_data && args.output_format != OutputFormat::Json {
+        return Err("--embed-data requires --output-format json".into());
+    }
+    if args.loglog_epsilon.is_some()
+        && (args.ri_method != macs::RiMethod::LogLog || !args.quantities.contains(&Quantity::Ri))
+    {
+        return Err("--loglog-epsilon requires --ri-method log-log and ri in --quantities".into());
+    }
+    if args.eval_id.is_some() && args.library.len() > 1 {
+        return Err("--eval-id pins one evaluation and requires a single --library".into());
+    }
+    if args.cumulative.is_some() && args.library.len() > 1 {
+        return Err("--cumulative writes one curve and requires a single --library".into());
+    }
+    if args.dump_intervals.is_some() && args.library.len() > 1 {
+        return Err("--dump-intervals writes one integral and requires a single --library".into());
+    }
+    check_charged_options(&args)?;
+    if args.kadonis_convention {
+        apply_kadonis_convention(&mut args);
+    }
+
+    // Only --list-libraries can use the other quantities, none of them is a σ(E) to integrate
+    if args.quantity != exfor_client::DEFAULT_QUANTITY {
+        return Err(format!(
+            "--quantity {} is not a cross section and has no MACS, only --list-libraries accepts it",
+            args.quantity
+        )
+        .into());
+    }
+
+    let cache = exfor_client::CachedSource::new(source);
+    let source: &dyn exfor_client::CrossSectionSource = &cache;
+
+    // On Ctrl-C, stop computing and flush whatever results exist so far
+    let interrupted = Arc::new(AtomicBool::new(false));
+    {
+        let interrupted = Arc::clone(&interrupted);
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                interrupted.store(true, Ordering::SeqCst);
+            }
+        });
+    }
+
+    let requested: Vec<f64> = args
+        .temperatures
+        .iter()
+        .map(|t| t.to_kev(args.temperature_unit))
+        .collect();
+    if args.output_format == OutputFormat::Reaclib
+        && (args.single_temperature.is_some()
+            || args.ratio.is_some()
+            || args.normalize_to.is_some())
+    {
+        return Err("--output-format reaclib cannot be combined with --single-temperature, --ratio or --normalize-to".into());
+    }
+    let temperatures = match args.single_temperature {
+        _ if !args.quantities.contains(&Quantity::Macs) => Vec::new(),
+        _ if args.output_format == OutputFormat::Reaclib => {
+            report::status("REACLIB output: computing the MACS over T9 = 0.01 - 10");
+            reaclib::temperature_grid_kev()
+        }
+        Some(temp) => vec![temp.to_kev(args.temperature_unit)],
+        None if args.keep_duplicate_temperatures => requested,
+        None => {
+            let unique = unique_temperatures(&requested);
+            if unique.len() < requested.len() {
+                report::warning(format!(
+                    "removed {} duplicate temperature(s), computing {}",
+                    requested.len() - unique.len(),
+                    unique
+                        .iter()
+                        .map(f64::to_string)
+                        .collect::<Vec<_>>()
+                        .join(",")
+                ));
+            }
+            unique
+        }
+    };
+    let temperatures = match args.normalize_to {
+        Some(reference) if args.quantities.contains(&Quantity::Macs) => {
+            let reference = reference.to_kev(args.temperature_unit);
+            let mut temperatures = temperatures;
+            if !temperatures.contains(&reference) {
+                report::status(format!(
+                    "Adding the {} keV normalization reference to the temperatures",
+                    reference
+                ));
+                temperatures.push(reference);
+            }
+            temperatures
+        }
+        _ => temperatures,
+    };
+
+    if let Some(rate) = args.from_rate {
+        let table = from_rate_table(&args, rate, &temperatures)?;
+        report::check_strict()?;
+        let mut out = open_output(&args)?;
+        write_results(&mut out, &args, &table)?;
+        out.flush()?;
+        return Ok(());
+    }
+
+    if let Some(cross_section) = args.constant_sigma {
+        report::init(args.quiet, temperatures.len() as u64);
+        let table = constant_sigma_table(&args, cross_section, &temperatures, &interrupted)?;
+        report::finish();
+        report::check_strict()?;
+        let mut out = open_output(&args)?;
+        write_results(&mut out, &args, &table)?;
+        out.flush()?;
+        return Ok(());
+    }
+
+    let mut offline_db = match &args.offline_db {
+        Some(path) if args.refresh_offline_db && !path.exists() => {
+            Some(offline_db::OfflineDb::default())
+        }
+        Some(path) => Some(offline_db::OfflineDb::load(path)?),
+        None => None,
+    };
+
+    if args.interactive {
+        if args.library.len() > 1 {
+            return Err(
+                "--interactive explores one dataset and requires a single --library".into(),
+            );
+        }
+        let target = resolve_target(args.target.as_deref(), args.z, args.a)?;
+        let mass = target_mass(&args, &target)?;
+        nuclide::check_mass(&target, mass)?;
+        return interactive(
+            &args,
+            source,
+            (&target, mass),
+            &interrupted,
+            offline_db.as_mut(),
+        )
+        .await;
+    }
+
+    if args.suggest_temperatures {
+        let target = resolve_target(args.target.as_deref(), args.z, args.a)?;
+        let mass = target_mass(&args, &target)?;
+        return suggest_temperatures(
+            &args,
+            source,
+            (&target, mass),
+            &interrupted,
+            offline_db.as_mut(),
+        )
+        .await;
+    }
+
+    if args.describe {
+        let target = resolve_target(args.target.as_deref(), args.z, args.a)?;
+        return describe(&args, source, &target, &interrupted, offline_db.as_mut()).await;
+    }
+
+    if let Some(ratio) = &args.ratio {
+        let target = resolve_target(args.target.as_deref(), args.z, args.a)?;
+        let mass = target_mass(&args, &target)?;
+        nuclide::check_mass(&target, mass)?;
+        let tables = reaction_ratio(
+            &args,
+            source,
+            ratio,
+            (&target, mass),
+            &temperatures,
+            &interrupted,
+            offline_db.as_mut(),
+        )
+        .await?;
+
+        if let (Some(path), Some(db)) = (&args.offline_db, &offline_db)
+            && args.refresh_offline_db
+        {
+            db.save(path)?;
+            report::status(format!("Offline database written to {}", path.display()));
+        }
+        report::finish();
+        report::check_strict()?;
+        let mut out = open_output(&args)?;
+        write_ratio(&mut out, &args, &tables)?;
+        out.flush()?;
+        if interrupted.load(Ordering::SeqCst) {
+            std::process::exit(INTERRUPTED_EXIT_CODE);
+        }
+        return Ok(());
+    }
+
+    let targets = match &args.element {
+        Some(symbol) if args.natural => natural_targets(symbol)?,
+        Some(symbol) => element_targets(&args, source, symbol, offline_db.as_ref()).await?,
+        None => {
+            let target = resolve_target(args.target.as_deref(), args.z, args.a)?;
+            let mass = target_mass(&args, &target)?;
+            nuclide::check_mass(&target, mass)?;
+            vec![(target, mass)]
+        }
+    };
+
+    report::init(
+        args.quiet,
+        (targets.len() * args.library.len() * temperatures.len()) as u64,
+    );
+    if args.element.is_some() {
+        let names: Vec<&str> = targets.iter().map(|(target, _)| target.as_str()).collect();
+        report::status(format!(
+            "Found {} isotopes: {}",
+            names.len(),
+            names.join(", ")
+        ));
+    }
+    // NDJSON lines are written as soon as each table is computed
+    let mut ndjson_out = match args.output_format {
+        OutputFormat::Ndjson => Some(open_output(&args)?),
+        _ => None,
+    };
+    let mut target_results: Vec<(String, Vec<macs::MacsTable>)> = Vec::new();
+    let mut curves: Vec<(String, grid_export::Curve)> = Vec::new();
+    for (target, mass) in &targets {
+        let mut library_results = Vec::with_capacity(args.library.len());
+        for library in &args.library {
+            if interrupted.load(Ordering::SeqCst) {
+                break;
+            }
+            if args.element.is_some() {
+                report::set_step(format!("{} {}", target, library));
+            } else {
+                report::set_step(library.clone());
+            }
+            let table = compute_table(
+                &args,
+                source,
+                library,
+                target,
+                *mass,
+                &temperatures,
+                &interrupted,
+                offline_db.as_mut(),
+                Some(&advance_progress_bar),
+            )
+            .await;
+            match table {
+                Ok((table, curve)) => {
+                    if let Some(out) = ndjson_out.as_mut() {
+                        report::check_strict()?;
+                        write_ndjson(out, &table)?;
+                    }
+                    if let Some(curve) = curve {
+                        curves.push((table.library.clone(), curve));
+                    }
+                    library_results.push(table);
+                }
+                // In an element scan, isotopes missing from a library are skipped
+                Err(e) if args.element.is_some() => {
+                    report::status(format!("Skipping {} in {}: {}", target, library, e));
+                    report::advance(temperatures.len() as u64);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        if !library_results.is_empty() {
+            target_results.push((target.clone(), library_results));
+        }
+        // Datasets of one isotope are not needed for the next
+        cache.clear();
+    }
+
+    if let Some(symbol) = args.element.as_deref().filter(|_| args.natural)
+        && !target_results.is_empty()
+    {
+        let tables = natural_tables(symbol, &target_results)?;
+        if let Some(out) = ndjson_out.as_mut() {
+            report::check_strict()?;
+            for table in &tables {
+                write_ndjson(out, table)?;
+            }
+        }
+        let target = tables[0].target.clone();
+        target_results.push((target, tables));
+    }
+
+    if let (Some(path), Some(db)) = (&args.offline_db, &offline_db)
+        && args.refresh_offline_db
+    {
+        db.save(path)?;
+        report::status(format!("Offline database written to {}", path.display()));
+    }
+
+    report::check_strict()?;
+    if let Some(path) = &args.export_grid {
+        let mut file = std::io::BufWriter::new(create_output_file(path)?);
+        grid_export::write_common_grid(&mut file, &curves)?;
+        file.flush()?;
+        report::status(format!(
+            "Common-grid cross sections written to {}",
+            path.display()
+        ));
+    }
+
+    if interrupted.load(Ordering::SeqCst) {
+        let computed: usize = target_results
+            .iter()
+            .flat_map(|(_, tables)| tables)
+            .map(|t| t.results.len())
+            .sum();
+        if computed == 0 {
+            report::warning("Interrupted before any result was computed");
+            std::process::exit(INTERRUPTED_EXIT_CODE);
+        }
+        report::warning(format!(
+            "Interrupted, writing the {} results computed so far",
+            computed
+        ));
+    }
+
+    report::finish();
+    if ndjson_out.is_none() {
+        let mut out = open_output(&args)?;
+        if args.element.is_some() && args.output_format == OutputFormat::Json {
+            // One JSON document listing the tables of all isotopes and libraries
+            let tables: Vec<&macs::MacsTable> = target_results
+                .iter()
+                .flat_map(|(_, tables)| tables)
+                .collect();
+            writeln!(out, "{}", serde_json::to_string_pretty(&tables)?)?;
+        } else {
+            for (target, library_results) in &target_results {
+                match library_results.as_slice() {
+                    [table] => write_results(&mut out, &args, table)?,
+                    _ => write_comparison(&mut out, &args, target, &temperatures, library_results)?,
+                }
+            }
+        }
+        out.flush()?;
+    }
+
+    if interrupted.load(Ordering::SeqCst) {
+        std::process::exit(INTERRUPTED_EXIT_CODE);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses a command line given without the program name
+    fn args(line: &str) -> Args {
+        Args::try_parse_from(std::iter::once("macs-rs").chain(line.split_whitespace())).unwrap()
+    }
+
+    /// A response with one dataset σ(E) = 1 + E b, E in MeV, on a log grid
+    fn response() -> exfor_client::CrossSectionResponse {
+        let points: Vec<String> = (0..=900)
+            .map(|i| {
+                let energy = 10f64.powf(-8.0 + i as f64 / 100.0);
+                format!(r#"{{"E": {}, "Sig": {}}}"#, energy, 1.0 + energy)
+            })
+            .collect();
+        serde_json::from_str(&format!(
+            r#"{{"datasets": [{{"id": "1", "LIBRARY": "MOCK", "TARGET": "Mo-94", "MT": 102,
+                "COLUMNS": ["E(MeV)", "Sig(b)"], "pts": [{}]}}]}}"#,
+            points.join(", ")
+        ))
+        .unwrap()
+    }
+
+    /// Source serving fixture responses by target, recording each request
+    ///
+    /// A fixture keyed "target quantity" (e.g. "Mo-94 DA") only serves that
+    /// quantity. A request without fixture fails as a library without the
+    /// section would.
+    struct MockSource {
+        responses: Vec<(&'static str, exfor_client::CrossSectionResponse)>,
+        requests: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl MockSource {
+        fn new(responses: Vec<(&'static str, exfor_client::CrossSectionResponse)>) -> Self {
+            MockSource {
+                responses,
+                requests: std::sync::Mutex::new(Vec::new()),
+            }
+        }
+
+        fn requests(&self) -> Vec<String> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    impl exfor_client::CrossSectionSource for MockSource {
+        fn fetch<'a>(
+            &'a self,
+            request: exfor_client::FetchRequest<'a>,
+        ) -> exfor_client::FetchFuture<'a> {
+            Box::pin(async move {
+                self.requests
+                    .lock()
+                    .unwrap()
+                    .push(format!("{} {}", request.target, request.lib_name));
+                let keyed = format!("{} {}", request.target, request.quantity);
+                let (_, response) = self
+                    .responses
+                    .iter()
+                    .find(|(key, _)| *key == request.target || *key == keyed)
+                    .ok_or_else(|| {
+                        exfor_client::NoSectionError(format!("No data for {}", request.target))
+                    })?;
+                Ok(response.clone())
+            })
+        }
+
+        /// Streams the fixture through the parser used for downloads
+        fn stream<'a>(
+            &'a self,
+            request: exfor_client::FetchRequest<'a>,
+            sink: &'a mut dyn exfor_client::PointSink,
+        ) -> exfor_client::StreamFuture<'a> {
+            Box::pin(async move {
+                let json = serde_json::to_vec(&self.fetch(request).await?)?;
+                Ok(exfor_client::stream_points(json.as_slice(), sink)?)
+            })
+        }
+    }
+
+    /// MACS at each temperature computed from a response
+    fn macs_values(args: &Args, response: &exfor_client::CrossSectionResponse) -> Vec<f64> {
+        compute_from_response(
+            args,
+            response,
+            "Mo-94",
+            94.0,
+            &[5.0, 30.0],
+            &AtomicBool::new(false),
+            None,
+        )
+        .unwrap()
+        .macs
+        .iter()
+        .map(|result| result.macs_mb)
+        .collect()
+    }
+
+    #[test]
+    fn macs_scales_linearly_with_renorm() {
+        let base = macs_values(&args("-t Mo-94 -l MOCK"), &response());
+        for factor in [0.5, 1.02, 3.0] {
+            let scaled = macs_values(
+                &args(&format!("-t Mo-94 -l MOCK --renorm {}", factor)),
+                &response(),
+            );
+            for (scaled, base) in scaled.iter().zip(&base) {
+                assert!(
+                    (scaled / base - factor).abs() < 1e-12,
+                    "{} vs {}",
+                    scaled,
+                    base
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn renorm_must_be_positive() {
+        assert!(parse_renorm("0").is_err());
+        assert!(parse_renorm("-1").is_err());
+        assert!(parse_renorm("inf").is_err());
+        assert_eq!(parse_renorm("1.05"), Ok(1.05));
+    }
+
+    /// A path in the temporary directory unique to this test run
+    fn temp_path(extension: &str) -> std::path::PathBuf {
+        static COUNT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        std::env::temp_dir().join(format!(
+            "macs-rs-test-{}-{}.{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed),
+            extension
+        ))
+    }
+
+    /// Lines of the CSV that `run` writes for a command line
+    async fn run_csv(line: &str) -> Vec<String> {
+        let source = MockSource::new(vec![("Mo-94", response())]);
+        let path = temp_path("csv");
+        let line = format!("{} --output-format csv -o {}", line, path.display());
+        run(args(&line), &source).await.unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        written.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn duplicate_temperatures_keep_their_first_occurrence() {
+        assert_eq!(
+            unique_temperatures(&[30.0, 8.0, 30.0, 30.0, 25.0, 8.0]),
+            vec![30.0, 8.0, 25.0]
+        );
+    }
+
+    #[tokio::test]
+    async fn duplicate_temperatures_are_computed_once() {
+        let lines = run_csv("-t Mo-94 -m 94 -l MOCK -T 30,30keV,30").await;
+        assert_eq!(lines.len(), 2, "{:?}", lines);
+
+        let kept =
+            run_csv("-t Mo-94 -m 94 -l MOCK -T 30,30,30 --keep-duplicate-temperatures").await;
+        assert_eq!(kept.len(), 4, "{:?}", kept);
+        assert_eq!(kept[1], kept[3]);
+    }
+
+    #[tokio::test]
+    async fn table_is_computed_from_the_injected_source() {
+        let source = MockSource::new(vec![("Mo-94", response())]);
+        let args = args("-t Mo-94 -l MOCK");
+        let (table, _) = compute_table(
+            &args,
+            &source,
+            "MOCK",
+            "Mo-94",
+            94.0,
+            &[5.0, 30.0],
+            &AtomicBool::new(false),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(source.requests(), vec!["Mo-94 MOCK"]);
+        assert_eq!((table.target.as_str(), table.atomic_mass), ("Mo-94", 94.0));
+        let macs: Vec<f64> = table.results.iter().map(|r| r.macs_mb).collect();
+        assert_eq!(macs, macs_values(&args, &response()));
+    }
+
+    #[tokio::test]
+    async fn streaming_through_a_source_matches_the_full_response() {
+        let source = MockSource::new(vec![("Mo-94", response())]);
+        let streamed = compute_streaming(
+            &args("-t Mo-94 -l MOCK --stream"),
+            &source,
+            "MOCK",
+            "Mo-94",
+            94.0,
+            &[5.0, 30.0],
+            None,
+        )
+        .await
+        .unwrap();
+        let full = macs_values(&args("-t Mo-94 -l MOCK"), &response());
+        for (streamed, full) in streamed.iter().zip(&full) {
+            assert!((streamed.macs_mb / full - 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[tokio::test]
+    async fn run_writes_the_results_of_the_source() {
+        let source = MockSource::new(vec![("Mo-94", response())]);
+        let path = temp_path("csv");
+        let line = format!(
+            "-t Mo-94 -m 94 -l MOCK -T 5,30 --output-format csv -o {}",
+            path.display()
+        );
+        run(args(&line), &source).await.unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(source.requests(), vec!["Mo-94 MOCK"]);
+        assert_eq!(written.lines().count(), 3, "{}", written);
+    }
+
+    #[tokio::test]
+    async fn missing_data_is_reported_by_the_source() {
+        let source = MockSource::new(Vec::new());
+        let error = run(args("-t Mo-94 -m 94 -l MOCK -T 30"), &source)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("No data for Mo-94"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn natural_target_is_queried_as_element_zero() {
+        let natural = serde_json::to_string(&response())
+            .unwrap()
+            .replace("Mo-94", "Mo-0");
+        let source = MockSource::new(vec![("Mo-0", serde_json::from_str(&natural).unwrap())]);
+        let path = temp_path("csv");
+        let line = format!(
+            "-t Mo-nat -l MOCK -T 30 --output-format csv -o {}",
+            path.display()
+        );
+        run(args(&line), &source).await.unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(source.requests(), vec!["Mo-0 MOCK"]);
+
+        // The reduced mass is taken at the standard atomic weight of Mo
+        let args = args("-t Mo-nat -l MOCK");
+        assert_eq!(target_mass(&args, "Mo-0"), Ok(95.95));
+        let expected = compute_from_response(
+            &args,
+            &source.responses[0].1,
+            "Mo-0",
+            95.95,
+            &[30.0],
+            &AtomicBool::new(false),
+            None,
+        )
+        .unwrap()
+        .macs[0]
+            .macs_mb;
+        let macs: f64 = written.lines().nth(1).unwrap()["30,".len()..]
+            .parse()
+            .unwrap();
+        assert!((macs / expected - 1.0).abs() < 1e-12, "{}", written);
+    }
+
+    #[test]
+    fn natural_target_spellings_resolve_to_element_zero() {
+        for target in ["Mo-nat", "Mo-NAT", "Mo-0"] {
+            assert_eq!(resolve_target(Some(target), None, None).unwrap(), "Mo-0");
+        }
+        assert_eq!(resolve_target(None, Some(42), Some(0)).unwrap(), "Mo-0");
+        assert_eq!(
+            target_mass(&args("-t Mo-nat -l MOCK -m 96"), "Mo-0"),
+            Ok(96.0)
+        );
+        assert!(target_mass(&args("-t Mo-94 -l MOCK"), "Mo-94").is_err());
+    }
+
+    /// The fixture response with a second dataset of twice the cross section
+    fn two_dataset_response() -> exfor_client::CrossSectionResponse {
+        let mut response = response();
+        let mut doubled = response.datasets[0].clone();
+        doubled.id = "2".to_string();
+        for point in &mut doubled.points {
+            point.cross_section *= 2.0;
+        }
+        response.datasets.push(doubled);
+        response
+    }
+
+    #[test]
+    fn dataset_index_picks_a_dataset_of_the_response() {
+        let first = macs_values(&args("-t Mo-94 -l MOCK"), &response());
+        let response = two_dataset_response();
+        assert_eq!(macs_values(&args("-t Mo-94 -l MOCK"), &response), first);
+        assert_eq!(
+            macs_values(&args("-t Mo-94 -l MOCK --dataset-index 1"), &response),
+            first
+        );
+        let second = macs_values(&args("-t Mo-94 -l MOCK --dataset-index 2"), &response);
+        for (second, first) in second.iter().zip(&first) {
+            assert!((second / first - 2.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn dataset_index_must_exist() {
+        let Err(error) = compute_from_response(
+            &args("-t Mo-94 -l MOCK --dataset-index 3"),
+            &two_dataset_response(),
+            "Mo-94",
+            94.0,
+            &[30.0],
+            &AtomicBool::new(false),
+            None,
+        ) else {
+            panic!("dataset 3 of 2 was accepted");
+        };
+        assert!(error.to_string().contains("holds 2 datasets"), "{}", error);
+        assert!(parse_dataset_index("0").is_err());
+    }
+
+    #[test]
+    fn temperature_list_mixes_units_and_bare_numbers() {
+        let args = args("-t Mo-94 -l MOCK -T 30keV,1GK,3e8 --temperature-unit k");
+        let temperatures: Vec<f64> = args
+            .temperatures
+            .iter()
+            .map(|t| t.to_kev(args.temperature_unit))
+            .collect();
+        assert_eq!(temperatures[0], 30.0);
+        assert_eq!(temperatures[1], units::gk_to_kev(1.0));
+        assert_eq!(temperatures[2], units::kelvin_to_kev(3e8));
+        assert!(
+            Args::try_parse_from(["macs-rs", "-t", "Mo-94", "-l", "MOCK", "-T", "30,1MK"]).is_err()
+        );
+    }
+
+    #[test]
+    fn constant_sigma_matches_the_analytic_macs() {
+        for (weighting, factor) in [
+            ("flux", 2.0 / std::f64::consts::PI.sqrt()),
+            ("number-density", 1.0),
+        ] {
+            let args = args(&format!(
+                "--constant-sigma 0.5 -T 1,30,100 --weighting {}",
+                weighting
+            ));
+            let table =
+                constant_sigma_table(&args, 0.5, &[1.0, 30.0, 100.0], &AtomicBool::new(false))
+                    .unwrap();
+            assert_eq!(table.target, "X");
+            for result in &table.results {
+                assert!(
+                    (result.macs_mb / (500.0 * factor) - 1.0).abs() < 1e-4,
+                    "{} mb at {} keV with {} weighting",
+                    result.macs_mb,
+                    result.temperature_kev,
+                    weighting
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn constant_sigma_does_not_depend_on_the_mass() {
+        let macs = |line: &str| {
+            constant_sigma_table(&args(line), 2.0, &[30.0], &AtomicBool::new(false))
+                .unwrap()
+                .results[0]
+                .macs_mb
+        };
+        let light = macs("--constant-sigma 2 -m 1");
+        let heavy = macs("--constant-sigma 2 -t Mo-94 -m 94");
+        assert!((heavy / light - 1.0).abs() < 1e-4, "{} vs {}", heavy, light);
+        assert!(parse_constant_sigma("0").is_err());
+    }
+
+    /// Isotropic angular distributions integrating to σ = 1 + E b, the
+    /// cross section of [`response`]
+    fn differential_response() -> exfor_client::CrossSectionResponse {
+        let points: Vec<String> = (0..=900)
+            .flat_map(|i| {
+                let energy = 10f64.powf(-8.0 + i as f64 / 100.0);
+                let per_steradian = (1.0 + energy) / (4.0 * std::f64::consts::PI);
+                [-1.0, 0.0, 1.0].map(|cosine| {
+                    format!(
+                        r#"{{"E": {}, "Cos": {}, "Sig": {}}}"#,
+                        energy, cosine, per_steradian
+                    )
+                })
+            })
+            .collect();
+        serde_json::from_str(&format!(
+            r#"{{"datasets": [{{"id": "1", "LIBRARY": "MOCK", "TARGET": "Mo-94", "MT": 2,
+                "COLUMNS": ["E(MeV)", "Cos", "Sig"], "pts": [{}]}}]}}"#,
+            points.join(", ")
+        ))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn missing_cross_section_falls_back_to_differential_data() {
+        let source = MockSource::new(vec![("Mo-94 DA", differential_response())]);
+        let args = args("-t Mo-94 -l MOCK");
+        let (table, _) = compute_table(
+            &args,
+            &source,
+            "MOCK",
+            "Mo-94",
+            94.0,
+            &[5.0, 30.0],
+            &AtomicBool::new(false),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(source.requests(), vec!["Mo-94 MOCK", "Mo-94 MOCK"]);
+        assert!(table.approximate);
+        let expected = macs_values(&args, &response());
+        for (result, expected) in table.results.iter().zip(&expected) {
+            assert!((result.macs_mb / expected - 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[tokio::test]
+    async fn tabulated_cross_section_is_not_approximate() {
+        let source = MockSource::new(vec![
+            ("Mo-94 SIG", response()),
+            ("Mo-94 DA", differential_response()),
+        ]);
+        let (table, _) = compute_table(
+            &args("-t Mo-94 -l MOCK"),
+            &source,
+            "MOCK",
+            "Mo-94",
+            94.0,
+            &[30.0],
+            &AtomicBool::new(false),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(source.requests().len(), 1);
+        assert!(!table.approximate);
+    }
+
+    #[tokio::test]
+    async fn reference_temperature_normalizes_to_exactly_one() {
+        let source = MockSource::new(vec![("Mo-94", response())]);
+        let (table, _) = compute_table(
+            &args("-t Mo-94 -l MOCK --normalize-to 30"),
+            &source,
+            "MOCK",
+            "Mo-94",
+            94.0,
+            &[5.0, 30.0, 90.0],
+            &AtomicBool::new(false),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        let reference = table.results[1].macs_mb;
+        assert_eq!(table.results[1].normalized_macs, Some(1.0));
+        for result in &table.results {
+            assert_eq!(result.normalized_macs, Some(result.macs_mb / reference));
+        }
+    }
+
+    #[tokio::test]
+    async fn missing_reference_temperature_is_computed_additionally() {
+        let lines = run_csv("-t Mo-94 -m 94 -l MOCK -T 5,90 --normalize-to 30").await;
+        assert_eq!(lines.len(), 4, "{:?}", lines);
+        let column = lines[0]
+            .split(',')
+            .position(|name| name == "normalized_macs")
+            .unwrap();
+        let normalized: Vec<&str> = lines[1..]
+            .iter()
+            .map(|line| line.split(',').nth(column).unwrap())
+            .collect();
+        assert_eq!(
+            normalized.iter().filter(|value| **value == "1").count(),
+            1,
+            "{:?}",
+            lines
+        );
+    }
+
+    #[test]
+    fn background_accepts_a_constant_or_one_over_v() {
+        assert_eq!(parse_background("2"), Ok(macs::Background::Constant(2.0)));
+        assert_eq!(
+            parse_background("1/v:0.5"),
+            Ok(macs::Background::InverseVelocity(0.5))
+        );
+        assert!(parse_background("-1").is_err());
+        assert!(parse_background("exp:1").is_err());
+    }
+
+    #[test]
+    fn constant_background_raises_the_macs_by_its_own_macs() {
+        let compute = |line: &str| {
+            compute_from_response(
+                &args(line),
+                &response(),
+                "Mo-94",
+                94.0,
+                &[5.0, 30.0],
+                &AtomicBool::new(false),
+                None,
+            )
+            .unwrap()
+            .macs
+        };
+        let base = compute("-t Mo-94 -l MOCK");
+        let raised = compute("-t Mo-94 -l MOCK --background 2");
+        let expected = macs::constant_cross_section_macs(2.0, macs::MaxwellianWeighting::Flux);
+        for (raised, base) in raised.iter().zip(&base) {
+            assert_eq!(base.background_mb, None);
+            let background = raised.background_mb.unwrap();
+            assert!((raised.macs_mb - base.macs_mb - background).abs() < 1e-9 * raised.macs_mb);
+            assert!(
+                (background / expected - 1.0).abs() < 1e-3,
+                "{} vs {}",
+                background,
+                expected
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn natural_mode_combines_the_mocked_isotopes() {
+        let mut doubled = response();
+        for point in &mut doubled.datasets[0].points {
+            point.cross_section *= 2.0;
+        }
+        let source = MockSource::new(vec![("Cu-63", response()), ("Cu-65", doubled)]);
+        let path = temp_path("csv");
+        let line = format!(
+            "--element Cu --natural -l MOCK -T 30 --output-format csv -o {}",
+            path.display()
+        );
+        run(args(&line), &source).await.unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(source.requests(), ["Cu-63 MOCK", "Cu-65 MOCK"]);
+        let macs: Vec<f64> = written
+            .lines()
+            .filter(|line| line.starts_with("30,"))
+            .map(|line| line[3..].parse().unwrap())
+            .collect();
+        let [cu63, cu65, natural] = macs[..] else {
+            panic!("{}", written)
+        };
+        assert!((cu65 / cu63 - 2.0).abs() < 1e-4);
+        let expected = 0.6915 * cu63 + 0.3085 * cu65;
+        assert!((natural / expected - 1.0).abs() < 1e-12, "{}", written);
+    }
+
+    #[test]
+    fn neutron_only_options_are_rejected_for_charged_projectiles() {
+        for option in [
+            "--extrapolate",
+            "--half-extend-edges",
+            "--error-estimate",
+            "--raw-integral",
+            "--stream",
+            "--cross-check",
+            "--skip-unsupported",
+            "--validate-mc 100",
+            "--resonance 1e-3:1e-4",
+            "--max-points 100 --max-points-tolerance 1e-3",
+        ] {
+            let neutron = args(&format!("-t Mo-94 -l MOCK {}", option));
+            assert!(check_charged_options(&neutron).is_ok(), "{}", option);
+            let proton = args(&format!("-t Mo-94 -l MOCK -r p,g {}", option));
+            let error = check_charged_options(&proton).unwrap_err();
+            assert!(
+                error.contains("only supported for neutron-induced"),
+                "{}",
+                error
+            );
+        }
+        assert!(check_charged_options(&args("-t Mo-94 -l MOCK -r p,g --max-points 100")).is_ok());
+    }
+
+    #[test]
+    fn verbose_run_survives_a_vanishing_integrand() {
+        let zero: exfor_client::CrossSectionResponse = serde_json::from_str(
+            r#"{"datasets": [{"id": "1", "COLUMNS": ["E(MeV)", "Sig(b)"],
+                "pts": [{"E": 0.01, "Sig": 0.0}, {"E": 0.1, "Sig": 0.0}]}]}"#,
+        )
+        .unwrap();
+        for line in ["-t Mo-94 -l MOCK", "-t Mo-94 -l MOCK --verbose"] {
+            assert_eq!(macs_values(&args(line), &zero), vec![0.0, 0.0], "{}", line);
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! Stitching of several cross section datasets into one grid
+//!
+//! Evaluations sometimes split a reaction into separate datasets, e.g. the
+//! resolved and unresolved resonance regions. Merging sorts them by energy
+//! and concatenates their points, keeping each dataset's interpolation law
+//! for its own energy range.
+
+use crate::exfor_client::CrossSectionDataset;
+use crate::interpolation::{self, PiecewiseInterpolation};
+
+/// Energy range of the merged grid taken from one source dataset
+#[derive(Debug)]
+pub struct MergedRange {
+    /// Index of the source dataset in the input slice
+    pub source: usize,
+    /// First energy taken from the dataset in MeV
+    pub start: f64,
+    /// Last energy taken from the dataset in MeV
+    pub end: f64,
+    /// Interpolation law declared by the dataset
+    pub interpolation: String,
+}
+
+/// Cross section grid stitched from several datasets
+pub struct MergedDataset {
+    /// Energies in MeV, strictly increasing
+    pub energies: Vec<f64>,
+    /// Cross sections in barns
+    pub cross_sections: Vec<f64>,
+    /// Per-range interpolation law of the merged grid
+    pub law: PiecewiseInterpolation,
+    /// Which dataset each part of the grid came from
+    pub ranges: Vec<MergedRange>,
+}
+
+/// Merges datasets covering different energy ranges into one grid
+///
+/// Datasets are ordered by their lowest energy, after conversion to MeV so
+/// that datasets declaring different units sort correctly. Where two datasets overlap
+/// the one starting at lower energy wins; points of the next dataset are used
+/// only above the last energy already covered. Each dataset's declared
+/// interpolation law applies within its own range, and the interval joining
+/// two datasets is interpolated linearly. Points are converted to MeV and barns
+/// from the units declared in each dataset's column headers.
+///
+/// # Returns
+/// * `Ok(merged)` - The stitched grid
+/// * `Err(msg)` - If no dataset contains any point or a unit is not recognized
+pub fn merge_datasets(datasets: &[CrossSectionDataset]) -> Result<MergedDataset, String> {
+    let mut converted = Vec::new();
+    for (source, dataset) in datasets.iter().enumerate() {
+        if !dataset.points.is_empty() {
+            converted.push((source, dataset.points_in_mev_barns()?));
+        }
+    }
+    converted.sort_by(|(_, (a, _)), (_, (b, _))| a[0].total_cmp(&b[0]));
+
+    if converted.is_empty() {
+        return Err("No dataset contains any point".to_string());
+    }
+
+    let mut merged = MergedDataset {
+        energies: Vec::new(),
+        cross_sections: Vec::new(),
+        law: PiecewiseInterpolation::new(),
+        ranges: Vec::new(),
+    };
+
+    for (source, (energies, cross_sections)) in converted {
+        let dataset = &datasets[source];
+        let covered = merged.energies.last().copied().unwrap_or(f64::NEG_INFINITY);
+        let points: Vec<(f64, f64)> = energies
+            .into_iter()
+            .zip(cross_sections)
+            .filter(|&(energy, _)| energy > covered)
+            .collect();
+        let (Some(&(start, _)), Some(&(end, _))) = (points.first(), points.last()) else {
+            continue;
+        };
+
+        let law = interpolation::from_name(&dataset.default_interpolation)
+            .unwrap_or_else(|| Box::new(interpolation::LinLin));
+        merged.law.push_range(start, end, law);
+        merged.ranges.push(MergedRange {
+            source,
+            start,
+            end,
+            interpolation: dataset.default_interpolation.clone(),
+        });
+        merged
+            .energies
+            .extend(points.iter().map(|&(energy, _)| energy));
+        merged
+            .cross_sections
+            .extend(points.iter().map(|&(_, cross_section)| cross_section));
+    }
+
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpolation::Interpolation;
+
+    fn dataset(interpolation: &str, points: &[(f64, f64)]) -> CrossSectionDataset {
+        let pts: Vec<String> = points
+            .iter()
+            .map(|(e, sig)| format!(r#"{{"E": {}, "Sig": {}}}"#, e, sig))
+            .collect();
+        serde_json::from_str(&format!(
+            r#"{{"id": "{}", "COLUMNS": ["E(MeV)", "Sig(b)"], "defaultInterpolation": "{}", "pts": [{}]}}"#,
+            interpolation,
+            interpolation,
+            pts.join(", ")
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn merged_ranges_keep_their_own_law() {
+        // Given in reverse order, overlapping at 0.8 MeV
+        let high = dataset("Lin-Lin", &[(0.8, 9.0), (1.0, 4.0), (2.0, 2.0)]);
+        let low = dataset("Log-Log", &[(0.1, 10.0), (0.4, 5.0)]);
+        let merged = merge_datasets(&[high, low]).unwrap();
+
+        assert_eq!(merged.energies, vec![0.1, 0.4, 0.8, 1.0, 2.0]);
+        assert_eq!(merged.cross_sections, vec![10.0, 5.0, 9.0, 4.0, 2.0]);
+        assert_eq!(merged.ranges.len(), 2);
+        assert_eq!((merged.ranges[0].source, merged.ranges[0].end), (1, 0.4));
+        assert_eq!((merged.ranges[1].source, merged.ranges[1].start), (0, 0.8));
+
+        let law = &merged.law;
+        // Log-log in the low range: σ ∝ E^-1/2, √2 lower at twice the energy
+        let log_log = law.interpolate(0.2, 0.1, 10.0, 0.4, 5.0);
+        assert!((log_log - 10.0 / 2f64.sqrt()).abs() < 1e-12);
+        // Lin-lin in the high range
+        assert!((law.interpolate(1.5, 1.0, 4.0, 2.0, 2.0) - 3.0).abs() < 1e-12);
+        // Linear across the junction between the two datasets
+        assert!((law.interpolate(0.6, 0.4, 5.0, 0.8, 9.0) - 7.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn overlapping_points_of_a_later_dataset_are_dropped() {
+        let low = dataset("Lin-Lin", &[(0.1, 1.0), (1.0, 2.0)]);
+        let inside = dataset("Log-Log", &[(0.2, 5.0), (0.5, 5.0)]);
+        let merged = merge_datasets(&[low, inside]).unwrap();
+
+        assert_eq!(merged.energies, vec![0.1, 1.0]);
+        assert_eq!(merged.ranges.len(), 1);
+    }
+
+    #[test]
+    fn datasets_in_different_units_sort_after_conversion() {
+        let mev = dataset("Lin-Lin", &[(0.5, 1.0), (1.0, 1.0)]);
+        let mut ev = dataset("Lin-Lin", &[(1.0, 3.0), (1000.0, 3.0)]);
+        ev.columns = vec!["E(eV)".to_string(), "Sig(b)".to_string()];
+        let merged = merge_datasets(&[mev, ev]).unwrap();
+
+        assert_eq!(merged.energies, vec![1e-6, 1e-3, 0.5, 1.0]);
+        assert_eq!(merged.ranges[0].source, 1);
+    }
+
+    #[test]
+    fn merging_needs_a_point() {
+        assert!(merge_datasets(&[dataset("Lin-Lin", &[])]).is_err());
+    }
+}
@@ -0,0 +1,132 @@
+//! Monte Carlo cross-check of the Maxwellian average
+//!
+//! The MACS is an expectation value over the Maxwellian: with θ = kT/a the
+//! flux-weighted average is (2/√π) times the mean of σ(E) for E drawn from
+//! E·exp(-E/θ), a Gamma(2, θ) distribution, and the number-density average is
+//! the mean of σ(E) for E drawn from √E·exp(-E/θ), a Gamma(3/2, θ)
+//! distribution. Sampling energies and averaging the interpolated cross
+//! section therefore estimates the MACS independently of the quadrature, with
+//! a statistical error shrinking as 1/√n.
+
+use crate::interpolation::{Interpolation, interpolate_at};
+use crate::macs::{self, MaxwellianWeighting};
+use crate::units;
+use std::f64::consts::PI;
+
+/// Seed of the sampler, fixed so that validation runs are reproducible
+pub const DEFAULT_SEED: u64 = 0x2545_F491_4F6C_DD1D;
+
+/// Small deterministic pseudo-random generator (SplitMix64)
+///
+/// Statistical quality is ample for a validation average and it avoids a
+/// dependency on a random number crate.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform deviate in (0, 1], safe to take the logarithm of
+    fn uniform(&mut self) -> f64 {
+        ((self.next_u64() >> 11) + 1) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Standard normal deviate (Box-Muller)
+    fn normal(&mut self) -> f64 {
+        (-2.0 * self.uniform().ln()).sqrt() * (2.0 * PI * self.uniform()).cos()
+    }
+}
+
+/// Draws a neutron energy in MeV from the Maxwellian of the chosen weighting
+fn sample_energy(rng: &mut SplitMix64, theta: f64, weighting: MaxwellianWeighting) -> f64 {
+    match weighting {
+        // Sum of two exponentials
+        MaxwellianWeighting::Flux => -theta * (rng.uniform().ln() + rng.uniform().ln()),
+        // Exponential plus half a squared normal (Gamma(1/2))
+        MaxwellianWeighting::NumberDensity => {
+            let z = rng.normal();
+            theta * (0.5 * z * z - rng.uniform().ln())
+        }
+    }
+}
+
+/// Monte Carlo estimate of the MACS with its statistical error
+#[derive(Debug, Clone, Copy)]
+pub struct McEstimate {
+    /// Estimated MACS in millibarns
+    pub macs_mb: f64,
+    /// Standard error of the estimate in millibarns
+    pub std_error_mb: f64,
+}
+
+/// Estimates the MACS by sampling energies from the Maxwellian
+///
+/// The cross section is evaluated with `law` at each sampled energy and taken
+/// as zero outside the grid, which matches the integral over the grid
+/// computed by [`macs::calculate_macs`].
+///
+/// # Arguments
+/// * `energies` - Energy points in MeV
+/// * `cross_sections` - Cross section values in barns
+/// * `law` - Interpolation law of the cross section
+/// * `atomic_mass` - Atomic mass number
+/// * `temperature_kev` - Temperature in keV
+/// * `weighting` - Averaging convention (see [`MaxwellianWeighting`])
+/// * `samples` - Number of sampled energies (at least 2)
+/// * `seed` - Seed of the generator, usually [`DEFAULT_SEED`]
+///
+/// # Returns
+/// * `Ok(estimate)` - Mean and standard error in millibarns
+/// * `Err(msg)` - If inputs are invalid
+#[allow(clippy::too_many_arguments)]
+pub fn monte_carlo_macs(
+    energies: &[f64],
+    cross_sections: &[f64],
+    law: &dyn Interpolation,
+    atomic_mass: f64,
+    temperature_kev: f64,
+    weighting: MaxwellianWeighting,
+    samples: usize,
+    seed: u64,
+) -> Result<McEstimate, String> {
+    if energies.is_empty() || energies.len() != cross_sections.len() {
+        return Err(
+            "Energy and cross section vectors must be non-empty and of the same length".to_string(),
+        );
+    }
+    if temperature_kev <= 0.0 {
+        return Err("Temperature must be positive".to_string());
+    }
+    if samples < 2 {
+        return Err("At least two samples are needed for a Monte Carlo estimate".to_string());
+    }
+
+    let theta = macs::thermal_energy(temperature_kev)
+        / macs::reduced_mass_factor(atomic_mass, macs::NEUTRON_MASS);
+    let scale = match weighting {
+        MaxwellianWeighting::Flux => 2.0 / PI.sqrt(),
+        MaxwellianWeighting::NumberDensity => 1.0,
+    };
+
+    let mut rng = SplitMix64(seed);
+    let (mut sum, mut sum_squares) = (0.0, 0.0);
+    for _ in 0..samples {
+        let energy = sample_energy(&mut rng, theta, weighting);
+        let sigma = interpolate_at(energies, cross_sections, law, energy).unwrap_or(0.0);
+        sum += sigma;
+        sum_squares += sigma * sigma;
+    }
+
+    let n = samples as f64;
+    let mean = sum / n;
+    let variance = ((sum_squares - n * mean * mean) / (n - 1.0)).max(0.0);
+    Ok(McEstimate {
+        macs_mb: units::barns_to_millibarns(scale * mean),
+        std_error_mb: units::barns_to_millibarns(scale * (variance / n).sqrt()),
+    })
+}
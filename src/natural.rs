@@ -0,0 +1,148 @@
+//! Natural-element MACS from the isotopes
+//!
+//! A sample of natural composition captures on every isotope, so its MACS is
+//! the abundance-weighted sum Σ fᵢ·MACSᵢ of the isotopic values. This is more
+//! accurate than integrating an elemental cross section, which evaluations
+//! often do not provide and which hides the isotopic resonance structure.
+//!
+//! The abundances come from [`nuclide::natural_abundances`]. Isotopes missing
+//! from a library are left out and the remaining abundances renormalized, so
+//! the sum stays an average; the share that was covered is returned so that
+//! the caller can flag an incomplete combination.
+
+use crate::macs::{MacsResult, MacsTable};
+use crate::nuclide;
+
+/// Combines the tables of the isotopes of an element from one library
+///
+/// # Arguments
+/// * `z` - Atomic number of the element
+/// * `isotopes` - Table of each isotope computed, with its mass number
+///
+/// # Returns
+/// * `Ok((table, covered))` - The natural-element table, with a MACS at each
+///   temperature computed for all the isotopes, and the summed abundance of
+///   the isotopes it includes
+/// * `Err(msg)` - If none of the isotopes occurs in nature
+pub fn combine(z: u32, isotopes: &[(u32, &MacsTable)]) -> Result<(MacsTable, f64), String> {
+    let abundances = nuclide::natural_abundances(z);
+    let weighted: Vec<(f64, &MacsTable)> = isotopes
+        .iter()
+        .filter_map(|&(a, table)| {
+            let (_, abundance) = abundances
+                .iter()
+                .find(|&&(mass_number, _)| mass_number == a)?;
+            Some((*abundance, table))
+        })
+        .collect();
+    let (_, first) = *weighted
+        .first()
+        .ok_or_else(|| format!("No natural isotope of Z = {} was computed", z))?;
+    let covered: f64 = weighted.iter().map(|(abundance, _)| abundance).sum();
+
+    let results = first
+        .results
+        .iter()
+        .filter_map(|result| {
+            let temperature = result.temperature_kev;
+            let macs_mb = weighted
+                .iter()
+                .map(|(abundance, table)| {
+                    table
+                        .results
+                        .iter()
+                        .find(|r| r.temperature_kev == temperature)
+                        .map(|r| abundance * r.macs_mb)
+                })
+                .sum::<Option<f64>>()?
+                / covered;
+            Some(MacsResult {
+                branching: result.branching,
+                renormalization: result.renormalization,
+                processing_temperature: result.processing_temperature,
+                ..MacsResult::new(temperature, macs_mb)
+            })
+        })
+        .collect();
+
+    let target = nuclide::format_target(z, nuclide::NATURAL_MASS_NUMBER)?;
+    let atomic_mass = nuclide::natural_atomic_weight(&target)
+        .ok_or_else(|| format!("{} has no natural atomic weight", target))?;
+    let table = MacsTable {
+        target,
+        reaction: first.reaction.clone(),
+        library: first.library.clone(),
+        atomic_mass,
+        results,
+        spectrum_average_mb: None,
+        thermal_cross_section_b: None,
+        resonance_integral_b: None,
+        datasets: Vec::new(),
+        data_sources: Vec::new(),
+        fit: None,
+        approximate: weighted.iter().any(|(_, table)| table.approximate),
+    };
+    Ok((table, covered))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Table of a copper isotope with a MACS at each (temperature, value)
+    fn copper(a: u32, macs: &[(f64, f64)]) -> MacsTable {
+        let results: Vec<String> = macs
+            .iter()
+            .map(|(t, m)| format!(r#"{{"temperature_kev": {}, "macs_mb": {}}}"#, t, m))
+            .collect();
+        serde_json::from_str(&format!(
+            r#"{{"target": "Cu-{}", "reaction": "n,g", "library": "MOCK",
+                "atomic_mass": {}, "results": [{}]}}"#,
+            a,
+            a,
+            results.join(", ")
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn isotopes_are_weighted_by_their_abundances() {
+        let cu63 = copper(63, &[(5.0, 200.0), (30.0, 100.0)]);
+        let cu65 = copper(65, &[(5.0, 80.0), (30.0, 40.0)]);
+        let (table, covered) = combine(29, &[(63, &cu63), (65, &cu65)]).unwrap();
+        assert!((covered - 1.0).abs() < 1e-12);
+        assert_eq!(table.target, "Cu-0");
+        assert_eq!(table.library, "MOCK");
+        assert_eq!(table.results.len(), 2);
+        for (result, expected) in table.results.iter().zip([
+            0.6915 * 200.0 + 0.3085 * 80.0,
+            0.6915 * 100.0 + 0.3085 * 40.0,
+        ]) {
+            assert!(
+                (result.macs_mb - expected).abs() < 1e-9,
+                "{}",
+                result.macs_mb
+            );
+        }
+    }
+
+    #[test]
+    fn missing_isotopes_renormalize_the_abundances() {
+        let cu63 = copper(63, &[(30.0, 100.0)]);
+        let cu64 = copper(64, &[(30.0, 1000.0)]);
+        let (table, covered) = combine(29, &[(63, &cu63), (64, &cu64)]).unwrap();
+        assert!((covered - 0.6915).abs() < 1e-12);
+        assert!((table.results[0].macs_mb - 100.0).abs() < 1e-9);
+
+        assert!(combine(29, &[(64, &cu64)]).is_err());
+    }
+
+    #[test]
+    fn temperatures_missing_from_an_isotope_are_dropped() {
+        let cu63 = copper(63, &[(5.0, 200.0), (30.0, 100.0)]);
+        let cu65 = copper(65, &[(30.0, 40.0)]);
+        let (table, _) = combine(29, &[(63, &cu63), (65, &cu65)]).unwrap();
+        assert_eq!(table.results.len(), 1);
+        assert_eq!(table.results[0].temperature_kev, 30.0);
+    }
+}
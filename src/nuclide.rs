@@ -0,0 +1,555 @@
+//! Nuclide identification helpers
+//!
+//! Converts between EXFOR target strings (e.g. "Mo-94") and the
+//! (Z, A) pair of proton and mass numbers. Natural elements are written
+//! with mass number 0 (e.g. "Mo-0"), as in EXFOR.
+
+/// Element symbols indexed by atomic number minus one
+const ELEMENT_SYMBOLS: [&str; 118] = [
+    "H", "He", "Li", "Be", "B", "C", "N", "O", "F", "Ne", "Na", "Mg", "Al", "Si", "P", "S", "Cl",
+    "Ar", "K", "Ca", "Sc", "Ti", "V", "Cr", "Mn", "Fe", "Co", "Ni", "Cu", "Zn", "Ga", "Ge", "As",
+    "Se", "Br", "Kr", "Rb", "Sr", "Y", "Zr", "Nb", "Mo", "Tc", "Ru", "Rh", "Pd", "Ag", "Cd", "In",
+    "Sn", "Sb", "Te", "I", "Xe", "Cs", "Ba", "La", "Ce", "Pr", "Nd", "Pm", "Sm", "Eu", "Gd", "Tb",
+    "Dy", "Ho", "Er", "Tm", "Yb", "Lu", "Hf", "Ta", "W", "Re", "Os", "Ir", "Pt", "Au", "Hg", "Tl",
+    "Pb", "Bi", "Po", "At", "Rn", "Fr", "Ra", "Ac", "Th", "Pa", "U", "Np", "Pu", "Am", "Cm", "Bk",
+    "Cf", "Es", "Fm", "Md", "No", "Lr", "Rf", "Db", "Sg", "Bh", "Hs", "Mt", "Ds", "Rg", "Cn", "Nh",
+    "Fl", "Mc", "Lv", "Ts", "Og",
+];
+
+/// Mass number EXFOR uses for natural-abundance targets
+pub const NATURAL_MASS_NUMBER: u32 = 0;
+
+/// Standard atomic weights in amu indexed by atomic number minus one,
+/// `None` for elements without a natural isotopic composition
+const NATURAL_ATOMIC_WEIGHTS: [Option<f64>; 92] = [
+    Some(1.008),
+    Some(4.0026),
+    Some(6.94),
+    Some(9.0122),
+    Some(10.81),
+    Some(12.011),
+    Some(14.007),
+    Some(15.999),
+    Some(18.998),
+    Some(20.180),
+    Some(22.990),
+    Some(24.305),
+    Some(26.982),
+    Some(28.085),
+    Some(30.974),
+    Some(32.06),
+    Some(35.45),
+    Some(39.95),
+    Some(39.098),
+    Some(40.078),
+    Some(44.956),
+    Some(47.867),
+    Some(50.942),
+    Some(51.996),
+    Some(54.938),
+    Some(55.845),
+    Some(58.933),
+    Some(58.693),
+    Some(63.546),
+    Some(65.38),
+    Some(69.723),
+    Some(72.630),
+    Some(74.922),
+    Some(78.971),
+    Some(79.904),
+    Some(83.798),
+    Some(85.468),
+    Some(87.62),
+    Some(88.906),
+    Some(91.224),
+    Some(92.906),
+    Some(95.95),
+    None,
+    Some(101.07),
+    Some(102.91),
+    Some(106.42),
+    Some(107.87),
+    Some(112.41),
+    Some(114.82),
+    Some(118.71),
+    Some(121.76),
+    Some(127.60),
+    Some(126.90),
+    Some(131.29),
+    Some(132.91),
+    Some(137.33),
+    Some(138.91),
+    Some(140.12),
+    Some(140.91),
+    Some(144.24),
+    None,
+    Some(150.36),
+    Some(151.96),
+    Some(157.25),
+    Some(158.93),
+    Some(162.50),
+    Some(164.93),
+    Some(167.26),
+    Some(168.93),
+    Some(173.05),
+    Some(174.97),
+    Some(178.49),
+    Some(180.95),
+    Some(183.84),
+    Some(186.21),
+    Some(190.23),
+    Some(192.22),
+    Some(195.08),
+    Some(196.97),
+    Some(200.59),
+    Some(204.38),
+    Some(207.2),
+    Some(208.98),
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    Some(232.04),
+    Some(231.04),
+    Some(238.03),
+];
+
+/// Natural isotopic abundances in atom percent indexed by atomic number minus
+/// one, as (mass number, abundance) pairs of the stable and primordial
+/// isotopes; empty for elements without a natural isotopic composition
+///
+/// Representative compositions after IUPAC; the values of each element add
+/// up to 100% within the rounding of the table.
+const NATURAL_ABUNDANCES: [&[(u32, f64)]; 92] = [
+    &[(1, 99.9885), (2, 0.0115)],
+    &[(3, 0.000134), (4, 99.999866)],
+    &[(6, 7.59), (7, 92.41)],
+    &[(9, 100.0)],
+    &[(10, 19.9), (11, 80.1)],
+    &[(12, 98.93), (13, 1.07)],
+    &[(14, 99.636), (15, 0.364)],
+    &[(16, 99.757), (17, 0.038), (18, 0.205)],
+    &[(19, 100.0)],
+    &[(20, 90.48), (21, 0.27), (22, 9.25)],
+    &[(23, 100.0)],
+    &[(24, 78.99), (25, 10.00), (26, 11.01)],
+    &[(27, 100.0)],
+    &[(28, 92.223), (29, 4.685), (30, 3.092)],
+    &[(31, 100.0)],
+    &[(32, 94.99), (33, 0.75), (34, 4.25), (36, 0.01)],
+    &[(35, 75.76), (37, 24.24)],
+    &[(36, 0.3336), (38, 0.0629), (40, 99.6035)],
+    &[(39, 93.2581), (40, 0.0117), (41, 6.7302)],
+    &[
+        (40, 96.941),
+        (42, 0.647),
+        (43, 0.135),
+        (44, 2.086),
+        (46, 0.004),
+        (48, 0.187),
+    ],
+    &[(45, 100.0)],
+    &[(46, 8.25), (47, 7.44), (48, 73.72), (49, 5.41), (50, 5.18)],
+    &[(50, 0.250), (51, 99.750)],
+    &[(50, 4.345), (52, 83.789), (53, 9.501), (54, 2.365)],
+    &[(55, 100.0)],
+    &[(54, 5.845), (56, 91.754), (57, 2.119), (58, 0.282)],
+    &[(59, 100.0)],
+    &[
+        (58, 68.077),
+        (60, 26.223),
+        (61, 1.1399),
+        (62, 3.6346),
+        (64, 0.9255),
+    ],
+    &[(63, 69.15), (65, 30.85)],
+    &[
+        (64, 49.17),
+        (66, 27.73),
+        (67, 4.04),
+        (68, 18.45),
+        (70, 0.61),
+    ],
+    &[(69, 60.108), (71, 39.892)],
+    &[
+        (70, 20.57),
+        (72, 27.45),
+        (73, 7.75),
+        (74, 36.50),
+        (76, 7.73),
+    ],
+    &[(75, 100.0)],
+    &[
+        (74, 0.89),
+        (76, 9.37),
+        (77, 7.63),
+        (78, 23.77),
+        (80, 49.61),
+        (82, 8.73),
+    ],
+    &[(79, 50.69), (81, 49.31)],
+    &[
+        (78, 0.355),
+        (80, 2.286),
+        (82, 11.593),
+        (83, 11.500),
+        (84, 56.987),
+        (86, 17.279),
+    ],
+    &[(85, 72.17), (87, 27.83)],
+    &[(84, 0.56), (86, 9.86), (87, 7.00), (88, 82.58)],
+    &[(89, 100.0)],
+    &[
+        (90, 51.45),
+        (91, 11.22),
+        (92, 17.15),
+        (94, 17.38),
+        (96, 2.80),
+    ],
+    &[(93, 100.0)],
+    &[
+        (92, 14.53),
+        (94, 9.15),
+        (95, 15.84),
+        (96, 16.67),
+        (97, 9.60),
+        (98, 24.39),
+        (100, 9.82),
+    ],
+    &[],
+    &[
+        (96, 5.54),
+        (98, 1.87),
+        (99, 12.76),
+        (100, 12.60),
+        (101, 17.06),
+        (102, 31.55),
+        (104, 18.62),
+    ],
+    &[(103, 100.0)],
+    &[
+        (102, 1.02),
+        (104, 11.14),
+        (105, 22.33),
+        (106, 27.33),
+        (108, 26.46),
+        (110, 11.72),
+    ],
+    &[(107, 51.839), (109, 48.161)],
+    &[
+        (106, 1.25),
+        (108, 0.89),
+        (110, 12.49),
+        (111, 12.80),
+        (112, 24.13),
+        (113, 12.22),
+        (114, 28.73),
+        (116, 7.49),
+    ],
+    &[(113, 4.29), (115, 95.71)],
+    &[
+        (112, 0.97),
+        (114, 0.66),
+        (115, 0.34),
+        (116, 14.54),
+        (117, 7.68),
+        (118, 24.22),
+        (119, 8.59),
+        (120, 32.58),
+        (122, 4.63),
+        (124, 5.79),
+    ],
+    &[(121, 57.21), (123, 42.79)],
+    &[
+        (120, 0.09),
+        (122, 2.55),
+        (123, 0.89),
+        (124, 4.74),
+        (125, 7.07),
+        (126, 18.84),
+        (128, 31.74),
+        (130, 34.08),
+    ],
+    &[(127, 100.0)],
+    &[
+        (124, 0.0952),
+        (126, 0.0890),
+        (128, 1.9102),
+        (129, 26.4006),
+        (130, 4.0710),
+        (131, 21.2324),
+        (132, 26.9086),
+        (134, 10.4357),
+        (136, 8.8573),
+    ],
+    &[(133, 100.0)],
+    &[
+        (130, 0.106),
+        (132, 0.101),
+        (134, 2.417),
+        (135, 6.592),
+        (136, 7.854),
+        (137, 11.232),
+        (138, 71.698),
+    ],
+    &[(138, 0.08881), (139, 99.91119)],
+    &[(136, 0.185), (138, 0.251), (140, 88.450), (142, 11.114)],
+    &[(141, 100.0)],
+    &[
+        (142, 27.152),
+        (143, 12.174),
+        (144, 23.798),
+        (145, 8.293),
+        (146, 17.189),
+        (148, 5.756),
+        (150, 5.638),
+    ],
+    &[],
+    &[
+        (144, 3.07),
+        (147, 14.99),
+        (148, 11.24),
+        (149, 13.82),
+        (150, 7.38),
+        (152, 26.75),
+        (154, 22.75),
+    ],
+    &[(151, 47.81), (153, 52.19)],
+    &[
+        (152, 0.20),
+        (154, 2.18),
+        (155, 14.80),
+        (156, 20.47),
+        (157, 15.65),
+        (158, 24.84),
+        (160, 21.86),
+    ],
+    &[(159, 100.0)],
+    &[
+        (156, 0.056),
+        (158, 0.095),
+        (160, 2.329),
+        (161, 18.889),
+        (162, 25.475),
+        (163, 24.896),
+        (164, 28.260),
+    ],
+    &[(165, 100.0)],
+    &[
+        (162, 0.139),
+        (164, 1.601),
+        (166, 33.503),
+        (167, 22.869),
+        (168, 26.978),
+        (170, 14.910),
+    ],
+    &[(169, 100.0)],
+    &[
+        (168, 0.123),
+        (170, 2.982),
+        (171, 14.09),
+        (172, 21.68),
+        (173, 16.103),
+        (174, 32.026),
+        (176, 12.996),
+    ],
+    &[(175, 97.401), (176, 2.599)],
+    &[
+        (174, 0.16),
+        (176, 5.26),
+        (177, 18.60),
+        (178, 27.28),
+        (179, 13.62),
+        (180, 35.08),
+    ],
+    &[(180, 0.01201), (181, 99.98799)],
+    &[
+        (180, 0.12),
+        (182, 26.50),
+        (183, 14.31),
+        (184, 30.64),
+        (186, 28.43),
+    ],
+    &[(185, 37.40), (187, 62.60)],
+    &[
+        (184, 0.02),
+        (186, 1.59),
+        (187, 1.96),
+        (188, 13.24),
+        (189, 16.15),
+        (190, 26.26),
+        (192, 40.78),
+    ],
+    &[(191, 37.3), (193, 62.7)],
+    &[
+        (190, 0.012),
+        (192, 0.782),
+        (194, 32.86),
+        (195, 33.78),
+        (196, 25.21),
+        (198, 7.356),
+    ],
+    &[(197, 100.0)],
+    &[
+        (196, 0.15),
+        (198, 9.97),
+        (199, 16.87),
+        (200, 23.10),
+        (201, 13.18),
+        (202, 29.86),
+        (204, 6.87),
+    ],
+    &[(203, 29.52), (205, 70.48)],
+    &[(204, 1.4), (206, 24.1), (207, 22.1), (208, 52.4)],
+    &[(209, 100.0)],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[(232, 100.0)],
+    &[(231, 100.0)],
+    &[(234, 0.0054), (235, 0.7204), (238, 99.2742)],
+];
+
+/// Returns the element symbol for an atomic number, if known
+pub fn symbol_for_z(z: u32) -> Option<&'static str> {
+    ELEMENT_SYMBOLS.get((z as usize).checked_sub(1)?).copied()
+}
+
+/// Returns the atomic number for an element symbol (case-insensitive)
+pub fn z_for_symbol(symbol: &str) -> Option<u32> {
+    ELEMENT_SYMBOLS
+        .iter()
+        .position(|s| s.eq_ignore_ascii_case(symbol))
+        .map(|i| i as u32 + 1)
+}
+
+/// Builds the EXFOR target string for a nuclide
+///
+/// # Arguments
+/// * `z` - Atomic number (e.g., 42 for molybdenum)
+/// * `a` - Mass number (e.g., 94)
+///
+/// # Returns
+/// * `Ok(target)` - Target string in EXFOR notation (e.g., "Mo-94")
+/// * `Err(msg)` - Error message if Z is not a known element
+pub fn format_target(z: u32, a: u32) -> Result<String, String> {
+    let symbol = symbol_for_z(z).ok_or_else(|| format!("Unknown atomic number Z={}", z))?;
+    Ok(format!("{}-{}", symbol, a))
+}
+
+/// Parses an EXFOR target string into its (Z, A) pair
+///
+/// Accepts the "Sym-A" notation used by EXFOR (e.g., "Mo-94", "zr-92").
+/// Natural elements may be written "Sym-0" or "Sym-nat" and get A = 0.
+/// Returns `None` if the string is not in that form or the symbol is unknown.
+pub fn parse_target(target: &str) -> Option<(u32, u32)> {
+    let (symbol, mass) = target.trim().split_once('-')?;
+    let z = z_for_symbol(symbol)?;
+    let a = if mass.eq_ignore_ascii_case("nat") {
+        NATURAL_MASS_NUMBER
+    } else {
+        mass.parse::<u32>().ok()?
+    };
+    Some((z, a))
+}
+
+/// Spells a target the way the EXFOR API expects it
+///
+/// Natural elements become "Sym-0" (e.g., "Mo-nat" gives "Mo-0"); other
+/// targets, including ones that cannot be parsed, are returned unchanged.
+pub fn exfor_target(target: &str) -> String {
+    match parse_target(target) {
+        Some((z, NATURAL_MASS_NUMBER)) => {
+            format_target(z, NATURAL_MASS_NUMBER).unwrap_or_else(|_| target.to_string())
+        }
+        _ => target.trim().to_string(),
+    }
+}
+
+/// Returns the standard atomic weight of a natural element target
+///
+/// Used as the atomic mass of natural targets. The MACS of a natural element
+/// is computed with a single reduced mass factor a = A/(1+A) taken at this
+/// mean weight instead of averaging over the isotopes; since a changes by
+/// less than 0.1% between neighbouring isotopes of elements beyond oxygen,
+/// the approximation is far below the uncertainty of the data.
+///
+/// # Returns
+/// * `Some(weight)` - In amu, if the target is a natural element with a
+///   natural isotopic composition
+/// * `None` - For single isotopes, unparsable targets, and elements such as
+///   Tc or Pm that have no stable isotope
+pub fn natural_atomic_weight(target: &str) -> Option<f64> {
+    match parse_target(target)? {
+        (z, NATURAL_MASS_NUMBER) => NATURAL_ATOMIC_WEIGHTS
+            .get((z as usize).checked_sub(1)?)
+            .copied()
+            .flatten(),
+        _ => None,
+    }
+}
+
+/// Returns the natural isotopic composition of an element
+///
+/// # Returns
+/// The (mass number, abundance fraction) of each naturally occurring
+/// isotope, lightest first; empty for unknown atomic numbers and for
+/// elements such as Tc or Pm that have no stable isotope
+pub fn natural_abundances(z: u32) -> Vec<(u32, f64)> {
+    (z as usize)
+        .checked_sub(1)
+        .and_then(|i| NATURAL_ABUNDANCES.get(i))
+        .map_or(Vec::new(), |isotopes| {
+            isotopes
+                .iter()
+                .map(|&(a, percent)| (a, percent / 100.0))
+                .collect()
+        })
+}
+
+/// Largest accepted difference between an atomic mass and the mass number
+pub const MASS_NUMBER_TOLERANCE: f64 = 0.5;
+
+/// Cross-checks an atomic mass against the mass number of a target string
+///
+/// Catches typos such as `--mass 9.4` for Mo-94. Targets that cannot be
+/// parsed, and natural elements (mass number 0, e.g. "Mo-0"), are not checked.
+///
+/// # Arguments
+/// * `target` - Target string (e.g., "Mo-94")
+/// * `atomic_mass` - Atomic mass supplied by the user
+pub fn check_mass(target: &str, atomic_mass: f64) -> Result<(), String> {
+    match parse_target(target) {
+        Some((_, a)) if a != 0 && (atomic_mass - a as f64).abs() > MASS_NUMBER_TOLERANCE => {
+            Err(format!(
+                "Atomic mass {} does not match the mass number {} of {}",
+                atomic_mass, a, target
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mass_matching_the_target_is_accepted() {
+        assert!(check_mass("Mo-94", 94.0).is_ok());
+        assert!(check_mass("Mo-94", 93.9050883).is_ok());
+        assert!(check_mass("Mo-nat", 95.95).is_ok());
+    }
+
+    #[test]
+    fn mass_mismatching_the_target_is_rejected() {
+        let error = check_mass("Mo-94", 9.4).unwrap_err();
+        assert!(error.contains("mass number 94"), "{}", error);
+        assert!(check_mass("Zr-92", 94.0).is_err());
+    }
+}
@@ -0,0 +1,77 @@
+//! Offline snapshot of EXFOR cross section datasets
+//!
+//! A snapshot is a single JSON object mapping "target|reaction|library" keys
+//! to the unmodified e4sig responses (`CrossSectionResponse`) returned by the
+//! IAEA API. Each response keeps the API's own `now` timestamp and `program`
+//! fields, which record when and by which service version it was produced.
+
+use crate::exfor_client::CrossSectionResponse;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Cross section datasets stored for offline use
+#[derive(Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct OfflineDb {
+    entries: BTreeMap<String, CrossSectionResponse>,
+}
+
+/// Builds the snapshot key of a dataset
+fn key(target: &str, reaction: &str, library: &str) -> String {
+    format!("{}|{}|{}", target, reaction, library)
+}
+
+impl OfflineDb {
+    /// Reads a snapshot file
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read offline database {}: {}", path.display(), e))?;
+        let db = serde_json::from_str(&content)
+            .map_err(|e| format!("Cannot parse offline database {}: {}", path.display(), e))?;
+        Ok(db)
+    }
+
+    /// Writes the snapshot file
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content)
+            .map_err(|e| format!("Cannot write offline database {}: {}", path.display(), e))?;
+        Ok(())
+    }
+
+    /// Looks up the dataset stored for a target, reaction and library
+    pub fn get(
+        &self,
+        target: &str,
+        reaction: &str,
+        library: &str,
+    ) -> Option<&CrossSectionResponse> {
+        self.entries.get(&key(target, reaction, library))
+    }
+
+    /// Lists the targets stored for a reaction in any library
+    pub fn targets(&self, reaction: &str) -> Vec<String> {
+        let mut targets: Vec<String> = self
+            .entries
+            .keys()
+            .filter_map(|key| {
+                let mut fields = key.split('|');
+                let target = fields.next()?;
+                (fields.next()? == reaction).then(|| target.to_string())
+            })
+            .collect();
+        targets.dedup();
+        targets
+    }
+
+    /// Stores a dataset, replacing any previous entry with the same key
+    pub fn insert(
+        &mut self,
+        target: &str,
+        reaction: &str,
+        library: &str,
+        response: CrossSectionResponse,
+    ) {
+        self.entries
+            .insert(key(target, reaction, library), response);
+    }
+}
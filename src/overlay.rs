@@ -0,0 +1,236 @@
+//! Substitution of experimental points into an evaluated cross section
+//!
+//! Recent measurements sometimes differ from an evaluation over part of the
+//! energy range. For sensitivity studies the evaluated grid is kept as the
+//! base and, inside chosen energy windows, replaced by measured points read
+//! from a two-column (energy, cross section) text file.
+
+use crate::columns;
+use crate::interpolation::{self, Interpolation, LinLin, PiecewiseInterpolation, interpolate_at};
+use std::path::Path;
+
+/// Measured cross section points
+#[derive(Debug, Clone)]
+pub struct ExperimentalData {
+    /// Energies in MeV, strictly increasing
+    energies: Vec<f64>,
+    /// Cross sections in barns
+    cross_sections: Vec<f64>,
+}
+
+impl ExperimentalData {
+    /// Reads an experimental data file
+    ///
+    /// Each non-empty line holds an energy in MeV and a cross section in
+    /// barns, separated by whitespace or a comma. Lines starting with `#` are
+    /// comments.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let (energies, cross_sections) =
+            columns::read_two_columns(path, "experimental data file", "cross section")?;
+        Ok(ExperimentalData {
+            energies,
+            cross_sections,
+        })
+    }
+
+    /// Compares the measured points with an evaluated cross section
+    ///
+    /// # Returns
+    /// `Some((mean_ratio, points))` - Mean experimental/evaluated ratio over the
+    /// measured points inside the evaluated range where the evaluation is not
+    /// zero, or `None` if there are no such points
+    pub fn compare(
+        &self,
+        energies: &[f64],
+        cross_sections: &[f64],
+        law: &dyn Interpolation,
+    ) -> Option<(f64, usize)> {
+        let ratios: Vec<f64> = self
+            .energies
+            .iter()
+            .zip(&self.cross_sections)
+            .filter_map(|(&e, &measured)| {
+                let evaluated = interpolate_at(energies, cross_sections, law, e)?;
+                (evaluated != 0.0).then(|| measured / evaluated)
+            })
+            .collect();
+        if ratios.is_empty() {
+            return None;
+        }
+        Some((
+            ratios.iter().sum::<f64>() / ratios.len() as f64,
+            ratios.len(),
+        ))
+    }
+}
+
+/// Origin of the cross section over part of the overlaid grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataSource {
+    /// Evaluated library data
+    Evaluated,
+    /// Measured points from the experimental data file
+    Experimental,
+}
+
+/// Energy range of the overlaid grid taken from one source
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct SourceRange {
+    /// Where the cross section comes from
+    pub source: DataSource,
+    /// Lower energy in MeV
+    pub start_mev: f64,
+    /// Upper energy in MeV
+    pub end_mev: f64,
+    /// Number of grid points in the range
+    pub points: usize,
+}
+
+/// Evaluated cross section with experimental points substituted
+pub struct Overlay {
+    /// Energies in MeV, non-decreasing (window edges appear twice, as a step)
+    pub energies: Vec<f64>,
+    /// Cross sections in barns
+    pub cross_sections: Vec<f64>,
+    /// Evaluated law outside the windows, lin-lin inside them
+    pub law: PiecewiseInterpolation,
+    /// Source of each part of the grid, in energy order
+    pub ranges: Vec<SourceRange>,
+}
+
+impl Overlay {
+    /// Appends a range of points from one source
+    fn push(&mut self, source: DataSource, points: &[(f64, f64)], law: Box<dyn Interpolation>) {
+        let (Some(&(start, _)), Some(&(end, _))) = (points.first(), points.last()) else {
+            return;
+        };
+        self.law.push_range(start, end, law);
+        self.ranges.push(SourceRange {
+            source,
+            start_mev: start,
+            end_mev: end,
+            points: points.len(),
+        });
+        self.energies.extend(points.iter().map(|&(e, _)| e));
+        self.cross_sections.extend(points.iter().map(|&(_, cs)| cs));
+    }
+}
+
+/// Replaces the evaluated cross section by experimental points inside windows
+///
+/// Each window is clipped to the energies covered by both tabulations, and
+/// overlapping windows are joined. Inside a window the measured points are
+/// used, interpolated lin-lin, with values at the window edges interpolated
+/// from them; the evaluated cross section is kept elsewhere with its declared
+/// law. At each edge the grid steps from one source to the other.
+///
+/// # Arguments
+/// * `energies` - Evaluated energies in MeV
+/// * `cross_sections` - Evaluated cross sections in barns
+/// * `law_name` - Interpolation law declared by the evaluated dataset
+/// * `experimental` - Measured points
+/// * `windows` - (start, end) energy windows in MeV
+///
+/// # Returns
+/// * `Ok(overlay)` - The combined grid
+/// * `Err(msg)` - If a window shares no energies with both tabulations
+pub fn overlay(
+    energies: &[f64],
+    cross_sections: &[f64],
+    law_name: &str,
+    experimental: &ExperimentalData,
+    windows: &[(f64, f64)],
+) -> Result<Overlay, String> {
+    let evaluated_law = || {
+        interpolation::from_name(law_name)
+            .unwrap_or_else(|| Box::new(LinLin) as Box<dyn Interpolation>)
+    };
+    let (Some(&eval_first), Some(&eval_last)) = (energies.first(), energies.last()) else {
+        return Err("The evaluated cross section has no points".to_string());
+    };
+    let exp = &experimental.energies;
+    let (exp_first, exp_last) = (exp[0], exp[exp.len() - 1]);
+
+    let mut clipped: Vec<(f64, f64)> = Vec::with_capacity(windows.len());
+    for &(start, end) in windows {
+        let low = start.max(exp_first).max(eval_first);
+        let high = end.min(exp_last).min(eval_last);
+        if low >= high {
+            return Err(format!(
+                "The window {:.3e} - {:.3e} MeV is not covered by both the experimental and the evaluated data",
+                start, end
+            ));
+        }
+        clipped.push((low, high));
+    }
+    clipped.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut joined: Vec<(f64, f64)> = Vec::with_capacity(clipped.len());
+    for (low, high) in clipped {
+        match joined.last_mut() {
+            Some(last) if low <= last.1 => last.1 = last.1.max(high),
+            _ => joined.push((low, high)),
+        }
+    }
+
+    let base_law = evaluated_law();
+    let evaluated_at = |e: f64| interpolate_at(energies, cross_sections, &*base_law, e);
+    let experimental_at = |e: f64| interpolate_at(exp, &experimental.cross_sections, &LinLin, e);
+
+    let mut result = Overlay {
+        energies: Vec::new(),
+        cross_sections: Vec::new(),
+        law: PiecewiseInterpolation::new(),
+        ranges: Vec::new(),
+    };
+    let mut covered = f64::NEG_INFINITY;
+    for &(low, high) in &joined {
+        // Evaluated points from the previous window up to this one's lower edge
+        let mut points: Vec<(f64, f64)> = Vec::new();
+        if covered.is_finite() {
+            points.extend(evaluated_at(covered).map(|cs| (covered, cs)));
+        }
+        points.extend(
+            energies
+                .iter()
+                .zip(cross_sections)
+                .filter(|&(&e, _)| e > covered && e < low)
+                .map(|(&e, &cs)| (e, cs)),
+        );
+        points.extend(evaluated_at(low).map(|cs| (low, cs)));
+        if points.len() > 1 {
+            result.push(DataSource::Evaluated, &points, evaluated_law());
+        }
+
+        // Experimental points inside the window, from edge to edge
+        let mut points: Vec<(f64, f64)> = Vec::new();
+        points.extend(experimental_at(low).map(|cs| (low, cs)));
+        points.extend(
+            exp.iter()
+                .zip(&experimental.cross_sections)
+                .filter(|&(&e, _)| e > low && e < high)
+                .map(|(&e, &cs)| (e, cs)),
+        );
+        points.extend(experimental_at(high).map(|cs| (high, cs)));
+        result.push(DataSource::Experimental, &points, Box::new(LinLin));
+        covered = high;
+    }
+
+    // Evaluated points above the last window, starting on its upper edge
+    let mut points: Vec<(f64, f64)> = Vec::new();
+    if covered < eval_last {
+        points.extend(evaluated_at(covered).map(|cs| (covered, cs)));
+        points.extend(
+            energies
+                .iter()
+                .zip(cross_sections)
+                .filter(|&(&e, _)| e > covered)
+                .map(|(&e, &cs)| (e, cs)),
+        );
+    }
+    if points.len() > 1 {
+        result.push(DataSource::Evaluated, &points, evaluated_law());
+    }
+
+    Ok(result)
+}
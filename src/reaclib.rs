@@ -0,0 +1,343 @@
+//! REACLIB rate entries
+//!
+//! Reaction network codes read thermonuclear rates as REACLIB sets: seven
+//! coefficients of
+//!
+//! ln(N_A⟨σv⟩) = a₀ + a₁/T₉ + a₂·T₉^(-1/3) + a₃·T₉^(1/3) + a₄·T₉ + a₅·T₉^(5/3) + a₆·ln T₉
+//!
+//! with T₉ the temperature in GK. The MACS is computed on a fixed T₉ grid,
+//! converted to the rate with [`macs::macs_to_rate`] and fitted by least
+//! squares in ln(rate). The fit quality is the largest relative deviation of
+//! the fitted rate from the computed one over the grid.
+//!
+//! Entries follow the REACLIB 2 layout: the chapter number on its own line,
+//! then `(5x,6a5,8x,a4,a1,a1,3x,1pe12.5)` for the nuclides, label, flags and
+//! Q value, and `(4e13.6)`, `(3e13.6)` for the coefficients. The Q value is
+//! written as zero, since no mass table is available here; network codes
+//! only use it for energy generation, not for the forward rate.
+
+use crate::fit;
+use crate::macs::{self, MacsResult};
+use crate::nuclide;
+use crate::units;
+
+/// Lowest temperature of the fit grid in GK
+const MIN_T9: f64 = 0.01;
+
+/// Highest temperature of the fit grid in GK
+const MAX_T9: f64 = 10.0;
+
+/// Fit temperatures per decade of T₉
+const T9_PER_DECADE: usize = 10;
+
+/// Largest relative deviation of the fitted rate accepted without a warning
+pub const MAX_FIT_DEVIATION: f64 = 0.05;
+
+/// Set label written in the entries
+const LABEL: &str = "macs";
+
+/// Temperatures (kT) in keV of the fit grid, log-spaced in T₉
+pub fn temperature_grid_kev() -> Vec<f64> {
+    let decades = (MAX_T9 / MIN_T9).log10().round() as usize;
+    let points = decades * T9_PER_DECADE + 1;
+    (0..points)
+        .map(|i| {
+            let t9 = MIN_T9 * 10f64.powf(i as f64 / T9_PER_DECADE as f64);
+            units::gk_to_kev(t9)
+        })
+        .collect()
+}
+
+/// Basis functions of the REACLIB form at a temperature in GK
+fn basis(t9: f64) -> [f64; 7] {
+    [
+        1.0,
+        1.0 / t9,
+        t9.powf(-1.0 / 3.0),
+        t9.powf(1.0 / 3.0),
+        t9,
+        t9.powf(5.0 / 3.0),
+        t9.ln(),
+    ]
+}
+
+/// Fitted REACLIB set
+#[derive(Debug, Clone)]
+pub struct ReaclibFit {
+    /// Coefficients a₀ to a₆
+    pub coefficients: [f64; 7],
+    /// Largest relative deviation (fit - rate) / rate over the grid
+    pub max_deviation: f64,
+}
+
+impl ReaclibFit {
+    /// Evaluates the fitted rate at a temperature in GK
+    pub fn evaluate(&self, t9: f64) -> f64 {
+        basis(t9)
+            .iter()
+            .zip(&self.coefficients)
+            .map(|(b, a)| a * b)
+            .sum::<f64>()
+            .exp()
+    }
+}
+
+/// Fits the REACLIB form to the rates of a set of MACS results
+///
+/// # Arguments
+/// * `results` - MACS at the temperatures of [`temperature_grid_kev`]
+/// * `atomic_mass` - Atomic mass of the target in amu
+///
+/// # Returns
+/// * `Ok(fit)` - Coefficients and fit quality
+/// * `Err(msg)` - If there are too few temperatures, a MACS is not positive,
+///   or the temperatures do not determine the fit
+pub fn fit_rates(results: &[MacsResult], atomic_mass: f64) -> Result<ReaclibFit, String> {
+    if results.len() <= 7 {
+        return Err(format!(
+            "A REACLIB fit needs more than 7 temperatures, got {}",
+            results.len()
+        ));
+    }
+    if let Some(result) = results
+        .iter()
+        .find(|r| r.macs_mb <= 0.0 || r.macs_mb.is_nan())
+    {
+        return Err(format!(
+            "Cannot fit the rate of a MACS of {} mb at {} keV, it must be positive",
+            result.macs_mb, result.temperature_kev
+        ));
+    }
+
+    let reduced_mass = macs::neutron_reduced_mass(atomic_mass);
+    let points: Vec<(f64, f64)> = results
+        .iter()
+        .map(|r| {
+            let t9 = units::kev_to_gk(r.temperature_kev);
+            let rate = macs::macs_to_rate(r.macs_mb, r.temperature_kev, reduced_mass);
+            (t9, rate)
+        })
+        .collect();
+
+    // Normal equations Σ bᵢ·bⱼ aⱼ = Σ bᵢ·ln(rate)
+    let mut matrix = vec![vec![0.0; 7]; 7];
+    let mut rhs = vec![0.0; 7];
+    for &(t9, rate) in &points {
+        let b = basis(t9);
+        for (i, (row, rhs_i)) in matrix.iter_mut().zip(&mut rhs).enumerate() {
+            *rhs_i += b[i] * rate.ln();
+            for (j, value) in row.iter_mut().enumerate() {
+                *value += b[i] * b[j];
+            }
+        }
+    }
+    let solution =
+        fit::solve(matrix, rhs).ok_or("The temperatures do not determine the REACLIB fit")?;
+
+    let mut fit = ReaclibFit {
+        coefficients: [0.0; 7],
+        max_deviation: 0.0,
+    };
+    fit.coefficients.copy_from_slice(&solution);
+    fit.max_deviation = points
+        .iter()
+        .map(|&(t9, rate)| ((fit.evaluate(t9) - rate) / rate).abs())
+        .fold(0.0, f64::max);
+    Ok(fit)
+}
+
+/// REACLIB name of a nuclide, e.g. "mo94", or "n", "p" and "he4"
+fn nuclide_name(z: u32, a: u32) -> Result<String, String> {
+    match (z, a) {
+        (0, 1) => Ok("n".to_string()),
+        (1, 1) => Ok("p".to_string()),
+        _ => {
+            let symbol =
+                nuclide::symbol_for_z(z).ok_or_else(|| format!("No element with Z = {}", z))?;
+            Ok(format!("{}{}", symbol.to_ascii_lowercase(), a))
+        }
+    }
+}
+
+/// Chapter and nuclides of a neutron-induced reaction on a target
+///
+/// (n,γ) is chapter 4 (two reactants, one product); reactions emitting a
+/// particle are chapter 5 (two reactants, two products).
+pub fn reaction_nuclides(target: &str, reaction: &str) -> Result<(u32, Vec<String>), String> {
+    let (z, a) = nuclide::parse_target(target)
+        .filter(|&(_, a)| a != nuclide::NATURAL_MASS_NUMBER)
+        .ok_or_else(|| format!("REACLIB entries need an isotope target, got '{}'", target))?;
+    let (projectile, ejectile) = reaction
+        .split_once(',')
+        .map(|(p, e)| (p.trim(), e.trim()))
+        .ok_or_else(|| format!("Cannot read the reaction '{}'", reaction))?;
+    if !projectile.eq_ignore_ascii_case("n") {
+        return Err(format!(
+            "REACLIB output is only available for neutron-induced reactions, not ({})",
+            reaction
+        ));
+    }
+    let emitted = match ejectile.to_ascii_lowercase().as_str() {
+        "g" => None,
+        "p" => Some((1, 1)),
+        "a" => Some((2, 4)),
+        _ => {
+            return Err(format!(
+                "REACLIB output supports (n,g), (n,p) and (n,a), not ({})",
+                reaction
+            ));
+        }
+    };
+    let (zc, ac) = (z, a + 1);
+    let mut nuclides = vec![nuclide_name(0, 1)?, nuclide_name(z, a)?];
+    let chapter = match emitted {
+        None => {
+            nuclides.push(nuclide_name(zc, ac)?);
+            4
+        }
+        Some((ze, ae)) => {
+            let residual = zc
+                .checked_sub(ze)
+                .zip(ac.checked_sub(ae))
+                .ok_or_else(|| format!("{} cannot emit the ejectile of ({})", target, reaction))?;
+            nuclides.push(nuclide_name(ze, ae)?);
+            nuclides.push(nuclide_name(residual.0, residual.1)?);
+            5
+        }
+    };
+    Ok((chapter, nuclides))
+}
+
+/// Writes one REACLIB 2 entry
+pub fn write_entry(
+    out: &mut dyn std::io::Write,
+    chapter: u32,
+    nuclides: &[String],
+    fit: &ReaclibFit,
+) -> std::io::Result<()> {
+    writeln!(out, "{}", chapter)?;
+    let names: String = (0..6)
+        .map(|i| format!("{:>5}", nuclides.get(i).map_or("", String::as_str)))
+        .collect();
+    // Resonant flag 'n' (non-resonant) and a blank reverse-rate flag
+    writeln!(
+        out,
+        "{:5}{}{:8}{}n {:3}{:>12}",
+        "",
+        names,
+        "",
+        LABEL,
+        "",
+        units::format_exponent(0.0, 5)
+    )?;
+    let coefficients: Vec<String> = fit
+        .coefficients
+        .iter()
+        .map(|&a| format!("{:>13}", units::format_exponent(a, 6)))
+        .collect();
+    writeln!(out, "{}", coefficients[..4].concat())?;
+    writeln!(out, "{}", coefficients[4..].concat())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Result at each temperature of the fit grid with the MACS implied by
+    /// the rate of a REACLIB set
+    fn results_of(coefficients: [f64; 7], atomic_mass: f64) -> Vec<MacsResult> {
+        let set = ReaclibFit {
+            coefficients,
+            max_deviation: 0.0,
+        };
+        let reduced_mass = macs::neutron_reduced_mass(atomic_mass);
+        temperature_grid_kev()
+            .into_iter()
+            .map(|kt| {
+                let rate = set.evaluate(units::kev_to_gk(kt));
+                serde_json::from_str(&format!(
+                    r#"{{"temperature_kev": {}, "macs_mb": {}}}"#,
+                    kt,
+                    macs::rate_to_macs(rate, kt, reduced_mass)
+                ))
+                .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fit_recovers_known_coefficients() {
+        let coefficients = [14.5, -0.02, 0.4, -1.2, 0.35, -0.04, 0.9];
+        let fit = fit_rates(&results_of(coefficients, 94.0), 94.0).unwrap();
+        for (found, expected) in fit.coefficients.iter().zip(coefficients) {
+            assert!(
+                (found - expected).abs() < 1e-6,
+                "{:?} vs {:?}",
+                fit.coefficients,
+                coefficients
+            );
+        }
+        assert!(fit.max_deviation < 1e-9, "{}", fit.max_deviation);
+    }
+
+    #[test]
+    fn fit_needs_enough_positive_rates() {
+        let mut results = results_of([14.5, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0], 94.0);
+        assert!(fit_rates(&results[..7], 94.0).is_err());
+        results[3].macs_mb = 0.0;
+        assert!(fit_rates(&results, 94.0).is_err());
+    }
+
+    #[test]
+    fn entry_follows_the_reaclib_layout() {
+        // Free neutron decay as distributed in REACLIB, up to the set label,
+        // flags and Q value, which are ours
+        let reference = [
+            "1",
+            "         n    p                            wc12w     7.82300e-01",
+            "-6.781610e+00 0.000000e+00 0.000000e+00 0.000000e+00",
+            " 0.000000e+00 0.000000e+00 0.000000e+00",
+        ];
+        let fit = ReaclibFit {
+            coefficients: [-6.78161, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+            max_deviation: 0.0,
+        };
+        let mut out = Vec::new();
+        write_entry(&mut out, 1, &["n".to_string(), "p".to_string()], &fit).unwrap();
+        let written = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = written.lines().collect();
+        assert_eq!(lines.len(), 4, "{}", written);
+        assert_eq!(lines[0], reference[0]);
+        // 5x, 6a5, 8x before the label
+        assert_eq!(lines[1][..43], reference[1][..43]);
+        assert_eq!(&lines[1][43..], "macsn     0.00000e+00");
+        assert_eq!(lines[1].len(), reference[1].len());
+        assert_eq!(lines[2..], reference[2..]);
+    }
+
+    #[test]
+    fn capture_and_emission_have_their_chapters() {
+        let (chapter, nuclides) = reaction_nuclides("Mo-94", "n,g").unwrap();
+        assert_eq!(
+            (chapter, nuclides),
+            (4, vec!["n".into(), "mo94".into(), "mo95".into()])
+        );
+        let (chapter, nuclides) = reaction_nuclides("N-14", "n,p").unwrap();
+        assert_eq!(
+            (chapter, nuclides),
+            (5, vec!["n".into(), "n14".into(), "p".into(), "c14".into()])
+        );
+        let (chapter, nuclides) = reaction_nuclides("O-17", "n,a").unwrap();
+        assert_eq!(
+            (chapter, nuclides),
+            (
+                5,
+                vec!["n".into(), "o17".into(), "he4".into(), "c14".into()]
+            )
+        );
+        assert!(reaction_nuclides("Mo-nat", "n,g").is_err());
+        assert!(reaction_nuclides("Mo-94", "p,g").is_err());
+        assert!(reaction_nuclides("Mo-94", "n,2n").is_err());
+    }
+}
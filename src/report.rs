@@ -0,0 +1,102 @@
+//! Status reporting on stderr
+//!
+//! Status lines, warnings and the progress bar all go to stderr so that the
+//! results written to stdout stay clean. Status lines are hidden under
+//! `--quiet`; warnings are always shown, and counted so that `--strict` can
+//! refuse to write results once any was raised.
+
+use indicatif::{ProgressBar, ProgressStyle};
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+static STRICT: AtomicBool = AtomicBool::new(false);
+static WARNINGS: AtomicUsize = AtomicUsize::new(0);
+static PROGRESS: OnceLock<ProgressBar> = OnceLock::new();
+
+/// Sets up reporting for a run
+///
+/// A progress bar of `total` steps is shown unless `quiet` is set or stdout
+/// is not a terminal (e.g. when the results are piped to another program).
+pub fn init(quiet: bool, total: u64) {
+    QUIET.store(quiet, Ordering::SeqCst);
+    let bar = if quiet || !std::io::stdout().is_terminal() {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new(total).with_style(
+            ProgressStyle::with_template("[{bar:30}] {pos}/{len} {msg}")
+                .expect("progress template is valid")
+                .progress_chars("=> "),
+        )
+    };
+    let _ = PROGRESS.set(bar);
+}
+
+/// Hides or shows status lines from now on, e.g. between interactive queries
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::SeqCst);
+}
+
+/// Returns the progress bar, hidden if reporting was not initialized
+fn progress() -> &'static ProgressBar {
+    PROGRESS.get_or_init(ProgressBar::hidden)
+}
+
+/// Prints a status line unless running quietly
+pub fn status(message: impl std::fmt::Display) {
+    if !QUIET.load(Ordering::SeqCst) {
+        progress().suspend(|| eprintln!("{}", message));
+    }
+}
+
+/// Prints a warning
+pub fn warning(message: impl std::fmt::Display) {
+    WARNINGS.fetch_add(1, Ordering::SeqCst);
+    progress().suspend(|| eprintln!("Warning: {}", message));
+}
+
+/// Prints an error that does not end the run, e.g. a bad line typed at the
+/// --interactive prompt
+pub fn error(message: impl std::fmt::Display) {
+    progress().suspend(|| eprintln!("Error: {}", message));
+}
+
+/// Makes [`check_strict`] fail once any warning has been raised
+pub fn set_strict(strict: bool) {
+    STRICT.store(strict, Ordering::SeqCst);
+}
+
+/// Checks that no warning was raised in strict mode
+///
+/// Called before results are written, so that under `--strict` dubious
+/// values never reach the output.
+///
+/// # Returns
+/// * `Ok(())` - If strict mode is off or no warning was raised
+/// * `Err(msg)` - Giving the number of warnings otherwise
+pub fn check_strict() -> Result<(), String> {
+    let warnings = WARNINGS.load(Ordering::SeqCst);
+    if STRICT.load(Ordering::SeqCst) && warnings > 0 {
+        return Err(format!(
+            "{} warning(s) raised with --strict, no results written",
+            warnings
+        ));
+    }
+    Ok(())
+}
+
+/// Shows what the run is currently working on next to the progress bar
+pub fn set_step(message: impl Into<std::borrow::Cow<'static, str>>) {
+    progress().set_message(message);
+}
+
+/// Advances the progress bar by `steps`
+pub fn advance(steps: u64) {
+    progress().inc(steps);
+}
+
+/// Removes the progress bar
+pub fn finish() {
+    progress().finish_and_clear();
+}
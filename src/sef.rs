@@ -0,0 +1,165 @@
+//! Stellar enhancement factors
+//!
+//! In a star the target is partly in thermally excited states, which capture
+//! neutrons differently from the ground state measured in the laboratory.
+//! The stellar enhancement factor SEF(kT) = MACS*/MACS_lab, tabulated by
+//! statistical-model codes, converts one into the other.
+
+use crate::columns;
+use crate::interpolation::{LinLin, interpolate_at};
+use crate::macs::MacsResult;
+use std::path::Path;
+
+/// Stellar enhancement factor as a function of temperature
+#[derive(Debug, Clone)]
+pub struct SefTable {
+    /// Temperatures (kT) in keV, increasing
+    temperatures: Vec<f64>,
+    /// Enhancement factors MACS*/MACS_lab
+    factors: Vec<f64>,
+}
+
+impl SefTable {
+    /// Reads an SEF table
+    ///
+    /// Each non-empty line holds a temperature (kT) in keV and a factor,
+    /// separated by whitespace or a comma. Lines starting with `#` are comments.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let (temperatures, factors) = columns::read_two_columns(path, "SEF table", "SEF")?;
+        if let Some(factor) = factors.iter().find(|&&f| f <= 0.0 || !f.is_finite()) {
+            return Err(format!(
+                "The SEF table {} holds the non-positive factor {}",
+                path.display(),
+                factor
+            )
+            .into());
+        }
+        Ok(SefTable {
+            temperatures,
+            factors,
+        })
+    }
+
+    /// Enhancement factor at a temperature, interpolated linearly
+    ///
+    /// # Returns
+    /// `None` if the temperature lies outside the table
+    pub fn factor_at(&self, temperature_kev: f64) -> Option<f64> {
+        interpolate_at(&self.temperatures, &self.factors, &LinLin, temperature_kev)
+    }
+
+    /// Adds the stellar MACS and its ratio to the laboratory MACS to results
+    ///
+    /// The laboratory `macs_mb` of each result is kept; `stellar_macs_mb`
+    /// becomes `macs_mb` times the factor at its temperature, and
+    /// `stellar_enhancement` the factor itself.
+    ///
+    /// # Returns
+    /// * `Ok(())` - If every temperature is covered by the table
+    /// * `Err(msg)` - Naming the first temperature outside the table
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use macs_rs::{macs::MacsTable, sef::SefTable};
+    /// # use std::path::Path;
+    /// # fn example(table: &mut MacsTable) -> Result<(), Box<dyn std::error::Error>> {
+    /// let sef = SefTable::load(Path::new("mo94_sef.txt"))?;
+    /// sef.apply(&mut table.results)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn apply(&self, results: &mut [MacsResult]) -> Result<(), String> {
+        for result in results {
+            let factor = self.factor_at(result.temperature_kev).ok_or_else(|| {
+                format!(
+                    "{} keV lies outside the SEF table ({} - {} keV)",
+                    result.temperature_kev,
+                    self.temperatures[0],
+                    self.temperatures[self.temperatures.len() - 1]
+                )
+            })?;
+            result.stellar_macs_mb = Some(result.macs_mb * factor);
+            result.stellar_enhancement = Some(factor);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpolation::LogLog;
+    use crate::macs::{IntegrationMethod, MaxwellianWeighting, calculate_macs};
+
+    /// Unity up to 10 keV, then rising linearly to 1.5 at 100 keV
+    fn rising_sef() -> SefTable {
+        static COUNT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "macs-rs-sef-{}-{}.txt",
+            std::process::id(),
+            COUNT.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+        ));
+        std::fs::write(&path, "# kT SEF\n1 1.0\n10, 1.0\n100 1.5\n").unwrap();
+        let sef = SefTable::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        sef
+    }
+
+    fn results_at(temperatures: &[f64]) -> Vec<MacsResult> {
+        let energies: Vec<f64> = (0..=2000)
+            .map(|i| 1e-9 * (2e9f64).powf(i as f64 / 2000.0))
+            .collect();
+        let cross_sections: Vec<f64> = energies.iter().map(|e| (2.53e-8 / e).sqrt()).collect();
+        temperatures
+            .iter()
+            .map(|&kt| {
+                let macs_mb = calculate_macs(
+                    &energies,
+                    &cross_sections,
+                    94.0,
+                    kt,
+                    MaxwellianWeighting::Flux,
+                    IntegrationMethod::Trapezoid,
+                    &LogLog,
+                )
+                .unwrap();
+                serde_json::from_str(&format!(
+                    r#"{{"temperature_kev": {}, "macs_mb": {}}}"#,
+                    kt, macs_mb
+                ))
+                .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn stellar_macs_is_the_laboratory_macs_times_the_factor() {
+        let sef = rising_sef();
+        let mut results = results_at(&[5.0, 30.0, 100.0]);
+        sef.apply(&mut results).unwrap();
+
+        let ratios: Vec<f64> = results
+            .iter()
+            .map(|r| r.stellar_enhancement.unwrap())
+            .collect();
+        assert_eq!(ratios[0], 1.0);
+        assert!((ratios[1] - (1.0 + 0.5 * 20.0 / 90.0)).abs() < 1e-12);
+        assert_eq!(ratios[2], 1.5);
+        for result in &results {
+            let stellar = result.stellar_macs_mb.unwrap();
+            assert_eq!(
+                stellar,
+                result.macs_mb * result.stellar_enhancement.unwrap()
+            );
+        }
+        assert_eq!(results[0].stellar_macs_mb, Some(results[0].macs_mb));
+        assert!(results[2].stellar_macs_mb.unwrap() > results[2].macs_mb);
+    }
+
+    #[test]
+    fn temperatures_outside_the_table_are_rejected() {
+        let mut results = results_at(&[30.0, 200.0]);
+        let err = rising_sef().apply(&mut results).unwrap_err();
+        assert!(err.starts_with("200 keV lies outside"), "{}", err);
+    }
+}
@@ -0,0 +1,102 @@
+//! Averaging over a tabulated neutron spectrum
+//!
+//! Samples irradiated in a measured spectrum see a cross section averaged
+//! over that spectrum instead of a Maxwellian. The spectrum is read from a
+//! two-column (energy, flux) text file.
+
+use crate::columns;
+use crate::interpolation::{Interpolation, LinLin, interpolate_at};
+use crate::units;
+use std::path::Path;
+
+/// Tabulated neutron spectrum
+#[derive(Debug, Clone)]
+pub struct Spectrum {
+    /// Energies in MeV, increasing
+    energies: Vec<f64>,
+    /// Flux per unit energy, in arbitrary units
+    flux: Vec<f64>,
+}
+
+/// Integrates a function sampled on a grid with the trapezoidal rule
+fn trapezoid(xs: &[f64], ys: &[f64]) -> f64 {
+    xs.windows(2)
+        .zip(ys.windows(2))
+        .map(|(x, y)| 0.5 * (y[0] + y[1]) * (x[1] - x[0]))
+        .sum()
+}
+
+impl Spectrum {
+    /// Reads a spectrum file
+    ///
+    /// Each non-empty line holds an energy in MeV and a flux, separated by
+    /// whitespace or a comma. Lines starting with `#` are comments.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let (energies, flux) = columns::read_two_columns(path, "spectrum", "flux")?;
+        Ok(Spectrum { energies, flux })
+    }
+
+    /// Computes the spectrum-averaged cross section
+    ///
+    /// Both tabulations are resampled onto the union of their grids over the
+    /// energy range they share; the cross section is interpolated with its own
+    /// law and the spectrum linearly. The average is
+    /// ∫σ(E)φ(E)dE / ∫φ(E)dE over that range.
+    ///
+    /// # Arguments
+    /// * `energies` - Cross section energy points in MeV
+    /// * `cross_sections` - Cross section values in barns
+    /// * `law` - Interpolation law of the cross section
+    ///
+    /// # Returns
+    /// * `Ok((average, coverage))` - Average in millibarns and the fraction of
+    ///   the spectrum's flux lying inside the shared range
+    /// * `Err(msg)` - If the grids do not overlap or the flux there is zero
+    pub fn average_cross_section(
+        &self,
+        energies: &[f64],
+        cross_sections: &[f64],
+        law: &dyn Interpolation,
+    ) -> Result<(f64, f64), String> {
+        let (Some(&cs_first), Some(&cs_last)) = (energies.first(), energies.last()) else {
+            return Err("Cross section has no points".to_string());
+        };
+        let low = cs_first.max(self.energies[0]);
+        let high = cs_last.min(self.energies[self.energies.len() - 1]);
+        if low >= high {
+            return Err("The spectrum and the cross section do not overlap in energy".to_string());
+        }
+
+        let mut grid: Vec<f64> = energies
+            .iter()
+            .chain(&self.energies)
+            .copied()
+            .filter(|&e| e >= low && e <= high)
+            .collect();
+        grid.sort_by(f64::total_cmp);
+        grid.dedup();
+
+        let mut flux = Vec::with_capacity(grid.len());
+        let mut product = Vec::with_capacity(grid.len());
+        for &e in &grid {
+            let phi = interpolate_at(&self.energies, &self.flux, &LinLin, e).unwrap_or(0.0);
+            let sigma = interpolate_at(energies, cross_sections, law, e).unwrap_or(0.0);
+            flux.push(phi);
+            product.push(sigma * phi);
+        }
+
+        let flux_integral = trapezoid(&grid, &flux);
+        if flux_integral <= 0.0 {
+            return Err(
+                "The spectrum has no flux where the cross section is tabulated".to_string(),
+            );
+        }
+        let total_flux = trapezoid(&self.energies, &self.flux);
+        let coverage = flux_integral / total_flux;
+
+        Ok((
+            units::barns_to_millibarns(trapezoid(&grid, &product) / flux_integral),
+            coverage,
+        ))
+    }
+}
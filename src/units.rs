@@ -0,0 +1,339 @@
+//! Unit conversions
+//!
+//! EXFOR tabulates energies in eV and cross sections in barns, while the
+//! MACS integral works in MeV and results are quoted in millibarns with
+//! temperatures (kT) in keV. All conversions between these units go through
+//! the functions below instead of bare factors. They work in any float
+//! precision.
+
+use num_traits::Float;
+
+/// Boltzmann constant in MeV/K
+pub const BOLTZMANN_MEV_PER_K: f64 = 8.617e-11;
+
+/// Avogadro constant in 1/mol
+pub const AVOGADRO_PER_MOL: f64 = 6.02214076e23;
+
+/// Atomic mass unit in MeV/c²
+pub const AMU_MEV: f64 = 931.49410242;
+
+/// Speed of light in cm/s
+pub const SPEED_OF_LIGHT_CM_PER_S: f64 = 2.99792458e10;
+
+/// Converts an `f64` constant to the float type `T`
+pub fn float<T: Float>(value: f64) -> T {
+    T::from(value).expect("f64 constants are representable in any float type")
+}
+
+/// Converts an energy from eV to MeV
+pub fn ev_to_mev<T: Float>(energy_ev: T) -> T {
+    energy_ev * float(1e-6)
+}
+
+/// Converts an energy from keV to MeV
+pub fn kev_to_mev<T: Float>(energy_kev: T) -> T {
+    energy_kev * float(1e-3)
+}
+
+/// Converts an energy from MeV to keV
+pub fn mev_to_kev<T: Float>(energy_mev: T) -> T {
+    energy_mev * float(1e3)
+}
+
+/// Converts an energy from eV to keV
+pub fn ev_to_kev<T: Float>(energy_ev: T) -> T {
+    energy_ev * float(1e-3)
+}
+
+/// Converts a cross section from barns to millibarns
+pub fn barns_to_millibarns<T: Float>(cross_section_b: T) -> T {
+    cross_section_b * float(1e3)
+}
+
+/// Converts a cross section from millibarns to barns
+pub fn millibarns_to_barns<T: Float>(cross_section_mb: T) -> T {
+    cross_section_mb * float(1e-3)
+}
+
+/// Converts a cross section from microbarns to barns
+pub fn microbarns_to_barns<T: Float>(cross_section_ub: T) -> T {
+    cross_section_ub * float(1e-6)
+}
+
+/// Converts a cross section from millibarns to cm²
+pub fn millibarns_to_cm2<T: Float>(cross_section_mb: T) -> T {
+    cross_section_mb * float(1e-27)
+}
+
+/// Converts a thermal energy kT in keV to the temperature in Kelvin
+pub fn kev_to_kelvin<T: Float>(temperature_kev: T) -> T {
+    kev_to_mev(temperature_kev) / float(BOLTZMANN_MEV_PER_K)
+}
+
+/// Converts a temperature in Kelvin to the thermal energy kT in MeV
+pub fn kelvin_to_mev<T: Float>(temperature_k: T) -> T {
+    float::<T>(BOLTZMANN_MEV_PER_K) * temperature_k
+}
+
+/// Converts a temperature in Kelvin to the thermal energy kT in keV
+pub fn kelvin_to_kev<T: Float>(temperature_k: T) -> T {
+    mev_to_kev(kelvin_to_mev(temperature_k))
+}
+
+/// Converts a temperature in GK (10⁹ K) to the thermal energy kT in keV
+pub fn gk_to_kev<T: Float>(temperature_gk: T) -> T {
+    kelvin_to_kev(temperature_gk * float(1e9))
+}
+
+/// Converts a thermal energy kT in keV to the temperature in GK (10⁹ K)
+pub fn kev_to_gk<T: Float>(temperature_kev: T) -> T {
+    kev_to_kelvin(temperature_kev) * float(1e-9)
+}
+
+/// Splits a column header such as "E(eV)" into its name and declared unit
+///
+/// # Returns
+/// * `(name, Some(unit))` - If the header ends with a unit in parentheses
+/// * `(header, None)` - Otherwise
+pub fn split_column_header(header: &str) -> (&str, Option<&str>) {
+    match header
+        .trim()
+        .strip_suffix(')')
+        .and_then(|h| h.split_once('('))
+    {
+        Some((name, unit)) => (name.trim(), Some(unit.trim())),
+        None => (header.trim(), None),
+    }
+}
+
+/// Formats a number in scientific notation with a signed two-digit exponent
+///
+/// Unlike `{:e}`, which writes "1.234e2" and "1.234e-12", every value with
+/// the same `precision` has the same width (e.g. "1.234e+02", "1.234e-12"),
+/// so columns of them line up.
+pub fn format_exponent(value: f64, precision: usize) -> String {
+    let formatted = format!("{:.*e}", precision, value);
+    match formatted.split_once('e') {
+        Some((mantissa, exponent)) => match exponent.parse::<i32>() {
+            Ok(exponent) => format!("{}e{:+03}", mantissa, exponent),
+            Err(_) => formatted,
+        },
+        None => formatted,
+    }
+}
+
+/// Unit of a tabulated cross section
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CrossSectionUnit {
+    /// Barns, as tabulated by EXFOR
+    #[default]
+    Barn,
+    /// Millibarns
+    Millibarn,
+    /// Microbarns
+    Microbarn,
+}
+
+impl CrossSectionUnit {
+    /// Reads a unit symbol such as "b", "mb" or "barns", ignoring case
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        match symbol.to_ascii_lowercase().as_str() {
+            "b" | "barn" | "barns" => Some(CrossSectionUnit::Barn),
+            "mb" | "millibarn" | "millibarns" => Some(CrossSectionUnit::Millibarn),
+            "ub" | "µb" | "microbarn" | "microbarns" => Some(CrossSectionUnit::Microbarn),
+            _ => None,
+        }
+    }
+
+    /// Converts a cross section in this unit to barns
+    pub fn to_barns(self, cross_section: f64) -> f64 {
+        match self {
+            CrossSectionUnit::Barn => cross_section,
+            CrossSectionUnit::Millibarn => millibarns_to_barns(cross_section),
+            CrossSectionUnit::Microbarn => microbarns_to_barns(cross_section),
+        }
+    }
+}
+
+/// Unit of a temperature given on the command line
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum TemperatureUnit {
+    /// Thermal energy kT in keV
+    #[default]
+    Kev,
+    /// Thermal energy kT in eV
+    Ev,
+    /// Temperature in Kelvin
+    K,
+    /// Temperature in GK (10⁹ K), as used by reaction networks
+    Gk,
+}
+
+impl TemperatureUnit {
+    /// Suffixes recognized after a number, longest first so that "keV" is
+    /// not read as "eV"
+    const SUFFIXES: [(&'static str, TemperatureUnit); 4] = [
+        ("kev", TemperatureUnit::Kev),
+        ("gk", TemperatureUnit::Gk),
+        ("ev", TemperatureUnit::Ev),
+        ("k", TemperatureUnit::K),
+    ];
+
+    /// Converts a temperature in this unit to the thermal energy kT in keV
+    pub fn to_kev(self, value: f64) -> f64 {
+        match self {
+            TemperatureUnit::Kev => value,
+            TemperatureUnit::Ev => ev_to_kev(value),
+            TemperatureUnit::K => kelvin_to_kev(value),
+            TemperatureUnit::Gk => gk_to_kev(value),
+        }
+    }
+}
+
+/// Temperature as typed on the command line, with its unit if one was given
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Temperature {
+    /// Numeric value
+    pub value: f64,
+    /// Unit suffix, or `None` for a bare number
+    pub unit: Option<TemperatureUnit>,
+}
+
+impl Temperature {
+    /// Converts to kT in keV, reading a bare number in `default_unit`
+    pub fn to_kev(self, default_unit: TemperatureUnit) -> f64 {
+        self.unit.unwrap_or(default_unit).to_kev(self.value)
+    }
+}
+
+impl std::str::FromStr for Temperature {
+    type Err = String;
+
+    /// Parses a number with an optional unit suffix (keV, eV, K or GK, in any
+    /// case), e.g. "30", "30keV" or "0.35 GK"
+    fn from_str(token: &str) -> Result<Self, String> {
+        let trimmed = token.trim();
+        let lower = trimmed.to_ascii_lowercase();
+        let (number, unit) = match TemperatureUnit::SUFFIXES
+            .iter()
+            .find(|(suffix, _)| lower.ends_with(suffix))
+        {
+            Some(&(suffix, unit)) => (&trimmed[..trimmed.len() - suffix.len()], Some(unit)),
+            None => (trimmed, None),
+        };
+        let value: f64 = number.trim().parse().map_err(|_| {
+            format!(
+                "cannot read temperature '{}', expected a number optionally followed by keV, eV, K or GK",
+                token
+            )
+        })?;
+        Ok(Temperature { value, unit })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() <= 1e-12 * expected.abs(),
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn energy_conversions_round_trip() {
+        for energy in [2.53e-8, 1.0, 30.0, 2e7] {
+            assert_close(mev_to_kev(kev_to_mev(energy)), energy);
+            assert_close(kev_to_mev(ev_to_kev(energy)), ev_to_mev(energy));
+        }
+        assert_close(ev_to_mev(1e6), 1.0);
+        assert_close(ev_to_kev(1e3), 1.0);
+    }
+
+    #[test]
+    fn cross_section_conversions_round_trip() {
+        for cross_section in [1e-6, 0.35, 1e4] {
+            assert_close(
+                millibarns_to_barns(barns_to_millibarns(cross_section)),
+                cross_section,
+            );
+            assert_close(
+                barns_to_millibarns(microbarns_to_barns(cross_section)),
+                cross_section * 1e-3,
+            );
+        }
+        assert_close(CrossSectionUnit::Millibarn.to_barns(350.0), 0.35);
+        assert_close(CrossSectionUnit::Microbarn.to_barns(350.0), 3.5e-4);
+        assert_close(millibarns_to_cm2(1.0), 1e-27);
+        assert_close(
+            millibarns_to_cm2(barns_to_millibarns(1.0)),
+            barns_to_millibarns(1e-27),
+        );
+    }
+
+    #[test]
+    fn temperature_conversions_round_trip() {
+        for kt in [1e-3, 8.0, 30.0, 1e3] {
+            assert_close(kelvin_to_kev(kev_to_kelvin(kt)), kt);
+            assert_close(gk_to_kev(kev_to_gk(kt)), kt);
+            assert_close(kev_to_kelvin(mev_to_kev(kelvin_to_mev(kt))), kt);
+        }
+        // kT = 30 keV is about 0.35 GK, kT = 1 keV about 11.6 MK
+        assert!((kev_to_gk(30.0) - 0.348).abs() < 1e-3);
+        assert!((kev_to_kelvin(1.0) - 1.16e7).abs() < 1e5);
+    }
+
+    #[test]
+    fn conversions_work_in_f32() {
+        assert!((kelvin_to_kev(kev_to_kelvin(30.0f32)) - 30.0).abs() < 1e-4);
+        assert_eq!(barns_to_millibarns(2.0f32), 2000.0);
+    }
+
+    fn temperature(token: &str) -> Temperature {
+        token.parse().unwrap()
+    }
+
+    #[test]
+    fn temperatures_read_their_unit_suffix() {
+        assert_eq!(
+            temperature("30keV"),
+            Temperature {
+                value: 30.0,
+                unit: Some(TemperatureUnit::Kev)
+            }
+        );
+        assert_eq!(temperature(" 0.35 GK").unit, Some(TemperatureUnit::Gk));
+        assert_eq!(temperature("2500EV").unit, Some(TemperatureUnit::Ev));
+        assert_eq!(temperature("3.5e8K").unit, Some(TemperatureUnit::K));
+
+        // Each value of a mixed list converts with its own unit
+        let mixed: Vec<f64> = ["30keV", "30000eV", "1GK", "1e9K"]
+            .iter()
+            .map(|t| temperature(t).to_kev(TemperatureUnit::K))
+            .collect();
+        assert_close(mixed[1], mixed[0]);
+        assert_close(mixed[2], gk_to_kev(1.0));
+        assert_close(mixed[3], mixed[2]);
+    }
+
+    #[test]
+    fn bare_temperatures_use_the_default_unit() {
+        let bare = temperature("30");
+        assert_eq!(bare.unit, None);
+        assert_eq!(bare.to_kev(TemperatureUnit::Kev), 30.0);
+        assert_close(bare.to_kev(TemperatureUnit::Ev), 0.03);
+        assert_close(bare.to_kev(TemperatureUnit::Gk), gk_to_kev(30.0));
+    }
+
+    #[test]
+    fn unreadable_temperatures_name_the_token() {
+        for token in ["", "keV", "30 MeV", "thirty", "30keVK"] {
+            let err = token.parse::<Temperature>().unwrap_err();
+            assert!(err.contains(&format!("'{}'", token)), "{}", err);
+        }
+    }
+}